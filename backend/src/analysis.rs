@@ -0,0 +1,494 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::f32::consts::PI;
+use std::path::{Path, PathBuf};
+
+use crate::db;
+use crate::metadata::{AudioFormat, SampleMetaData};
+use crate::output;
+use crate::wav;
+
+/// Only the start of long files is analyzed; samples rarely run longer than this
+const MAX_SECONDS: u64 = 60;
+/// Audio is decimated to about this rate first: enough for onsets and pitches up to 2 kHz
+const ANALYSIS_RATE: u32 = 11025;
+/// FFT size and hop of the onset envelope, about 93 ms and 12 ms at the analysis rate
+const ONSET_FRAME: usize = 1024;
+const ONSET_HOP: usize = 128;
+/// FFT size and hop of the chroma, long enough to tell semitones apart from about 80 Hz
+const CHROMA_FRAME: usize = 4096;
+const CHROMA_HOP: usize = 2048;
+/// Tempo search range
+const MIN_BPM: f64 = 60.0;
+const MAX_BPM: f64 = 200.0;
+/// Tempi are weighted by their distance from this one in octaves, to settle
+/// half/double-time ambiguity the way a listener would
+const PRIOR_BPM: f64 = 120.0;
+/// Audio shorter than this gets no tempo estimate
+const MIN_TEMPO_SECONDS: f64 = 2.0;
+/// Range of frequencies folded into the chroma
+const MIN_CHROMA_HZ: f64 = 55.0;
+const MAX_CHROMA_HZ: f64 = 2000.0;
+
+/// Krumhansl-Kessler key profiles, starting from the tonic
+const MAJOR_PROFILE: [f64; 12] = [6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88];
+const MINOR_PROFILE: [f64; 12] = [6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17];
+
+/// Pitch classes from C, spelled like Splice's `audio_key`
+const PITCH_CLASSES: [&str; 12] = ["c", "c#", "d", "d#", "e", "f", "f#", "g", "g#", "a", "a#", "b"];
+
+/// Tempo and key estimated from the audio, each with a confidence from 0.0 to 1.0
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Analysis {
+    pub bpm: Option<f64>,
+    pub bpm_confidence: Option<f64>,
+    /// Root note, lowercase with sharps like `audio_key`
+    pub key: Option<String>,
+    /// `major` or `minor`
+    pub chord_type: Option<String>,
+    pub key_confidence: Option<f64>,
+}
+
+impl Analysis {
+    /// The detected BPM, rounded, if it is at least `min_confidence` sure
+    pub fn confident_bpm(&self, min_confidence: f64) -> Option<u32> {
+        let bpm = self.bpm?;
+        (self.bpm_confidence? >= min_confidence).then(|| bpm.round() as u32)
+    }
+    
+    /// The detected key and chord type, if they are at least `min_confidence` sure
+    pub fn confident_key(&self, min_confidence: f64) -> Option<(String, String)> {
+        if self.key_confidence? < min_confidence {
+            return None;
+        }
+        Some((self.key.clone()?, self.chord_type.clone()?))
+    }
+    
+    /// Short summary such as `128.0 BPM (82%), A minor (64%)`
+    pub fn describe(&self) -> String {
+        let percent = |confidence: Option<f64>| confidence.unwrap_or(0.0) * 100.0;
+        let tempo = match self.bpm {
+            Some(bpm) => format!("{:.1} BPM ({:.0}%)", bpm, percent(self.bpm_confidence)),
+            None => "no tempo".to_string(),
+        };
+        let key = match (&self.key, &self.chord_type) {
+            (Some(key), Some(chord_type)) => format!("{} {} ({:.0}%)", key.to_uppercase(), chord_type, percent(self.key_confidence)),
+            _ => "no key".to_string(),
+        };
+        format!("{}, {}", tempo, key)
+    }
+    
+    /// Fill in the BPM and key Splice left empty with detections that are at least
+    /// `min_confidence` sure, so layouts and rules can use them
+    pub fn fill_missing(&self, sample: &mut SampleMetaData, min_confidence: f64) {
+        if sample.bpm.unwrap_or(0) == 0 {
+            if let Some(bpm) = self.confident_bpm(min_confidence) {
+                sample.bpm = Some(bpm);
+            }
+        }
+        if sample.audio_key.as_deref().is_none_or(|key| key.trim().is_empty()) {
+            if let Some((key, chord_type)) = self.confident_key(min_confidence) {
+                sample.audio_key = Some(key);
+                sample.chord_type = Some(chord_type);
+            }
+        }
+    }
+}
+
+/// Estimate the tempo and key of a PCM or float WAV
+pub fn analyze_file(path: &Path) -> Result<Analysis> {
+    let (info, samples) = wav::read_mono(path, MAX_SECONDS)?;
+    let (samples, rate) = decimate(&samples, info.sample_rate);
+    
+    let mut analysis = Analysis::default();
+    if let Some((bpm, confidence)) = estimate_tempo(&samples, rate, info.duration_secs()) {
+        analysis.bpm = Some(bpm);
+        analysis.bpm_confidence = Some(confidence);
+    }
+    if let Some((root, minor, confidence)) = estimate_key(&samples, rate) {
+        analysis.key = Some(PITCH_CLASSES[root].to_string());
+        analysis.chord_type = Some(if minor { "minor" } else { "major" }.to_string());
+        analysis.key_confidence = Some(confidence);
+    }
+    Ok(analysis)
+}
+
+/// Average blocks of samples down to about `ANALYSIS_RATE`, returning the new rate
+fn decimate(samples: &[f32], sample_rate: u32) -> (Vec<f32>, f64) {
+    let factor = (sample_rate as f64 / ANALYSIS_RATE as f64).round().max(1.0) as usize;
+    let decimated = samples.chunks(factor)
+        .map(|block| block.iter().sum::<f32>() / block.len() as f32)
+        .collect();
+    (decimated, sample_rate as f64 / factor as f64)
+}
+
+/// In-place iterative radix-2 FFT; the length must be a power of two
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+    
+    let mut len = 2;
+    while len <= n {
+        let (w_im, w_re) = (-2.0 * PI / len as f32).sin_cos();
+        for start in (0..n).step_by(len) {
+            let (mut cur_re, mut cur_im) = (1.0f32, 0.0f32);
+            for k in 0..len / 2 {
+                let a = start + k;
+                let b = a + len / 2;
+                let t_re = re[b] * cur_re - im[b] * cur_im;
+                let t_im = re[b] * cur_im + im[b] * cur_re;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+                let next_re = cur_re * w_re - cur_im * w_im;
+                cur_im = cur_re * w_im + cur_im * w_re;
+                cur_re = next_re;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+/// Magnitude spectra (`frame / 2 + 1` bins) of Hann-windowed frames. Audio shorter
+/// than one frame is zero-padded to fill it.
+fn spectrogram(samples: &[f32], frame: usize, hop: usize) -> Vec<Vec<f32>> {
+    let window: Vec<f32> = (0..frame)
+        .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / frame as f32).cos())
+        .collect();
+    let mut re = vec![0.0; frame];
+    let mut im = vec![0.0; frame];
+    let mut spectra = Vec::new();
+    
+    let mut start = 0;
+    loop {
+        for i in 0..frame {
+            re[i] = samples.get(start + i).copied().unwrap_or(0.0) * window[i];
+            im[i] = 0.0;
+        }
+        fft(&mut re, &mut im);
+        spectra.push((0..=frame / 2).map(|k| (re[k] * re[k] + im[k] * im[k]).sqrt()).collect());
+        
+        start += hop;
+        if start + frame > samples.len() {
+            break;
+        }
+    }
+    spectra
+}
+
+/// Tempo from the autocorrelation of a spectral-flux onset envelope, with its
+/// confidence: how strongly the envelope repeats at that period
+fn estimate_tempo(samples: &[f32], rate: f64, duration: f64) -> Option<(f64, f64)> {
+    if duration < MIN_TEMPO_SECONDS {
+        return None;
+    }
+    
+    // Rises in log-compressed magnitude, summed over the spectrum
+    let spectra = spectrogram(samples, ONSET_FRAME, ONSET_HOP);
+    let log_spectra: Vec<Vec<f32>> = spectra.iter()
+        .map(|spectrum| spectrum.iter().map(|m| (1.0 + 100.0 * m).ln()).collect())
+        .collect();
+    let flux: Vec<f64> = log_spectra.iter().enumerate()
+        .map(|(i, spectrum)| match i.checked_sub(1) {
+            Some(previous) => spectrum.iter().zip(&log_spectra[previous])
+                .map(|(now, before)| (now - before).max(0.0) as f64)
+                .sum(),
+            None => 0.0,
+        })
+        .collect();
+    
+    // Keep only what stands out from the surrounding quarter second
+    let fps = rate / ONSET_HOP as f64;
+    let half_window = (fps * 0.125) as usize;
+    let envelope: Vec<f64> = (0..flux.len())
+        .map(|i| {
+            let from = i.saturating_sub(half_window);
+            let to = (i + half_window + 1).min(flux.len());
+            let mean = flux[from..to].iter().sum::<f64>() / (to - from) as f64;
+            (flux[i] - mean).max(0.0)
+        })
+        .collect();
+    
+    let energy: f64 = envelope.iter().map(|v| v * v).sum();
+    let min_lag = (60.0 * fps / MAX_BPM).floor() as usize;
+    let max_lag = ((60.0 * fps / MIN_BPM).ceil() as usize).min(envelope.len() / 2);
+    if energy <= f64::EPSILON || min_lag < 2 || max_lag <= min_lag + 1 {
+        return None;
+    }
+    
+    // Normalized so a perfectly periodic envelope scores 1.0 at its period
+    let n = envelope.len() as f64;
+    let acf: Vec<f64> = (0..=max_lag + 1)
+        .map(|lag| {
+            let sum: f64 = envelope.iter().zip(&envelope[lag..]).map(|(a, b)| a * b).sum();
+            sum / energy * n / (n - lag as f64)
+        })
+        .collect();
+    
+    let weight = |lag: f64| {
+        let octaves = (60.0 * fps / lag / PRIOR_BPM).log2();
+        (-0.5 * octaves * octaves).exp()
+    };
+    let best = (min_lag..=max_lag)
+        .filter(|&lag| acf[lag] >= acf[lag - 1] && acf[lag] >= acf[lag + 1])
+        .max_by(|&a, &b| (acf[a] * weight(a as f64)).total_cmp(&(acf[b] * weight(b as f64))))?;
+    
+    // Parabolic interpolation between the neighbouring lags
+    let (before, peak, after) = (acf[best - 1], acf[best], acf[best + 1]);
+    let curvature = before - 2.0 * peak + after;
+    let offset = if curvature.abs() > f64::EPSILON { 0.5 * (before - after) / curvature } else { 0.0 };
+    let mut bpm = 60.0 * fps / (best as f64 + offset.clamp(-0.5, 0.5));
+    
+    // Loops are cut to whole beats at a whole-number tempo: when a nearby tempo makes
+    // the length exactly that, it is more precise than the autocorrelation peak
+    let whole_beats = (duration * bpm / 60.0).round();
+    if whole_beats >= 4.0 {
+        let snapped = whole_beats * 60.0 / duration;
+        if (snapped - bpm).abs() / bpm < 0.02 && (snapped - snapped.round()).abs() < 0.05 {
+            bpm = snapped.round();
+        }
+    }
+    
+    Some(((bpm * 10.0).round() / 10.0, peak.clamp(0.0, 1.0)))
+}
+
+fn pearson(a: &[f64; 12], b: &[f64; 12]) -> f64 {
+    let mean_a = a.iter().sum::<f64>() / 12.0;
+    let mean_b = b.iter().sum::<f64>() / 12.0;
+    let mut covariance = 0.0;
+    let mut variance_a = 0.0;
+    let mut variance_b = 0.0;
+    for (x, y) in a.iter().zip(b) {
+        covariance += (x - mean_a) * (y - mean_b);
+        variance_a += (x - mean_a) * (x - mean_a);
+        variance_b += (y - mean_b) * (y - mean_b);
+    }
+    let denominator = (variance_a * variance_b).sqrt();
+    if denominator <= f64::EPSILON { 0.0 } else { covariance / denominator }
+}
+
+/// Key by correlating the chroma profile with the 24 rotated major and minor key
+/// profiles. Returns the root pitch class, whether it is minor, and the correlation
+/// as confidence.
+fn estimate_key(samples: &[f32], rate: f64) -> Option<(usize, bool, f64)> {
+    let pitch_classes: Vec<Option<usize>> = (0..=CHROMA_FRAME / 2)
+        .map(|bin| {
+            let hz = bin as f64 * rate / CHROMA_FRAME as f64;
+            (MIN_CHROMA_HZ..=MAX_CHROMA_HZ).contains(&hz)
+                .then(|| ((69.0 + 12.0 * (hz / 440.0).log2()).round() as i64).rem_euclid(12) as usize)
+        })
+        .collect();
+    
+    let mut chroma = [0.0f64; 12];
+    for spectrum in spectrogram(samples, CHROMA_FRAME, CHROMA_HOP) {
+        for (magnitude, pitch_class) in spectrum.iter().zip(&pitch_classes) {
+            if let Some(pitch_class) = pitch_class {
+                chroma[*pitch_class] += *magnitude as f64;
+            }
+        }
+    }
+    if chroma.iter().sum::<f64>() <= f64::EPSILON {
+        return None;
+    }
+    
+    let mut best: Option<(usize, bool, f64)> = None;
+    for root in 0..12 {
+        for (minor, profile) in [(false, &MAJOR_PROFILE), (true, &MINOR_PROFILE)] {
+            let rotated: [f64; 12] = std::array::from_fn(|pitch_class| profile[(pitch_class + 12 - root) % 12]);
+            let correlation = pearson(&chroma, &rotated);
+            if best.is_none_or(|(_, _, best)| correlation > best) {
+                best = Some((root, minor, correlation));
+            }
+        }
+    }
+    best.map(|(root, minor, correlation)| (root, minor, correlation.clamp(0.0, 1.0)))
+}
+
+/// Result of analyzing the library
+#[derive(Debug, Default, Serialize)]
+pub struct AnalyzeSummary {
+    pub analyzed: usize,
+    /// Samples analyzed before, left alone without `--force`
+    pub already_analyzed: usize,
+    /// Samples in formats other than WAV
+    pub skipped: usize,
+    /// Records whose library file no longer exists
+    pub missing: Vec<PathBuf>,
+    #[serde(serialize_with = "output::failures")]
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+impl output::PlainText for AnalyzeSummary {}
+
+/// Analyze every WAV in the library that hasn't been yet, or all of them with `force`,
+/// and store the estimates
+pub fn analyze_library(database_path: &Path, force: bool) -> Result<AnalyzeSummary> {
+    let mut summary = AnalyzeSummary::default();
+    
+    for record in db::get_all_samples(database_path)? {
+        let path = PathBuf::from(&record.file_path);
+        if AudioFormat::from_path(&path) != Some(AudioFormat::Wav) {
+            summary.skipped += 1;
+            continue;
+        }
+        if record.analyzed_at.is_some() && !force {
+            summary.already_analyzed += 1;
+            continue;
+        }
+        if !path.is_file() {
+            summary.missing.push(path);
+            continue;
+        }
+        
+        match analyze_file(&path).and_then(|analysis| {
+            db::store_analysis(database_path, &record.file_hash, &analysis)?;
+            Ok(analysis)
+        }) {
+            Ok(analysis) => {
+                say!("   🎼 {}: {}", record.filename, analysis.describe());
+                summary.analyzed += 1;
+            }
+            Err(e) => {
+                eprintln!("🚨 Failed to analyze {:?}: {}", path, e);
+                summary.failed.push((path, e.to_string()));
+            }
+        }
+    }
+    
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata;
+    
+    const RATE: u32 = 44100;
+    
+    fn analyze(samples: &[f32]) -> Analysis {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("signal.wav");
+        wav::write_float(&path, 1, RATE, samples).unwrap();
+        analyze_file(&path).unwrap()
+    }
+    
+    /// Short decaying noise bursts on every beat
+    fn click_track(bpm: f64, seconds: f64) -> Vec<f32> {
+        let beat = (60.0 / bpm * RATE as f64) as usize;
+        let click = RATE as usize / 50;
+        let mut noise: u32 = 1;
+        (0..(seconds * RATE as f64) as usize)
+            .map(|i| {
+                let offset = i % beat;
+                if offset >= click {
+                    return 0.0;
+                }
+                noise = noise.wrapping_mul(1664525).wrapping_add(1013904223);
+                let white = (noise >> 8) as f32 / (1 << 23) as f32 - 1.0;
+                white * 0.8 * (1.0 - offset as f32 / click as f32)
+            })
+            .collect()
+    }
+    
+    fn chord(hz: &[f64], seconds: f64) -> Vec<f32> {
+        (0..(seconds * RATE as f64) as usize)
+            .map(|i| {
+                let t = i as f64 / RATE as f64;
+                hz.iter().map(|hz| (2.0 * std::f64::consts::PI * hz * t).sin()).sum::<f64>() as f32 * 0.25
+            })
+            .collect()
+    }
+    
+    #[test]
+    fn detects_the_tempo_of_a_click_track() {
+        for bpm in [90.0, 128.0, 150.0] {
+            let analysis = analyze(&click_track(bpm, 8.0));
+            let detected = analysis.bpm.unwrap();
+            assert!((detected - bpm).abs() < 1.0, "{} BPM detected as {}", bpm, detected);
+            assert!(analysis.bpm_confidence.unwrap() > 0.5);
+        }
+    }
+    
+    #[test]
+    fn snaps_whole_beat_loops_to_a_whole_tempo() {
+        // 16 beats at 125 BPM last exactly 7.68 seconds
+        let analysis = analyze(&click_track(125.0, 7.68));
+        assert_eq!(analysis.bpm, Some(125.0));
+    }
+    
+    #[test]
+    fn skips_the_tempo_of_short_audio() {
+        let analysis = analyze(&click_track(120.0, 1.5));
+        assert_eq!(analysis.bpm, None);
+        assert_eq!(analysis.confident_bpm(0.0), None);
+    }
+    
+    #[test]
+    fn detects_the_key_of_a_chord() {
+        let a_minor = analyze(&chord(&[220.0, 261.63, 329.63], 4.0));
+        assert_eq!(a_minor.confident_key(0.5), Some(("a".to_string(), "minor".to_string())));
+        
+        let d_major = analyze(&chord(&[146.83, 185.0, 220.0], 4.0));
+        assert_eq!(d_major.confident_key(0.5), Some(("d".to_string(), "major".to_string())));
+    }
+    
+    #[test]
+    fn silence_has_no_tempo_or_key() {
+        let analysis = analyze(&vec![0.0; RATE as usize * 4]);
+        assert_eq!(analysis, Analysis::default());
+        assert_eq!(analysis.describe(), "no tempo, no key");
+    }
+    
+    #[test]
+    fn fill_missing_respects_the_confidence_threshold() {
+        let analysis = Analysis {
+            bpm: Some(127.6),
+            bpm_confidence: Some(0.8),
+            key: Some("f#".to_string()),
+            chord_type: Some("minor".to_string()),
+            key_confidence: Some(0.4),
+        };
+        let mut sample = metadata::test_metadata().sample_meta_data;
+        sample.bpm = None;
+        sample.audio_key = Some(" ".to_string());
+        sample.chord_type = None;
+        
+        analysis.fill_missing(&mut sample, 0.5);
+        assert_eq!(sample.bpm, Some(128));
+        assert_eq!(sample.audio_key.as_deref(), Some(" "));
+        assert_eq!(sample.chord_type, None);
+        
+        analysis.fill_missing(&mut sample, 0.4);
+        assert_eq!(sample.audio_key.as_deref(), Some("f#"));
+        assert_eq!(sample.chord_type.as_deref(), Some("minor"));
+    }
+    
+    #[test]
+    fn fill_missing_keeps_declared_values() {
+        let analysis = Analysis {
+            bpm: Some(90.0),
+            bpm_confidence: Some(1.0),
+            key: Some("c".to_string()),
+            chord_type: Some("major".to_string()),
+            key_confidence: Some(1.0),
+        };
+        let mut sample = metadata::test_metadata().sample_meta_data;
+        analysis.fill_missing(&mut sample, 0.0);
+        assert_eq!(sample.bpm, Some(120));
+        assert_eq!(sample.audio_key.as_deref(), Some("a"));
+        assert_eq!(sample.chord_type.as_deref(), Some("minor"));
+    }
+}
@@ -0,0 +1,442 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::integrity::MismatchAction;
+use crate::layout::{self, CollisionPolicy, PathTemplate};
+use crate::metadata::AudioFormat;
+use crate::rules::CategoryRules;
+
+/// Log levels accepted for `[logging] level`
+const LOG_LEVELS: &[&str] = &["trace", "debug", "info", "warn", "error"];
+
+/// Daemon settings, built from defaults, `config.toml`, `SPLICE_ALT_*`
+/// environment variables and command-line flags, later sources winning
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub paths: PathsConfig,
+    pub library: LibraryConfig,
+    pub watcher: WatcherConfig,
+    pub logging: LoggingConfig,
+    /// File the settings were read from, `None` when no file exists
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PathsConfig {
+    /// Directory to watch for new samples
+    pub watch_dir: PathBuf,
+    /// Sample library base directory
+    pub library_dir: PathBuf,
+    /// SQLite database file
+    pub database: PathBuf,
+    /// Where failed or suspicious downloads are moved, instead of `<library_dir>/.quarantine`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quarantine_dir: Option<PathBuf>,
+}
+
+impl Default for PathsConfig {
+    fn default() -> Self {
+        Self {
+            watch_dir: default_watch_dir(),
+            library_dir: default_library_dir(),
+            database: default_database(),
+            quarantine_dir: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LibraryConfig {
+    /// Library path template
+    pub layout: String,
+    /// Category rules file, instead of `~/.config/splice-alt/rules.toml`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rules: Option<PathBuf>,
+    /// What to do when a sample's library path is already taken
+    pub on_collision: CollisionPolicy,
+    /// How sure a BPM or key estimate has to be (0.0 - 1.0) to stand in for a missing
+    /// value in layouts, reorganize and search
+    pub min_detection_confidence: f64,
+}
+
+impl Default for LibraryConfig {
+    fn default() -> Self {
+        Self {
+            layout: layout::DEFAULT_TEMPLATE.to_string(),
+            rules: None,
+            on_collision: CollisionPolicy::default(),
+            min_detection_confidence: 0.6,
+        }
+    }
+}
+
+/// Retry and timing knobs for the file watcher
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WatcherConfig {
+    /// Extensions of the audio files to import; each is paired with the JSON of the same stem
+    pub audio_extensions: Vec<String>,
+    /// How long a file has to go without events, and keep the same size, before it is processed
+    pub debounce_ms: u64,
+    /// Process a download anyway once it has stayed empty or beside a browser temp file
+    /// this long without growing, as a cancelled download does
+    pub settle_timeout_secs: u64,
+    /// How many times an event, hash or database insert is attempted
+    pub retry_attempts: u32,
+    /// Delay before the first retry; event retries back off exponentially from here
+    pub retry_delay_ms: u64,
+    /// How long a new WAV waits for its JSON metadata before being queued as pending
+    pub json_wait_ms: u64,
+    /// Give up parsing a metadata file after this long
+    pub metadata_timeout_secs: u64,
+    /// Pause after every tenth consecutive error
+    pub error_pause_secs: u64,
+    /// How long shutdown waits for the sample pair in progress before giving up on it
+    pub shutdown_timeout_secs: u64,
+    /// What to do with a WAV that doesn't match the size or hash in its metadata
+    pub on_mismatch: MismatchAction,
+    /// Move pairs that still fail after every retry to the quarantine directory
+    pub quarantine_failures: bool,
+    /// Write the Splice metadata into each imported WAV (LIST/INFO, bext and iXML chunks)
+    pub embed_metadata: bool,
+    /// Write `acid` and `smpl` chunks so DAWs pick up the tempo, root note and loop points
+    pub embed_loop_chunks: bool,
+    /// Estimate BPM and key from the audio of each imported WAV
+    pub analyze_on_import: bool,
+    /// Measure the loudness of library samples in the background while the daemon runs
+    pub loudness_job: bool,
+}
+
+impl Default for WatcherConfig {
+    fn default() -> Self {
+        Self {
+            audio_extensions: ["wav", "aif", "aiff", "flac", "mp3", "ogg"].map(String::from).to_vec(),
+            debounce_ms: 1000,
+            settle_timeout_secs: 300,
+            retry_attempts: 3,
+            retry_delay_ms: 1000,
+            json_wait_ms: 5000,
+            metadata_timeout_secs: 10,
+            error_pause_secs: 30,
+            shutdown_timeout_secs: 30,
+            on_mismatch: MismatchAction::default(),
+            quarantine_failures: true,
+            embed_metadata: false,
+            embed_loop_chunks: false,
+            analyze_on_import: false,
+            loudness_job: false,
+        }
+    }
+}
+
+impl WatcherConfig {
+    /// Whether `path` has one of the configured audio extensions, in any case
+    pub fn is_audio_file(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| self.audio_extensions.iter().any(|known| known.eq_ignore_ascii_case(ext)))
+    }
+    
+    /// The audio file sharing `json_path`'s stem, whichever configured extension it has
+    pub fn audio_partner(&self, json_path: &Path) -> Option<PathBuf> {
+        self.audio_extensions
+            .iter()
+            .flat_map(|ext| [json_path.with_extension(ext), json_path.with_extension(ext.to_uppercase())])
+            .find(|candidate| candidate.is_file())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    /// One of trace, debug, info, warn or error; `RUST_LOG` still takes precedence
+    pub level: String,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self { level: "info".to_string() }
+    }
+}
+
+/// Settings given on the command line, which win over everything else
+#[derive(Debug, Clone, Default)]
+pub struct Overrides {
+    pub config_file: Option<PathBuf>,
+    pub watch_dir: Option<PathBuf>,
+    pub library_dir: Option<PathBuf>,
+    pub database: Option<PathBuf>,
+    pub layout: Option<String>,
+}
+
+fn default_database() -> PathBuf {
+    let base_dir = dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."));
+    let db_dir = base_dir.join("splice-alt");
+    
+    // Create parent directory if it doesn't exist
+    if let Err(e) = std::fs::create_dir_all(&db_dir) {
+        tracing::warn!("Failed to create database directory {:?}: {}", db_dir, e);
+    }
+    
+    db_dir.join("samples.db")
+}
+
+fn default_watch_dir() -> PathBuf {
+    dirs::download_dir().unwrap_or_else(|| PathBuf::from("./downloads"))
+}
+
+fn default_library_dir() -> PathBuf {
+    dirs::audio_dir()
+        .unwrap_or_else(|| dirs::home_dir().unwrap_or_else(|| PathBuf::from(".")).join("Music"))
+        .join("Samples")
+        .join("SpliceLib")
+}
+
+/// Expand a leading `~/` so paths in the file and environment can be written like in a shell
+fn expand_home(path: PathBuf) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path,
+    }
+}
+
+/// Read `SPLICE_ALT_<NAME>`, treating an empty value as unset
+fn env_value<T: FromStr>(name: &str) -> Result<Option<T>>
+where
+    T::Err: std::fmt::Display,
+{
+    let var = format!("SPLICE_ALT_{}", name);
+    match std::env::var(&var) {
+        Ok(value) if !value.trim().is_empty() => value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|e| anyhow::anyhow!("Invalid {} '{}': {}", var, value, e)),
+        _ => Ok(None),
+    }
+}
+
+impl Config {
+    /// Default location of the config file (`~/.config/splice-alt/config.toml`)
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("splice-alt").join("config.toml"))
+    }
+    
+    /// The file `load` reads: `--config`, then `SPLICE_ALT_CONFIG`, then the default location
+    pub fn file_path(overrides: &Overrides) -> Result<Option<PathBuf>> {
+        Ok(overrides.config_file.clone()
+            .or(env_value::<PathBuf>("CONFIG")?.map(expand_home))
+            .or_else(Self::default_path))
+    }
+    
+    /// Build the effective configuration. A missing default file just means defaults;
+    /// a file named with `--config` or `SPLICE_ALT_CONFIG` has to exist.
+    pub fn load(overrides: &Overrides) -> Result<Self> {
+        let explicit = overrides.config_file.is_some() || env_value::<PathBuf>("CONFIG")?.is_some();
+        let mut config = match Self::file_path(overrides)? {
+            Some(path) if explicit || path.exists() => Self::read_file(&path)?,
+            _ => Self::default(),
+        };
+        
+        config.apply_env()?;
+        config.apply_overrides(overrides);
+        config.validate()
+            .map_err(|e| anyhow::anyhow!("Invalid configuration: {}", e))?;
+        Ok(config)
+    }
+    
+    /// Parse a config file on its own, without environment or command-line overrides
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let config = Self::read_file(path)?;
+        config.validate()
+            .map_err(|e| anyhow::anyhow!("Invalid config file {:?}: {}", path, e))?;
+        Ok(config)
+    }
+    
+    fn read_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read config file {:?}: {}", path, e))?;
+        let mut config: Config = toml::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("Failed to parse config file {:?}: {}", path, e))?;
+        
+        config.paths.watch_dir = expand_home(config.paths.watch_dir);
+        config.paths.library_dir = expand_home(config.paths.library_dir);
+        config.paths.database = expand_home(config.paths.database);
+        config.paths.quarantine_dir = config.paths.quarantine_dir.map(expand_home);
+        config.library.rules = config.library.rules.map(expand_home);
+        config.source = Some(path.to_path_buf());
+        Ok(config)
+    }
+    
+    fn apply_env(&mut self) -> Result<()> {
+        if let Some(path) = env_value::<PathBuf>("WATCH_DIR")? {
+            self.paths.watch_dir = expand_home(path);
+        }
+        if let Some(path) = env_value::<PathBuf>("LIBRARY_DIR")? {
+            self.paths.library_dir = expand_home(path);
+        }
+        if let Some(path) = env_value::<PathBuf>("DATABASE")? {
+            self.paths.database = expand_home(path);
+        }
+        if let Some(path) = env_value::<PathBuf>("QUARANTINE_DIR")? {
+            self.paths.quarantine_dir = Some(expand_home(path));
+        }
+        if let Some(layout) = env_value("LAYOUT")? {
+            self.library.layout = layout;
+        }
+        if let Some(path) = env_value::<PathBuf>("RULES")? {
+            self.library.rules = Some(expand_home(path));
+        }
+        if let Some(policy) = env_value("ON_COLLISION")? {
+            self.library.on_collision = policy;
+        }
+        if let Some(confidence) = env_value("MIN_DETECTION_CONFIDENCE")? {
+            self.library.min_detection_confidence = confidence;
+        }
+        if let Some(extensions) = env_value::<String>("AUDIO_EXTENSIONS")? {
+            self.watcher.audio_extensions = extensions
+                .split(',')
+                .map(|ext| ext.trim().trim_start_matches('.').to_string())
+                .filter(|ext| !ext.is_empty())
+                .collect();
+        }
+        if let Some(debounce) = env_value("DEBOUNCE_MS")? {
+            self.watcher.debounce_ms = debounce;
+        }
+        if let Some(timeout) = env_value("SETTLE_TIMEOUT_SECS")? {
+            self.watcher.settle_timeout_secs = timeout;
+        }
+        if let Some(attempts) = env_value("RETRY_ATTEMPTS")? {
+            self.watcher.retry_attempts = attempts;
+        }
+        if let Some(delay) = env_value("RETRY_DELAY_MS")? {
+            self.watcher.retry_delay_ms = delay;
+        }
+        if let Some(wait) = env_value("JSON_WAIT_MS")? {
+            self.watcher.json_wait_ms = wait;
+        }
+        if let Some(timeout) = env_value("METADATA_TIMEOUT_SECS")? {
+            self.watcher.metadata_timeout_secs = timeout;
+        }
+        if let Some(pause) = env_value("ERROR_PAUSE_SECS")? {
+            self.watcher.error_pause_secs = pause;
+        }
+        if let Some(timeout) = env_value("SHUTDOWN_TIMEOUT_SECS")? {
+            self.watcher.shutdown_timeout_secs = timeout;
+        }
+        if let Some(action) = env_value("ON_MISMATCH")? {
+            self.watcher.on_mismatch = action;
+        }
+        if let Some(enabled) = env_value("QUARANTINE_FAILURES")? {
+            self.watcher.quarantine_failures = enabled;
+        }
+        if let Some(enabled) = env_value("EMBED_METADATA")? {
+            self.watcher.embed_metadata = enabled;
+        }
+        if let Some(enabled) = env_value("EMBED_LOOP_CHUNKS")? {
+            self.watcher.embed_loop_chunks = enabled;
+        }
+        if let Some(enabled) = env_value("ANALYZE_ON_IMPORT")? {
+            self.watcher.analyze_on_import = enabled;
+        }
+        if let Some(enabled) = env_value("LOUDNESS_JOB")? {
+            self.watcher.loudness_job = enabled;
+        }
+        if let Some(level) = env_value("LOG_LEVEL")? {
+            self.logging.level = level;
+        }
+        Ok(())
+    }
+    
+    fn apply_overrides(&mut self, overrides: &Overrides) {
+        if let Some(path) = &overrides.watch_dir {
+            self.paths.watch_dir = path.clone();
+        }
+        if let Some(path) = &overrides.library_dir {
+            self.paths.library_dir = path.clone();
+        }
+        if let Some(path) = &overrides.database {
+            self.paths.database = path.clone();
+        }
+        if let Some(layout) = &overrides.layout {
+            self.library.layout = layout.clone();
+        }
+    }
+    
+    fn validate(&self) -> Result<()> {
+        PathTemplate::parse(&self.library.layout).map_err(|e| {
+            if e.to_string().contains("unknown placeholder") {
+                anyhow::anyhow!("{} (available placeholders: {})", e, layout::known_placeholders().join(", "))
+            } else {
+                e
+            }
+        })?;
+        
+        if let Some(rules) = &self.library.rules {
+            CategoryRules::from_file(rules)?;
+        }
+        
+        if !(0.0..=1.0).contains(&self.library.min_detection_confidence) {
+            anyhow::bail!("library.min_detection_confidence must be between 0.0 and 1.0");
+        }
+        
+        if self.watcher.audio_extensions.is_empty() {
+            anyhow::bail!("watcher.audio_extensions must list at least one extension");
+        }
+        for ext in &self.watcher.audio_extensions {
+            if ext.parse::<AudioFormat>().is_err() {
+                anyhow::bail!("watcher.audio_extensions: '{}' is not a supported audio format ({})", ext, AudioFormat::EXTENSIONS.join(", "));
+            }
+        }
+        
+        if self.watcher.retry_attempts == 0 {
+            anyhow::bail!("watcher.retry_attempts must be at least 1");
+        }
+        if self.watcher.settle_timeout_secs == 0 {
+            anyhow::bail!("watcher.settle_timeout_secs must be at least 1");
+        }
+        if self.watcher.metadata_timeout_secs == 0 {
+            anyhow::bail!("watcher.metadata_timeout_secs must be at least 1");
+        }
+        
+        if !LOG_LEVELS.contains(&self.logging.level.to_lowercase().as_str()) {
+            anyhow::bail!("logging.level '{}' is not one of {}", self.logging.level, LOG_LEVELS.join(", "));
+        }
+        
+        Ok(())
+    }
+    
+    /// Quarantine directory, `.quarantine` inside the library unless configured
+    pub fn quarantine_dir(&self) -> PathBuf {
+        self.paths.quarantine_dir.clone()
+            .unwrap_or_else(|| self.paths.library_dir.join(".quarantine"))
+    }
+    
+    /// The parsed library layout (already validated by `load`)
+    pub fn layout(&self) -> Result<PathTemplate> {
+        PathTemplate::parse(&self.library.layout)
+    }
+    
+    /// Category rules from the configured file, or the usual `rules.toml`/built-in fallback
+    pub fn category_rules(&self) -> Result<CategoryRules> {
+        match &self.library.rules {
+            Some(path) => CategoryRules::from_file(path),
+            None => CategoryRules::load(),
+        }
+    }
+    
+    /// Human readable description of where the settings came from
+    pub fn source_description(&self) -> String {
+        match &self.source {
+            Some(path) => format!("{:?}", path),
+            None => "built-in defaults".to_string(),
+        }
+    }
+}
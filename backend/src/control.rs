@@ -0,0 +1,355 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+use tokio::net::UnixListener;
+use tokio::sync::{mpsc, oneshot, watch};
+
+use crate::config::Config;
+use crate::db;
+use crate::loudness::LoudnessJob;
+use crate::watcher::{Counters, WatcherCommand, WatcherStats};
+
+/// How long clients wait for an answer; a rescan imports files and gets longer
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+const RESCAN_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Imports returned by `recent_imports` when no limit is given
+const DEFAULT_RECENT_LIMIT: usize = 20;
+
+// JSON-RPC 2.0 error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+/// The daemon is shutting down or the watcher has stopped
+const UNAVAILABLE: i64 = -32000;
+
+/// Methods the daemon answers
+pub const METHODS: &[&str] = &[
+    "status",
+    "counters",
+    "pending",
+    "recent_imports",
+    "pause",
+    "resume",
+    "rescan",
+    "reload",
+    "loudness_status",
+    "loudness_start",
+    "loudness_stop",
+    "shutdown",
+];
+
+/// Location of the control socket, next to the PID file
+pub fn socket_path() -> PathBuf {
+    dirs::runtime_dir()
+        .or_else(dirs::cache_dir)
+        .unwrap_or_else(|| PathBuf::from("/tmp"))
+        .join("splice-alt-daemon.sock")
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Request {
+    jsonrpc: String,
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Response {
+    jsonrpc: String,
+    id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+}
+
+impl Response {
+    fn new(id: Value, result: std::result::Result<Value, RpcError>) -> Self {
+        let (result, error) = match result {
+            Ok(result) => (Some(result), None),
+            Err(error) => (None, Some(error)),
+        };
+        Self { jsonrpc: "2.0".to_string(), id, result, error }
+    }
+}
+
+/// Result of the `status` method
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonStatus {
+    pub pid: u32,
+    pub uptime_secs: u64,
+    pub paused: bool,
+    /// Config file in use, `None` when running on defaults
+    pub config_file: Option<PathBuf>,
+    pub watch_dir: PathBuf,
+    pub library_dir: PathBuf,
+    pub database: PathBuf,
+    /// Files waiting in the pending queue
+    pub pending: usize,
+    pub counters: Counters,
+}
+
+/// Requests only the main daemon loop can carry out
+#[derive(Debug)]
+pub enum DaemonRequest {
+    /// Re-read the configuration, replying with the error if it was rejected
+    Reload(oneshot::Sender<std::result::Result<(), String>>),
+    Shutdown,
+}
+
+/// Everything a connection needs to answer requests
+#[derive(Clone)]
+pub struct ControlContext {
+    pub started: SystemTime,
+    pub stats: Arc<WatcherStats>,
+    /// Settings currently in effect
+    pub config: watch::Receiver<Config>,
+    pub watcher: mpsc::Sender<WatcherCommand>,
+    pub daemon: mpsc::Sender<DaemonRequest>,
+    pub loudness: Arc<LoudnessJob>,
+}
+
+/// Bind the control socket, replacing a stale one left behind by a daemon that died
+pub fn bind(path: &Path) -> Result<UnixListener> {
+    if path.exists() {
+        if std::os::unix::net::UnixStream::connect(path).is_ok() {
+            anyhow::bail!("Another daemon is already listening on {:?}", path);
+        }
+        fs::remove_file(path)
+            .map_err(|e| anyhow::anyhow!("Failed to remove stale control socket {:?}: {}", path, e))?;
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    
+    let listener = UnixListener::bind(path)
+        .map_err(|e| anyhow::anyhow!("Failed to bind control socket {:?}: {}", path, e))?;
+    // Only the owner may control the daemon
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+/// Accept connections until the task is dropped
+pub async fn serve(listener: UnixListener, context: ControlContext) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                let context = context.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = handle_connection(stream, context).await {
+                        eprintln!("⚠️  Control connection failed: {}", e);
+                    }
+                });
+            }
+            Err(e) => {
+                eprintln!("⚠️  Failed to accept control connection: {}", e);
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+        }
+    }
+}
+
+/// Answer newline-delimited requests until the client hangs up
+async fn handle_connection(stream: tokio::net::UnixStream, context: ControlContext) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = tokio::io::BufReader::new(reader).lines();
+    
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        
+        let response = match serde_json::from_str::<Value>(&line) {
+            Err(e) => Response::new(Value::Null, Err(RpcError::new(PARSE_ERROR, e.to_string()))),
+            Ok(value) => {
+                let id = value.get("id").cloned().unwrap_or(Value::Null);
+                match serde_json::from_value::<Request>(value) {
+                    Ok(request) if request.jsonrpc == "2.0" => {
+                        Response::new(id, dispatch(&request.method, request.params, &context).await)
+                    }
+                    Ok(_) => Response::new(id, Err(RpcError::new(INVALID_REQUEST, "jsonrpc must be \"2.0\""))),
+                    Err(e) => Response::new(id, Err(RpcError::new(INVALID_REQUEST, e.to_string()))),
+                }
+            }
+        };
+        
+        let mut text = serde_json::to_string(&response)?;
+        text.push('\n');
+        writer.write_all(text.as_bytes()).await?;
+    }
+    
+    Ok(())
+}
+
+fn internal(e: impl std::fmt::Display) -> RpcError {
+    RpcError::new(INTERNAL_ERROR, e.to_string())
+}
+
+fn to_value<T: Serialize>(value: &T) -> std::result::Result<Value, RpcError> {
+    serde_json::to_value(value).map_err(internal)
+}
+
+async fn dispatch(method: &str, params: Value, context: &ControlContext) -> std::result::Result<Value, RpcError> {
+    let config = context.config.borrow().clone();
+    
+    match method {
+        "status" => {
+            let pending = db::get_pending_files(&config.paths.database).map_err(internal)?.len();
+            let uptime = SystemTime::now().duration_since(context.started).unwrap_or_default();
+            to_value(&DaemonStatus {
+                pid: std::process::id(),
+                uptime_secs: uptime.as_secs(),
+                paused: context.stats.is_paused(),
+                config_file: config.source.clone(),
+                watch_dir: config.paths.watch_dir.clone(),
+                library_dir: config.paths.library_dir.clone(),
+                database: config.paths.database.clone(),
+                pending,
+                counters: context.stats.snapshot(),
+            })
+        }
+        "counters" => to_value::<Counters>(&context.stats.snapshot()),
+        "pending" => to_value(&db::get_pending_files(&config.paths.database).map_err(internal)?),
+        "recent_imports" => {
+            let limit = match params.get("limit") {
+                None | Some(Value::Null) => DEFAULT_RECENT_LIMIT,
+                Some(limit) => limit.as_u64()
+                    .ok_or_else(|| RpcError::new(INVALID_PARAMS, "limit must be a non-negative integer"))? as usize,
+            };
+            to_value(&db::get_recent_samples(&config.paths.database, limit).map_err(internal)?)
+        }
+        "pause" => {
+            let was_paused = context.stats.paused.swap(true, Ordering::Relaxed);
+            if !was_paused {
+                say!("⏸️  Watching paused via control socket");
+            }
+            Ok(json!({ "paused": true, "changed": !was_paused }))
+        }
+        "resume" => {
+            let was_paused = context.stats.paused.swap(false, Ordering::Relaxed);
+            if was_paused {
+                say!("▶️  Watching resumed via control socket");
+                // Pick up whatever arrived while events were being ignored
+                context.watcher.send(WatcherCommand::Rescan(None)).await
+                    .map_err(|_| RpcError::new(UNAVAILABLE, "watcher is not running"))?;
+            }
+            Ok(json!({ "paused": false, "changed": was_paused }))
+        }
+        "rescan" => {
+            let (reply, result) = oneshot::channel();
+            context.watcher.send(WatcherCommand::Rescan(Some(reply))).await
+                .map_err(|_| RpcError::new(UNAVAILABLE, "watcher is not running"))?;
+            let summary = result.await
+                .map_err(|_| RpcError::new(UNAVAILABLE, "watcher stopped before finishing the rescan"))?
+                .map_err(internal)?;
+            to_value(&summary)
+        }
+        "reload" => {
+            let (reply, result) = oneshot::channel();
+            context.daemon.send(DaemonRequest::Reload(reply)).await
+                .map_err(|_| RpcError::new(UNAVAILABLE, "daemon is shutting down"))?;
+            result.await
+                .map_err(|_| RpcError::new(UNAVAILABLE, "daemon is shutting down"))?
+                .map_err(internal)?;
+            Ok(json!({ "reloaded": true }))
+        }
+        "loudness_status" => to_value(&context.loudness.status()),
+        "loudness_start" => {
+            let force = match params.get("force") {
+                None | Some(Value::Null) => false,
+                Some(force) => force.as_bool()
+                    .ok_or_else(|| RpcError::new(INVALID_PARAMS, "force must be a boolean"))?,
+            };
+            if force {
+                let cleared = db::clear_loudness(&config.paths.database).map_err(internal)?;
+                say!("🔊 Measuring the loudness of all {} samples again", cleared);
+            }
+            let changed = context.loudness.set_running(true);
+            if changed {
+                say!("🔊 Loudness job started via control socket");
+            }
+            Ok(json!({ "running": true, "changed": changed }))
+        }
+        "loudness_stop" => {
+            let changed = context.loudness.set_running(false);
+            if changed {
+                say!("🔊 Loudness job stopped via control socket");
+            }
+            Ok(json!({ "running": false, "changed": changed }))
+        }
+        "shutdown" => {
+            context.daemon.send(DaemonRequest::Shutdown).await
+                .map_err(|_| RpcError::new(UNAVAILABLE, "daemon is already shutting down"))?;
+            Ok(json!({ "pid": std::process::id() }))
+        }
+        other => Err(RpcError::new(METHOD_NOT_FOUND, format!("unknown method '{}', expected one of {}", other, METHODS.join(", ")))),
+    }
+}
+
+/// Connection to a running daemon's control socket
+pub struct Client {
+    reader: BufReader<std::os::unix::net::UnixStream>,
+    next_id: u64,
+}
+
+impl Client {
+    /// Fails when no daemon is listening, which callers treat as "not reachable"
+    pub fn connect() -> Result<Self> {
+        let path = socket_path();
+        let stream = std::os::unix::net::UnixStream::connect(&path)
+            .map_err(|e| anyhow::anyhow!("Control socket {:?} is unreachable: {}", path, e))?;
+        stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+        Ok(Self { reader: BufReader::new(stream), next_id: 1 })
+    }
+    
+    /// Call a method and return its result, turning JSON-RPC errors into `Err`
+    pub fn call(&mut self, method: &str, params: Value) -> Result<Value> {
+        let timeout = if method == "rescan" { RESCAN_TIMEOUT } else { CLIENT_TIMEOUT };
+        self.reader.get_ref().set_read_timeout(Some(timeout))?;
+        
+        let id = self.next_id;
+        self.next_id += 1;
+        let request = Request { jsonrpc: "2.0".to_string(), id: json!(id), method: method.to_string(), params };
+        let mut text = serde_json::to_string(&request)?;
+        text.push('\n');
+        self.reader.get_mut().write_all(text.as_bytes())?;
+        
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            anyhow::bail!("Daemon closed the control connection without answering '{}'", method);
+        }
+        let response: Response = serde_json::from_str(&line)
+            .map_err(|e| anyhow::anyhow!("Invalid response from daemon: {}", e))?;
+        
+        match response.error {
+            Some(error) => anyhow::bail!("Daemon rejected '{}': {} (code {})", method, error.message, error.code),
+            None => Ok(response.result.unwrap_or(Value::Null)),
+        }
+    }
+}
@@ -1,7 +1,14 @@
 use anyhow::Result;
 use rusqlite::{Connection, params};
-use std::path::Path;
-use crate::metadata::{SampleMetadata, BitwigCategory};
+use serde::{Serialize, Serializer};
+use std::path::{Path, PathBuf};
+use strum::{Display, EnumString};
+use crate::analysis::Analysis;
+use crate::loudness::Loudness;
+use crate::config::WatcherConfig;
+use crate::metadata::{SampleMetadata, SampleMetaData, Pack, BitwigCategory};
+use crate::migrations;
+use crate::output::{plain_row, PlainText};
 
 pub fn init_database(db_path: &Path) -> Result<()> {
     // Ensure the parent directory exists
@@ -10,60 +17,16 @@ pub fn init_database(db_path: &Path) -> Result<()> {
             .map_err(|e| anyhow::anyhow!("Failed to create database directory {:?}: {}", parent, e))?;
     }
     
-    let conn = Connection::open(db_path)?;
-    
-    // Create the samples table
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS samples (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            file_path TEXT NOT NULL UNIQUE,
-            pack_name TEXT NOT NULL,
-            pack_uuid TEXT NOT NULL,
-            filename TEXT NOT NULL,
-            file_hash TEXT NOT NULL UNIQUE,
-            bpm INTEGER,
-            audio_key TEXT,
-            chord_type TEXT,
-            tags TEXT, -- JSON array of tags
-            mapped_category TEXT NOT NULL,
-            sample_type TEXT NOT NULL,
-            duration INTEGER NOT NULL,
-            file_size INTEGER NOT NULL,
-            provider_name TEXT NOT NULL,
-            date_downloaded TEXT NOT NULL,
-            date_processed DATETIME DEFAULT CURRENT_TIMESTAMP,
-            splice_url TEXT,
-            preview_url TEXT,
-            asset_uuid TEXT NOT NULL
-        )",
-        [],
-    )?;
-    
-    // Create indexes for common queries
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_file_hash ON samples(file_hash)",
-        [],
-    )?;
-    
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_pack_name ON samples(pack_name)",
-        [],
-    )?;
-    
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_category ON samples(mapped_category)",
-        [],
-    )?;
+    let mut conn = Connection::open(db_path)?;
     
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_tags ON samples(tags)",
-        [],
-    )?;
+    // Bring the schema up to date (backs the database up first when needed)
+    migrations::migrate(&mut conn, db_path)?;
     
-    println!("Database initialized at: {:?}", db_path);
+    say!("Database initialized at: {:?}", db_path);
     Ok(())
 }
 
+#[derive(Serialize)]
 pub struct SampleRecord {
     pub id: Option<i64>,
     pub file_path: String,
@@ -74,6 +37,7 @@ pub struct SampleRecord {
     pub bpm: Option<u32>,
     pub audio_key: Option<String>,
     pub chord_type: Option<String>,
+    #[serde(serialize_with = "tags_as_list")]
     pub tags: String, // JSON
     pub mapped_category: String,
     pub sample_type: String,
@@ -84,6 +48,54 @@ pub struct SampleRecord {
     pub splice_url: Option<String>,
     pub preview_url: String,
     pub asset_uuid: String,
+    pub category_confidence: Option<f64>,
+    pub runner_up_category: Option<String>,
+    /// Audio container (`wav`, `aiff`, `flac`, `mp3` or `ogg`)
+    pub format: Option<String>,
+    /// Read from the WAV header; `None` for other formats and samples imported before it was
+    pub sample_rate: Option<u32>,
+    pub bit_depth: Option<u16>,
+    pub channels: Option<u16>,
+    pub frame_count: Option<u64>,
+    /// Exact length in seconds, unlike Splice's rounded `duration`
+    pub duration_secs: Option<f64>,
+    /// Estimated from the audio by `analyze`, each with a confidence from 0.0 to 1.0
+    pub detected_bpm: Option<f64>,
+    pub detected_bpm_confidence: Option<f64>,
+    pub detected_key: Option<String>,
+    pub detected_chord_type: Option<String>,
+    pub detected_key_confidence: Option<f64>,
+    /// When the audio was last analyzed; `None` if it never was
+    pub analyzed_at: Option<String>,
+    /// Measured by the loudness pass: EBU R128 integrated loudness, peaks, RMS and DC offset
+    pub loudness_lufs: Option<f64>,
+    pub sample_peak_dbfs: Option<f64>,
+    pub true_peak_dbtp: Option<f64>,
+    pub rms_dbfs: Option<f64>,
+    pub dc_offset: Option<f64>,
+    /// When the loudness was last measured; `None` if it never was
+    pub loudness_analyzed_at: Option<String>,
+    #[serde(skip_serializing)]
+    pub metadata_json: Option<String>,
+}
+
+/// Output the stored JSON tag string as a real list
+fn tags_as_list<S: Serializer>(tags: &str, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    let tags: Vec<String> = serde_json::from_str(tags).unwrap_or_default();
+    tags.serialize(serializer)
+}
+
+impl PlainText for SampleRecord {
+    fn plain_lines(&self) -> Vec<String> {
+        vec![plain_row([
+            self.file_hash.clone(),
+            self.mapped_category.clone(),
+            self.bpm.map_or(String::new(), |b| b.to_string()),
+            self.audio_key.clone().unwrap_or_default(),
+            self.pack_name.clone(),
+            self.file_path.clone(),
+        ])]
+    }
 }
 
 impl From<&SampleMetadata> for SampleRecord {
@@ -99,7 +111,7 @@ impl From<&SampleMetadata> for SampleRecord {
             audio_key: metadata.sample_meta_data.audio_key.clone(),
             chord_type: metadata.sample_meta_data.chord_type.clone(),
             tags: serde_json::to_string(&metadata.sample_meta_data.tags).unwrap_or_default(),
-            mapped_category: metadata.get_category().as_str().to_string(),
+            mapped_category: String::new(), // Will be set once the category rules have been applied
             sample_type: metadata.sample_meta_data.sample_type.clone(),
             duration: metadata.sample_meta_data.duration,
             file_size: metadata.sample.file_size,
@@ -108,25 +120,183 @@ impl From<&SampleMetadata> for SampleRecord {
             splice_url: Some(metadata.sample.url.clone()),
             preview_url: metadata.sample_meta_data.preview_url.clone(),
             asset_uuid: metadata.sample_meta_data.asset_uuid.clone(),
+            category_confidence: None,
+            runner_up_category: None,
+            format: None, // Will be set from the audio file that is imported
+            sample_rate: None,
+            bit_depth: None,
+            channels: None,
+            frame_count: None,
+            duration_secs: None,
+            detected_bpm: None,
+            detected_bpm_confidence: None,
+            detected_key: None,
+            detected_chord_type: None,
+            detected_key_confidence: None,
+            analyzed_at: None,
+            loudness_lufs: None,
+            sample_peak_dbfs: None,
+            true_peak_dbtp: None,
+            rms_dbfs: None,
+            dc_offset: None,
+            loudness_analyzed_at: None,
+            metadata_json: serde_json::to_string(metadata).ok(),
+        }
+    }
+}
+
+impl SampleRecord {
+    /// Splice metadata for this sample, rebuilt from the stored columns for rows
+    /// imported before the original JSON was kept
+    pub fn sample_meta_data(&self) -> SampleMetaData {
+        if let Some(metadata) = self.metadata_json.as_deref()
+            .and_then(|json| serde_json::from_str::<SampleMetadata>(json).ok())
+        {
+            return metadata.sample_meta_data;
+        }
+        
+        SampleMetaData {
+            audio_key: self.audio_key.clone(),
+            bpm: self.bpm,
+            chord_type: self.chord_type.clone(),
+            dir: String::new(),
+            duration: self.duration,
+            file_hash: self.file_hash.clone(),
+            filename: self.filename.clone(),
+            pack: Pack {
+                uuid: self.pack_uuid.clone(),
+                name: self.pack_name.clone(),
+                description: String::new(),
+                provider_name: self.provider_name.clone(),
+                provider_description: String::new(),
+                cover_url: String::new(),
+                banner_url: String::new(),
+                main_genre: String::new(),
+                sample_count: 0,
+                preset_count: 0,
+                permalink: String::new(),
+                is_archived: false,
+            },
+            preview_url: self.preview_url.clone(),
+            price: 0,
+            provider_name: self.provider_name.clone(),
+            provider_uuid: String::new(),
+            provider_permalink: String::new(),
+            sample_type: self.sample_type.clone(),
+            tags: serde_json::from_str(&self.tags).unwrap_or_default(),
+            waveform_url: String::new(),
+            published: false,
+            popularity: 0,
+            trending: 0,
+            published_at: String::new(),
+            purchased_at: self.date_downloaded.clone(),
+            sas_id: String::new(),
+            liked: false,
+            licensed: false,
+            asset_uuid: self.asset_uuid.clone(),
+        }
+    }
+    
+    /// The stored tempo and key estimate, if the audio has been analyzed
+    pub fn analysis(&self) -> Option<Analysis> {
+        self.analyzed_at.as_ref()?;
+        Some(Analysis {
+            bpm: self.detected_bpm,
+            bpm_confidence: self.detected_bpm_confidence,
+            key: self.detected_key.clone(),
+            chord_type: self.detected_chord_type.clone(),
+            key_confidence: self.detected_key_confidence,
+        })
+    }
+    
+    /// Splice metadata with missing BPM and key filled in from detections that are
+    /// at least `min_confidence` sure
+    pub fn sample_meta_data_with_detections(&self, min_confidence: f64) -> SampleMetaData {
+        let mut sample = self.sample_meta_data();
+        if let Some(analysis) = self.analysis() {
+            analysis.fill_missing(&mut sample, min_confidence);
         }
+        sample
     }
 }
 
+/// Column list matching the field order expected by `sample_from_row`
+pub(crate) const SAMPLE_COLUMNS: &str = "id, file_path, pack_name, pack_uuid, filename, file_hash,
+    bpm, audio_key, chord_type, tags, mapped_category,
+    sample_type, duration, file_size, provider_name,
+    date_downloaded, splice_url, preview_url, asset_uuid,
+    category_confidence, runner_up_category, metadata_json, format,
+    sample_rate, bit_depth, channels, frame_count, duration_secs,
+    detected_bpm, detected_bpm_confidence, detected_key, detected_chord_type, detected_key_confidence, analyzed_at,
+    loudness_lufs, sample_peak_dbfs, true_peak_dbtp, rms_dbfs, dc_offset, loudness_analyzed_at";
+
+pub(crate) fn sample_from_row(row: &rusqlite::Row) -> rusqlite::Result<SampleRecord> {
+    Ok(SampleRecord {
+        id: Some(row.get(0)?),
+        file_path: row.get(1)?,
+        pack_name: row.get(2)?,
+        pack_uuid: row.get(3)?,
+        filename: row.get(4)?,
+        file_hash: row.get(5)?,
+        bpm: row.get(6)?,
+        audio_key: row.get(7)?,
+        chord_type: row.get(8)?,
+        tags: row.get(9)?,
+        mapped_category: row.get(10)?,
+        sample_type: row.get(11)?,
+        duration: row.get(12)?,
+        file_size: row.get(13)?,
+        provider_name: row.get(14)?,
+        date_downloaded: row.get(15)?,
+        splice_url: row.get(16)?,
+        preview_url: row.get(17)?,
+        asset_uuid: row.get(18)?,
+        category_confidence: row.get(19)?,
+        runner_up_category: row.get(20)?,
+        metadata_json: row.get(21)?,
+        format: row.get(22)?,
+        sample_rate: row.get(23)?,
+        bit_depth: row.get(24)?,
+        channels: row.get(25)?,
+        frame_count: row.get(26)?,
+        duration_secs: row.get(27)?,
+        detected_bpm: row.get(28)?,
+        detected_bpm_confidence: row.get(29)?,
+        detected_key: row.get(30)?,
+        detected_chord_type: row.get(31)?,
+        detected_key_confidence: row.get(32)?,
+        analyzed_at: row.get(33)?,
+        loudness_lufs: row.get(34)?,
+        sample_peak_dbfs: row.get(35)?,
+        true_peak_dbtp: row.get(36)?,
+        rms_dbfs: row.get(37)?,
+        dc_offset: row.get(38)?,
+        loudness_analyzed_at: row.get(39)?,
+    })
+}
+
 pub fn insert_sample(db_path: &Path, record: SampleRecord) -> Result<i64> {
-    let conn = Connection::open(db_path)?;
+    let mut conn = Connection::open(db_path)?;
     
     // Check if sample already exists by hash
     if sample_exists_by_hash(&conn, &record.file_hash)? {
         return Err(anyhow::anyhow!("Sample with hash {} already exists", record.file_hash));
     }
     
-    let _row_id = conn.execute(
+    let tx = conn.transaction()?;
+    tx.execute(
         "INSERT INTO samples (
             file_path, pack_name, pack_uuid, filename, file_hash,
             bpm, audio_key, chord_type, tags, mapped_category,
             sample_type, duration, file_size, provider_name,
-            date_downloaded, splice_url, preview_url, asset_uuid
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
+            date_downloaded, splice_url, preview_url, asset_uuid,
+            category_confidence, runner_up_category, metadata_json, format,
+            sample_rate, bit_depth, channels, frame_count, duration_secs,
+            detected_bpm, detected_bpm_confidence, detected_key, detected_chord_type, detected_key_confidence, analyzed_at,
+            loudness_lufs, sample_peak_dbfs, true_peak_dbtp, rms_dbfs, dc_offset, loudness_analyzed_at
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22,
+                  ?23, ?24, ?25, ?26, ?27, ?28, ?29, ?30, ?31, ?32, ?33,
+                  ?34, ?35, ?36, ?37, ?38, ?39)",
         params![
             record.file_path,
             record.pack_name,
@@ -146,10 +316,118 @@ pub fn insert_sample(db_path: &Path, record: SampleRecord) -> Result<i64> {
             record.splice_url,
             record.preview_url,
             record.asset_uuid,
+            record.category_confidence,
+            record.runner_up_category,
+            record.metadata_json,
+            record.format,
+            record.sample_rate,
+            record.bit_depth,
+            record.channels,
+            record.frame_count,
+            record.duration_secs,
+            record.detected_bpm,
+            record.detected_bpm_confidence,
+            record.detected_key,
+            record.detected_chord_type,
+            record.detected_key_confidence,
+            record.analyzed_at,
+            record.loudness_lufs,
+            record.sample_peak_dbfs,
+            record.true_peak_dbtp,
+            record.rms_dbfs,
+            record.dc_offset,
+            record.loudness_analyzed_at,
         ],
     )?;
     
-    Ok(conn.last_insert_rowid())
+    let sample_id = tx.last_insert_rowid();
+    link_pack_and_tags(&tx, sample_id, &record.sample_meta_data())?;
+    tx.commit()?;
+    
+    Ok(sample_id)
+}
+
+/// Record the sample's provider, pack and tags in their normalized tables.
+/// Existing provider and pack rows only gain details, they are never blanked.
+fn link_pack_and_tags(conn: &Connection, sample_id: i64, sample: &SampleMetaData) -> Result<()> {
+    let provider_id: Option<i64> = if sample.provider_name.trim().is_empty() {
+        None
+    } else {
+        conn.execute(
+            "INSERT INTO providers (name, uuid, permalink, description)
+             VALUES (?1, NULLIF(?2, ''), NULLIF(?3, ''), NULLIF(?4, ''))
+             ON CONFLICT(name) DO UPDATE SET
+                uuid = COALESCE(excluded.uuid, uuid),
+                permalink = COALESCE(excluded.permalink, permalink),
+                description = COALESCE(excluded.description, description)",
+            params![
+                sample.provider_name,
+                sample.provider_uuid,
+                sample.provider_permalink,
+                sample.pack.provider_description,
+            ],
+        )?;
+        Some(conn.query_row(
+            "SELECT id FROM providers WHERE name = ?1",
+            params![sample.provider_name],
+            |row| row.get(0),
+        )?)
+    };
+    
+    let pack = &sample.pack;
+    if !pack.uuid.trim().is_empty() {
+        conn.execute(
+            "INSERT INTO packs (uuid, name, provider_id, description, main_genre, cover_url, banner_url,
+                                sample_count, preset_count, permalink, is_archived)
+             VALUES (?1, ?2, ?3, NULLIF(?4, ''), NULLIF(?5, ''), NULLIF(?6, ''), NULLIF(?7, ''),
+                     NULLIF(?8, 0), NULLIF(?9, 0), NULLIF(?10, ''), ?11)
+             ON CONFLICT(uuid) DO UPDATE SET
+                name = excluded.name,
+                provider_id = COALESCE(excluded.provider_id, provider_id),
+                description = COALESCE(excluded.description, description),
+                main_genre = COALESCE(excluded.main_genre, main_genre),
+                cover_url = COALESCE(excluded.cover_url, cover_url),
+                banner_url = COALESCE(excluded.banner_url, banner_url),
+                sample_count = COALESCE(excluded.sample_count, sample_count),
+                preset_count = COALESCE(excluded.preset_count, preset_count),
+                permalink = COALESCE(excluded.permalink, permalink),
+                is_archived = excluded.is_archived",
+            params![
+                pack.uuid,
+                pack.name,
+                provider_id,
+                pack.description,
+                pack.main_genre,
+                pack.cover_url,
+                pack.banner_url,
+                pack.sample_count,
+                pack.preset_count,
+                pack.permalink,
+                pack.is_archived,
+            ],
+        )?;
+    }
+    
+    for (position, tag) in sample.tags.iter().enumerate() {
+        let name = normalize_tag(tag);
+        if name.is_empty() {
+            continue;
+        }
+        
+        conn.execute("INSERT OR IGNORE INTO tags (name) VALUES (?1)", params![name])?;
+        conn.execute(
+            "INSERT OR IGNORE INTO sample_tags (sample_id, tag_id, position)
+             SELECT ?1, id, ?2 FROM tags WHERE name = ?3",
+            params![sample_id, position as i64, name],
+        )?;
+    }
+    
+    Ok(())
+}
+
+/// Tags are stored trimmed and lowercased so "Kick" and "kick " are the same tag
+pub fn normalize_tag(tag: &str) -> String {
+    tag.trim().to_lowercase()
 }
 
 pub fn sample_exists_by_hash(conn: &Connection, file_hash: &str) -> Result<bool> {
@@ -162,38 +440,12 @@ pub fn get_sample_by_hash(db_path: &Path, file_hash: &str) -> Result<Option<Samp
     let conn = Connection::open(db_path)?;
     
     let mut stmt = conn.prepare(
-        "SELECT id, file_path, pack_name, pack_uuid, filename, file_hash,
-                bpm, audio_key, chord_type, tags, mapped_category,
-                sample_type, duration, file_size, provider_name,
-                date_downloaded, splice_url, preview_url, asset_uuid
-         FROM samples WHERE file_hash = ?1"
+        &format!("SELECT {} FROM samples WHERE file_hash = ?1", SAMPLE_COLUMNS)
     )?;
     
-    let sample_iter = stmt.query_map(params![file_hash], |row| {
-        Ok(SampleRecord {
-            id: Some(row.get(0)?),
-            file_path: row.get(1)?,
-            pack_name: row.get(2)?,
-            pack_uuid: row.get(3)?,
-            filename: row.get(4)?,
-            file_hash: row.get(5)?,
-            bpm: row.get(6)?,
-            audio_key: row.get(7)?,
-            chord_type: row.get(8)?,
-            tags: row.get(9)?,
-            mapped_category: row.get(10)?,
-            sample_type: row.get(11)?,
-            duration: row.get(12)?,
-            file_size: row.get(13)?,
-            provider_name: row.get(14)?,
-            date_downloaded: row.get(15)?,
-            splice_url: row.get(16)?,
-            preview_url: row.get(17)?,
-            asset_uuid: row.get(18)?,
-        })
-    })?;
+    let mut sample_iter = stmt.query_map(params![file_hash], sample_from_row)?;
     
-    for sample in sample_iter {
+    if let Some(sample) = sample_iter.next() {
         return Ok(Some(sample?));
     }
     
@@ -211,41 +463,79 @@ pub fn update_file_path(db_path: &Path, file_hash: &str, new_path: &str) -> Resu
     Ok(())
 }
 
+/// Record the tempo and key estimated for a sample's audio
+pub fn store_analysis(db_path: &Path, file_hash: &str, analysis: &Analysis) -> Result<()> {
+    let conn = Connection::open(db_path)?;
+    
+    conn.execute(
+        "UPDATE samples SET detected_bpm = ?1, detected_bpm_confidence = ?2, detected_key = ?3,
+            detected_chord_type = ?4, detected_key_confidence = ?5, analyzed_at = CURRENT_TIMESTAMP
+         WHERE file_hash = ?6",
+        params![
+            analysis.bpm,
+            analysis.bpm_confidence,
+            analysis.key,
+            analysis.chord_type,
+            analysis.key_confidence,
+            file_hash,
+        ],
+    )?;
+    
+    Ok(())
+}
+
+/// Record the loudness measured for a sample's audio
+pub fn store_loudness(db_path: &Path, file_hash: &str, loudness: &Loudness) -> Result<()> {
+    let conn = Connection::open(db_path)?;
+    
+    conn.execute(
+        "UPDATE samples SET loudness_lufs = ?1, sample_peak_dbfs = ?2, true_peak_dbtp = ?3,
+            rms_dbfs = ?4, dc_offset = ?5, loudness_analyzed_at = CURRENT_TIMESTAMP
+         WHERE file_hash = ?6",
+        params![
+            loudness.integrated_lufs,
+            loudness.sample_peak_dbfs,
+            loudness.true_peak_dbtp,
+            loudness.rms_dbfs,
+            loudness.dc_offset,
+            file_hash,
+        ],
+    )?;
+    
+    Ok(())
+}
+
+/// Mark every sample as not yet measured so the loudness pass goes over them again,
+/// returning how many there are
+pub fn clear_loudness(db_path: &Path) -> Result<usize> {
+    let conn = Connection::open(db_path)?;
+    Ok(conn.execute("UPDATE samples SET loudness_analyzed_at = NULL", [])?)
+}
+
+/// Samples whose loudness hasn't been measured yet, in import order so an interrupted
+/// pass picks up where it stopped
+pub fn get_samples_without_loudness(db_path: &Path) -> Result<Vec<SampleRecord>> {
+    let conn = Connection::open(db_path)?;
+    
+    let mut stmt = conn.prepare(
+        &format!("SELECT {} FROM samples WHERE loudness_analyzed_at IS NULL ORDER BY id", SAMPLE_COLUMNS)
+    )?;
+    
+    let samples = stmt.query_map([], sample_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    
+    Ok(samples)
+}
+
 pub fn get_samples_by_category(db_path: &Path, category: BitwigCategory) -> Result<Vec<SampleRecord>> {
     let conn = Connection::open(db_path)?;
     
     let mut stmt = conn.prepare(
-        "SELECT id, file_path, pack_name, pack_uuid, filename, file_hash,
-                bpm, audio_key, chord_type, tags, mapped_category,
-                sample_type, duration, file_size, provider_name,
-                date_downloaded, splice_url, preview_url, asset_uuid
-         FROM samples WHERE mapped_category = ?1
-         ORDER BY pack_name, filename"
+        &format!("SELECT {} FROM samples WHERE mapped_category = ?1
+         ORDER BY pack_name, filename", SAMPLE_COLUMNS)
     )?;
     
-    let sample_iter = stmt.query_map(params![category.as_str()], |row| {
-        Ok(SampleRecord {
-            id: Some(row.get(0)?),
-            file_path: row.get(1)?,
-            pack_name: row.get(2)?,
-            pack_uuid: row.get(3)?,
-            filename: row.get(4)?,
-            file_hash: row.get(5)?,
-            bpm: row.get(6)?,
-            audio_key: row.get(7)?,
-            chord_type: row.get(8)?,
-            tags: row.get(9)?,
-            mapped_category: row.get(10)?,
-            sample_type: row.get(11)?,
-            duration: row.get(12)?,
-            file_size: row.get(13)?,
-            provider_name: row.get(14)?,
-            date_downloaded: row.get(15)?,
-            splice_url: row.get(16)?,
-            preview_url: row.get(17)?,
-            asset_uuid: row.get(18)?,
-        })
-    })?;
+    let sample_iter = stmt.query_map(params![category.as_str()], sample_from_row)?;
     
     let mut samples = Vec::new();
     for sample in sample_iter {
@@ -253,4 +543,414 @@ pub fn get_samples_by_category(db_path: &Path, category: BitwigCategory) -> Resu
     }
     
     Ok(samples)
-} 
\ No newline at end of file
+}
+
+pub fn get_all_samples(db_path: &Path) -> Result<Vec<SampleRecord>> {
+    let conn = Connection::open(db_path)?;
+    
+    let mut stmt = conn.prepare(
+        &format!("SELECT {} FROM samples ORDER BY mapped_category, pack_name, filename", SAMPLE_COLUMNS)
+    )?;
+    
+    let samples = stmt.query_map([], sample_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    
+    Ok(samples)
+}
+
+/// The most recently imported samples, newest first
+pub fn get_recent_samples(db_path: &Path, limit: usize) -> Result<Vec<SampleRecord>> {
+    let conn = Connection::open(db_path)?;
+    
+    let mut stmt = conn.prepare(
+        &format!("SELECT {} FROM samples ORDER BY date_processed DESC, id DESC LIMIT ?1", SAMPLE_COLUMNS)
+    )?;
+    
+    let samples = stmt.query_map(params![limit as i64], sample_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    
+    Ok(samples)
+}
+
+/// Point a sample at its new library location and category in a single transaction
+pub fn relocate_sample(
+    db_path: &Path,
+    file_hash: &str,
+    new_path: &str,
+    category: &str,
+    confidence: Option<f64>,
+    runner_up: Option<&str>,
+) -> Result<()> {
+    let mut conn = Connection::open(db_path)?;
+    let tx = conn.transaction()?;
+    
+    let updated = tx.execute(
+        "UPDATE samples
+         SET file_path = ?1, mapped_category = ?2, category_confidence = ?3, runner_up_category = ?4
+         WHERE file_hash = ?5",
+        params![new_path, category, confidence, runner_up, file_hash],
+    )?;
+    
+    if updated != 1 {
+        return Err(anyhow::anyhow!("Expected to update one sample with hash {}, updated {}", file_hash, updated));
+    }
+    
+    tx.commit()?;
+    Ok(())
+}
+
+/// Samples whose category was chosen with less than `threshold` confidence, least certain first
+pub fn get_low_confidence_samples(db_path: &Path, threshold: f64) -> Result<Vec<SampleRecord>> {
+    let conn = Connection::open(db_path)?;
+    
+    let mut stmt = conn.prepare(
+        &format!("SELECT {} FROM samples
+         WHERE category_confidence IS NOT NULL AND category_confidence < ?1
+         ORDER BY category_confidence, pack_name, filename", SAMPLE_COLUMNS)
+    )?;
+    
+    let samples = stmt.query_map(params![threshold], sample_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    
+    Ok(samples)
+}
+
+/// A Splice pack together with how many of its samples are in the library
+#[derive(Serialize)]
+pub struct PackRecord {
+    pub name: String,
+    pub provider_name: Option<String>,
+    pub main_genre: Option<String>,
+    pub description: Option<String>,
+    pub permalink: Option<String>,
+    /// Number of samples Splice lists for the pack
+    pub sample_count: Option<u32>,
+    pub samples_in_library: u32,
+}
+
+/// Packs with at least one sample in the library, optionally filtered by name or provider
+pub fn get_packs(db_path: &Path, filter: Option<&str>) -> Result<Vec<PackRecord>> {
+    let conn = Connection::open(db_path)?;
+    
+    let mut stmt = conn.prepare(
+        "SELECT packs.name, providers.name, packs.main_genre, packs.description,
+                packs.permalink, packs.sample_count, COUNT(samples.id)
+         FROM packs
+         JOIN samples ON samples.pack_uuid = packs.uuid
+         LEFT JOIN providers ON providers.id = packs.provider_id
+         WHERE ?1 IS NULL OR packs.name LIKE '%' || ?1 || '%' OR providers.name LIKE '%' || ?1 || '%'
+         GROUP BY packs.id
+         ORDER BY packs.name"
+    )?;
+    
+    let packs = stmt.query_map(params![filter], |row| {
+        Ok(PackRecord {
+            name: row.get(0)?,
+            provider_name: row.get(1)?,
+            main_genre: row.get(2)?,
+            description: row.get(3)?,
+            permalink: row.get(4)?,
+            sample_count: row.get(5)?,
+            samples_in_library: row.get(6)?,
+        })
+    })?
+    .collect::<rusqlite::Result<Vec<_>>>()?;
+    
+    Ok(packs)
+}
+
+impl PlainText for PackRecord {
+    fn plain_lines(&self) -> Vec<String> {
+        vec![plain_row([
+            self.name.clone(),
+            self.provider_name.clone().unwrap_or_default(),
+            self.main_genre.clone().unwrap_or_default(),
+            self.samples_in_library.to_string(),
+            self.sample_count.map_or(String::new(), |c| c.to_string()),
+        ])]
+    }
+}
+
+#[derive(Serialize)]
+pub struct TagCount {
+    pub name: String,
+    pub samples: u32,
+}
+
+impl PlainText for TagCount {
+    fn plain_lines(&self) -> Vec<String> {
+        vec![plain_row([self.samples.to_string(), self.name.clone()])]
+    }
+}
+
+/// Tags ordered by how many samples carry them
+pub fn get_tag_counts(db_path: &Path) -> Result<Vec<TagCount>> {
+    let conn = Connection::open(db_path)?;
+    
+    let mut stmt = conn.prepare(
+        "SELECT tags.name, COUNT(*) AS uses
+         FROM tags JOIN sample_tags ON sample_tags.tag_id = tags.id
+         GROUP BY tags.id
+         ORDER BY uses DESC, tags.name"
+    )?;
+    
+    let tags = stmt.query_map([], |row| Ok(TagCount { name: row.get(0)?, samples: row.get(1)? }))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    
+    Ok(tags)
+}
+
+/// Which half of a sample pair a pending file is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, Display, Serialize)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum PendingKind {
+    Audio,
+    Json,
+}
+
+impl PendingKind {
+    /// Path of the partner file this pending file is waiting for. A JSON's partner is
+    /// whichever audio file shares its stem, or a WAV while none has arrived.
+    pub fn partner_path(&self, path: &Path, settings: &WatcherConfig) -> PathBuf {
+        match self {
+            PendingKind::Audio => path.with_extension("json"),
+            PendingKind::Json => settings.audio_partner(path).unwrap_or_else(|| path.with_extension("wav")),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct PendingRecord {
+    pub id: i64,
+    pub file_path: String,
+    pub file_kind: PendingKind,
+    pub first_seen: String,
+    pub last_seen: String,
+    pub last_error: Option<String>,
+}
+
+pub fn add_pending_file(db_path: &Path, file_path: &Path, kind: PendingKind) -> Result<()> {
+    let conn = Connection::open(db_path)?;
+    
+    conn.execute(
+        "INSERT INTO pending_pairs (file_path, file_kind) VALUES (?1, ?2)
+         ON CONFLICT(file_path) DO UPDATE SET last_seen = CURRENT_TIMESTAMP",
+        params![file_path.to_string_lossy(), kind.to_string()],
+    )?;
+    
+    Ok(())
+}
+
+pub fn remove_pending_file(db_path: &Path, file_path: &Path) -> Result<bool> {
+    let conn = Connection::open(db_path)?;
+    
+    let removed = conn.execute(
+        "DELETE FROM pending_pairs WHERE file_path = ?1",
+        params![file_path.to_string_lossy()],
+    )?;
+    
+    Ok(removed > 0)
+}
+
+pub fn set_pending_error(db_path: &Path, file_path: &Path, error: &str) -> Result<()> {
+    let conn = Connection::open(db_path)?;
+    
+    conn.execute(
+        "UPDATE pending_pairs SET last_error = ?1 WHERE file_path = ?2",
+        params![error, file_path.to_string_lossy()],
+    )?;
+    
+    Ok(())
+}
+
+fn pending_from_row(row: &rusqlite::Row) -> rusqlite::Result<PendingRecord> {
+    let kind: String = row.get(2)?;
+    Ok(PendingRecord {
+        id: row.get(0)?,
+        file_path: row.get(1)?,
+        file_kind: kind.parse().unwrap_or(PendingKind::Audio),
+        first_seen: row.get(3)?,
+        last_seen: row.get(4)?,
+        last_error: row.get(5)?,
+    })
+}
+
+pub fn get_pending_files(db_path: &Path) -> Result<Vec<PendingRecord>> {
+    let conn = Connection::open(db_path)?;
+    
+    let mut stmt = conn.prepare(
+        "SELECT id, file_path, file_kind, first_seen, last_seen, last_error
+         FROM pending_pairs ORDER BY first_seen, id"
+    )?;
+    
+    let pending = stmt.query_map([], pending_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    
+    Ok(pending)
+}
+
+pub fn get_pending_file(db_path: &Path, id: i64) -> Result<Option<PendingRecord>> {
+    let conn = Connection::open(db_path)?;
+    
+    let mut stmt = conn.prepare(
+        "SELECT id, file_path, file_kind, first_seen, last_seen, last_error
+         FROM pending_pairs WHERE id = ?1"
+    )?;
+    
+    let mut pending_iter = stmt.query_map(params![id], pending_from_row)?;
+    
+    if let Some(record) = pending_iter.next() {
+        return Ok(Some(record?));
+    }
+    
+    Ok(None)
+}
+
+/// Remove pending entries, optionally only those first seen more than `older_than_days` ago
+pub fn purge_pending_files(db_path: &Path, older_than_days: Option<u32>) -> Result<Vec<PendingRecord>> {
+    let mut conn = Connection::open(db_path)?;
+    let cutoff = format!("-{} days", older_than_days.unwrap_or(0));
+    
+    let tx = conn.transaction()?;
+    let purged = {
+        let mut stmt = tx.prepare(
+            "SELECT id, file_path, file_kind, first_seen, last_seen, last_error
+             FROM pending_pairs WHERE first_seen <= datetime('now', ?1)
+             ORDER BY first_seen, id"
+        )?;
+        let purged = stmt.query_map(params![cutoff], pending_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        purged
+    };
+    tx.execute(
+        "DELETE FROM pending_pairs WHERE first_seen <= datetime('now', ?1)",
+        params![cutoff],
+    )?;
+    tx.commit()?;
+    
+    Ok(purged)
+}
+
+/// What an import journal entry is doing with the audio file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, Display, Serialize)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum JournalOperation {
+    /// New sample: move the audio file, then insert its record
+    Import,
+    /// Known sample whose library file was missing: move the audio file, then update the record's path
+    Restore,
+}
+
+/// The last step of an import that is known to have started
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, Display, Serialize)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum JournalState {
+    /// The audio file is being moved into the library; either copy may be incomplete
+    Moving,
+    /// The audio file is in the library, but the database may not know about it yet
+    Moved,
+    /// The database is up to date; only the JSON remains to be removed
+    Recorded,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JournalEntry {
+    pub id: i64,
+    pub operation: JournalOperation,
+    pub state: JournalState,
+    pub audio_path: PathBuf,
+    pub json_path: PathBuf,
+    pub target_path: PathBuf,
+    pub file_hash: String,
+    pub started_at: String,
+    pub updated_at: String,
+}
+
+/// Journal an import before its first step, returning the entry id
+pub fn begin_import(
+    db_path: &Path,
+    operation: JournalOperation,
+    audio_path: &Path,
+    json_path: &Path,
+    target_path: &Path,
+    file_hash: &str,
+) -> Result<i64> {
+    let conn = Connection::open(db_path)?;
+    
+    conn.execute(
+        "INSERT INTO import_journal (operation, state, wav_path, json_path, target_path, file_hash)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            operation.to_string(),
+            JournalState::Moving.to_string(),
+            audio_path.to_string_lossy(),
+            json_path.to_string_lossy(),
+            target_path.to_string_lossy(),
+            file_hash,
+        ],
+    )?;
+    
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn set_import_state(db_path: &Path, id: i64, state: JournalState) -> Result<()> {
+    let conn = Connection::open(db_path)?;
+    
+    let updated = conn.execute(
+        "UPDATE import_journal SET state = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        params![state.to_string(), id],
+    )?;
+    
+    if updated != 1 {
+        return Err(anyhow::anyhow!("Import journal entry {} does not exist", id));
+    }
+    
+    Ok(())
+}
+
+/// Drop a journal entry once its import is complete or fully undone
+pub fn finish_import(db_path: &Path, id: i64) -> Result<()> {
+    let conn = Connection::open(db_path)?;
+    conn.execute("DELETE FROM import_journal WHERE id = ?1", params![id])?;
+    Ok(())
+}
+
+/// Imports that were started but never finished, oldest first
+pub fn get_incomplete_imports(db_path: &Path) -> Result<Vec<JournalEntry>> {
+    let conn = Connection::open(db_path)?;
+    
+    let mut stmt = conn.prepare(
+        "SELECT id, operation, state, wav_path, json_path, target_path, file_hash, started_at, updated_at
+         FROM import_journal ORDER BY id"
+    )?;
+    
+    let entries = stmt.query_map([], |row| {
+        let operation: String = row.get(1)?;
+        let state: String = row.get(2)?;
+        Ok(JournalEntry {
+            id: row.get(0)?,
+            operation: operation.parse().unwrap_or(JournalOperation::Import),
+            // An unreadable state is treated as the earliest one, which is the most cautious
+            state: state.parse().unwrap_or(JournalState::Moving),
+            audio_path: PathBuf::from(row.get::<_, String>(3)?),
+            json_path: PathBuf::from(row.get::<_, String>(4)?),
+            target_path: PathBuf::from(row.get::<_, String>(5)?),
+            file_hash: row.get(6)?,
+            started_at: row.get(7)?,
+            updated_at: row.get(8)?,
+        })
+    })?
+    .collect::<rusqlite::Result<Vec<_>>>()?;
+    
+    Ok(entries)
+}
+
+/// Whether any sample record points at `file_path`
+pub fn sample_exists_at_path(db_path: &Path, file_path: &Path) -> Result<bool> {
+    let conn = Connection::open(db_path)?;
+    let mut stmt = conn.prepare("SELECT 1 FROM samples WHERE file_path = ?1 LIMIT 1")?;
+    Ok(stmt.exists(params![file_path.to_string_lossy()])?)
+}
@@ -0,0 +1,296 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Suffixes browsers give a download until it is complete: Chrome, Firefox, Safari and
+/// a few download managers
+pub const BROWSER_TEMP_EXTENSIONS: &[&str] = &["crdownload", "part", "partial", "download", "tmp"];
+
+/// Whether `path` is a browser's in-progress download rather than a finished file
+pub fn is_browser_temp_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| BROWSER_TEMP_EXTENSIONS.iter().any(|temp| temp.eq_ignore_ascii_case(ext)))
+}
+
+/// Whether a browser is still writing `path` through a temp file next to it, e.g.
+/// Firefox's empty `kick.wav` placeholder beside `kick.wav.part`
+pub fn is_still_downloading(path: &Path) -> bool {
+    BROWSER_TEMP_EXTENSIONS.iter().any(|temp| {
+        let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+        name.push(".");
+        name.push(temp);
+        path.with_file_name(name).exists()
+    })
+}
+
+/// Paths of one audio/JSON pair that have seen events and are waiting to settle
+#[derive(Debug)]
+struct PendingPair {
+    paths: Vec<PathBuf>,
+    /// When the pair is next checked: the quiet period after its latest event
+    due: Instant,
+    /// Sizes seen at the previous check, in the same order as `paths`
+    sizes: Vec<Option<u64>>,
+    /// When a size last changed; a pair stuck unsettled this long is released anyway
+    last_change: Instant,
+}
+
+/// Collects file events per audio/JSON pair and releases a pair once nothing has
+/// happened to it for the quiet period and its files have stopped growing, so a
+/// download is hashed once, after the browser has finished writing it
+#[derive(Debug)]
+pub struct Debouncer {
+    quiet_period: Duration,
+    /// How long a pair may stay empty or beside a browser temp file without any size
+    /// change before it is released to the import, which then fails or quarantines it
+    settle_timeout: Duration,
+    /// Keyed by the path without its extension, so both halves of a pair share an entry
+    pairs: HashMap<PathBuf, PendingPair>,
+}
+
+fn pair_key(path: &Path) -> PathBuf {
+    path.with_extension("")
+}
+
+fn file_size(path: &Path) -> Option<u64> {
+    fs::metadata(path).ok().filter(|m| m.is_file()).map(|m| m.len())
+}
+
+impl Debouncer {
+    pub fn new(quiet_period: Duration, settle_timeout: Duration) -> Self {
+        Self { quiet_period, settle_timeout, pairs: HashMap::new() }
+    }
+    
+    pub fn set_timing(&mut self, quiet_period: Duration, settle_timeout: Duration) {
+        self.quiet_period = quiet_period;
+        self.settle_timeout = settle_timeout;
+    }
+    
+    /// Record an event for `path`, restarting its pair's quiet period
+    pub fn touch(&mut self, path: PathBuf) {
+        let now = Instant::now();
+        let pair = self.pairs.entry(pair_key(&path)).or_insert_with(|| PendingPair {
+            paths: Vec::new(),
+            due: now,
+            sizes: Vec::new(),
+            last_change: now,
+        });
+        pair.due = now + self.quiet_period;
+        let size = file_size(&path);
+        match pair.paths.iter().position(|p| *p == path) {
+            Some(index) => {
+                if pair.sizes[index] != size {
+                    pair.last_change = now;
+                }
+                pair.sizes[index] = size;
+            }
+            None => {
+                pair.paths.push(path);
+                pair.sizes.push(size);
+                pair.last_change = now;
+            }
+        }
+    }
+    
+    /// Stop tracking a path that was removed or renamed away
+    pub fn forget(&mut self, path: &Path) {
+        let key = pair_key(path);
+        if let Some(pair) = self.pairs.get_mut(&key) {
+            if let Some(index) = pair.paths.iter().position(|p| p == path) {
+                pair.paths.remove(index);
+                pair.sizes.remove(index);
+            }
+            if pair.paths.is_empty() {
+                self.pairs.remove(&key);
+            }
+        }
+    }
+    
+    /// When the next pair is due to be checked
+    pub fn next_due(&self) -> Option<Instant> {
+        self.pairs.values().map(|pair| pair.due).min()
+    }
+    
+    /// Pairs that are due and whose files are complete: unchanged in size since the
+    /// last event or check, not empty, and without a browser temp file beside them.
+    /// Pairs still changing are checked again after another quiet period; files that
+    /// have disappeared are dropped. A pair that has gone the settle timeout without
+    /// growing but is still empty or beside a temp file (a cancelled download) is
+    /// released as it is, so it ends up failing or pending instead of waiting forever.
+    pub fn take_ready(&mut self) -> Vec<Vec<PathBuf>> {
+        let now = Instant::now();
+        let due: Vec<PathBuf> = self.pairs.iter()
+            .filter(|(_, pair)| pair.due <= now)
+            .map(|(key, _)| key.clone())
+            .collect();
+        
+        let mut ready = Vec::new();
+        for key in due {
+            let Some(mut pair) = self.pairs.remove(&key) else { continue };
+            
+            let sizes: Vec<Option<u64>> = pair.paths.iter().map(|path| file_size(path)).collect();
+            let mut paths = Vec::new();
+            let mut new_sizes = Vec::new();
+            let mut settled = true;
+            let mut changed = false;
+            for ((path, previous), size) in pair.paths.into_iter().zip(pair.sizes).zip(sizes) {
+                let Some(size) = size else { continue };
+                if previous != Some(size) {
+                    changed = true;
+                }
+                if size == 0 || is_still_downloading(&path) {
+                    settled = false;
+                }
+                paths.push(path);
+                new_sizes.push(Some(size));
+            }
+            
+            if paths.is_empty() {
+                continue;
+            }
+            if changed {
+                pair.last_change = now;
+            }
+            if settled && !changed {
+                ready.push(paths);
+            } else if !changed && now.duration_since(pair.last_change) >= self.settle_timeout {
+                eprintln!("⚠️  {:?} has not finished downloading after {}s, processing it anyway", paths, self.settle_timeout.as_secs());
+                ready.push(paths);
+            } else {
+                pair.paths = paths;
+                pair.sizes = new_sizes;
+                pair.due = now + self.quiet_period;
+                self.pairs.insert(key, pair);
+            }
+        }
+        
+        ready
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread::sleep;
+    
+    const SETTLE_TIMEOUT: Duration = Duration::from_millis(100);
+    
+    /// No quiet period, so every `take_ready` checks every pair
+    fn debouncer() -> Debouncer {
+        Debouncer::new(Duration::ZERO, SETTLE_TIMEOUT)
+    }
+    
+    fn write(path: &Path, len: usize) {
+        fs::write(path, vec![1u8; len]).unwrap();
+    }
+    
+    #[test]
+    fn releases_a_pair_once_it_stops_changing() {
+        let dir = tempfile::tempdir().unwrap();
+        let (audio, json) = (dir.path().join("kick.wav"), dir.path().join("kick.json"));
+        write(&audio, 100);
+        write(&json, 10);
+        
+        let mut debouncer = debouncer();
+        debouncer.touch(audio.clone());
+        debouncer.touch(json.clone());
+        let ready = debouncer.take_ready();
+        assert_eq!(ready.len(), 1);
+        assert_eq!(ready[0].len(), 2);
+        assert!(ready[0].contains(&audio) && ready[0].contains(&json));
+        assert_eq!(debouncer.next_due(), None);
+    }
+    
+    #[test]
+    fn holds_a_file_that_keeps_growing() {
+        let dir = tempfile::tempdir().unwrap();
+        let audio = dir.path().join("kick.wav");
+        write(&audio, 100);
+        
+        let mut debouncer = debouncer();
+        debouncer.touch(audio.clone());
+        // Growing for longer than the settle timeout never counts as stuck
+        for len in (200..=1000).step_by(100) {
+            write(&audio, len);
+            assert!(debouncer.take_ready().is_empty());
+            sleep(SETTLE_TIMEOUT / 4);
+        }
+        assert!(debouncer.next_due().is_some());
+        assert_eq!(debouncer.take_ready(), vec![vec![audio]]);
+    }
+    
+    #[test]
+    fn releases_an_empty_file_after_the_settle_timeout() {
+        let dir = tempfile::tempdir().unwrap();
+        let (audio, json) = (dir.path().join("kick.wav"), dir.path().join("kick.json"));
+        write(&audio, 0);
+        write(&json, 10);
+        
+        let mut debouncer = debouncer();
+        debouncer.touch(audio.clone());
+        debouncer.touch(json.clone());
+        assert!(debouncer.take_ready().is_empty());
+        // More events without any growth don't hold it back
+        debouncer.touch(audio.clone());
+        assert!(debouncer.take_ready().is_empty());
+        
+        sleep(SETTLE_TIMEOUT);
+        let ready = debouncer.take_ready();
+        assert_eq!(ready.len(), 1);
+        assert!(ready[0].contains(&audio) && ready[0].contains(&json));
+    }
+    
+    #[test]
+    fn waits_for_a_browser_temp_file_beside_it() {
+        let dir = tempfile::tempdir().unwrap();
+        let audio = dir.path().join("kick.wav");
+        let temp = dir.path().join("kick.wav.part");
+        write(&audio, 100);
+        write(&temp, 50);
+        
+        let mut debouncer = debouncer();
+        debouncer.touch(audio.clone());
+        assert!(debouncer.take_ready().is_empty());
+        
+        // Once the browser is done the pair goes through straight away
+        fs::remove_file(&temp).unwrap();
+        assert_eq!(debouncer.take_ready(), vec![vec![audio.clone()]]);
+        
+        // A stale temp file from a cancelled download only delays it
+        write(&temp, 50);
+        debouncer.touch(audio.clone());
+        assert!(debouncer.take_ready().is_empty());
+        sleep(SETTLE_TIMEOUT);
+        assert_eq!(debouncer.take_ready(), vec![vec![audio]]);
+    }
+    
+    #[test]
+    fn drops_forgotten_and_vanished_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let (audio, json) = (dir.path().join("kick.wav"), dir.path().join("kick.json"));
+        write(&audio, 0);
+        write(&json, 10);
+        
+        let mut debouncer = debouncer();
+        debouncer.touch(audio.clone());
+        debouncer.touch(json.clone());
+        assert!(debouncer.take_ready().is_empty());
+        
+        // The empty audio was renamed away mid-wait: the JSON alone is released
+        debouncer.forget(&audio);
+        assert_eq!(debouncer.take_ready(), vec![vec![json.clone()]]);
+        
+        debouncer.touch(json.clone());
+        debouncer.forget(&json);
+        assert_eq!(debouncer.next_due(), None);
+        
+        // A file deleted without an event is dropped at the next check
+        debouncer.touch(json.clone());
+        fs::remove_file(&json).unwrap();
+        assert!(debouncer.take_ready().is_empty());
+        assert_eq!(debouncer.next_due(), None);
+    }
+}
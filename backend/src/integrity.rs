@@ -0,0 +1,214 @@
+use anyhow::Result;
+use md5::Md5;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+use strum::{Display, EnumString};
+
+use crate::metadata::SampleMetadata;
+
+/// Read size for streaming a file through the hashers
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// What an import does when a WAV doesn't match the size or hash Splice declared for it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, EnumString, Display)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum MismatchAction {
+    /// Move the WAV and its JSON to the quarantine directory
+    Quarantine,
+    /// Treat it as a download still in progress: fail the attempt so it is retried after
+    /// the retry delay, leaving the files in the watch directory. Once the retries run out
+    /// the pair is quarantined as `verification` (or `malformed`), unless
+    /// `quarantine_failures` is off.
+    #[default]
+    RetryWait,
+    /// Log the mismatch and import the file anyway
+    Accept,
+}
+
+/// Size and digests of a file, computed in a single pass
+#[derive(Debug, Clone)]
+pub struct FileDigest {
+    pub size: u64,
+    /// Hex SHA-256, the key samples are stored under
+    pub sha256: String,
+    /// Hex MD5, only used to compare against hashes Splice declares in that format
+    pub md5: String,
+}
+
+/// Feed a file to `consume` in fixed-size chunks, returning its size
+fn stream_file(file_path: &Path, mut consume: impl FnMut(&[u8])) -> Result<u64> {
+    let mut file = fs::File::open(file_path)
+        .map_err(|e| anyhow::anyhow!("Failed to open {:?} for hashing: {}", file_path, e))?;
+    
+    let mut size = 0u64;
+    let mut buffer = vec![0; BUFFER_SIZE];
+    loop {
+        let read = file.read(&mut buffer)
+            .map_err(|e| anyhow::anyhow!("Failed to read file for hashing: {}", e))?;
+        if read == 0 {
+            return Ok(size);
+        }
+        consume(&buffer[..read]);
+        size += read as u64;
+    }
+}
+
+/// Stream a file through SHA-256 and MD5 without holding it in memory
+pub fn digest_file(file_path: &Path) -> Result<FileDigest> {
+    let mut sha256 = Sha256::new();
+    let mut md5 = Md5::new();
+    let size = stream_file(file_path, |chunk| {
+        sha256.update(chunk);
+        md5.update(chunk);
+    })?;
+    
+    Ok(FileDigest {
+        size,
+        sha256: format!("{:x}", sha256.finalize()),
+        md5: format!("{:x}", md5.finalize()),
+    })
+}
+
+/// Streaming SHA-256 of a file's contents
+pub fn sha256_file(file_path: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    stream_file(file_path, |chunk| hasher.update(chunk))?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Streaming SHA-256 of `len` bytes of a file starting at `offset`
+pub fn sha256_range(file_path: &Path, offset: u64, len: u64) -> Result<String> {
+    let mut file = fs::File::open(file_path)
+        .map_err(|e| anyhow::anyhow!("Failed to open {:?} for hashing: {}", file_path, e))?;
+    file.seek(SeekFrom::Start(offset))?;
+    
+    let mut hasher = Sha256::new();
+    let copied = io::copy(&mut file.take(len), &mut hasher)?;
+    if copied != len {
+        anyhow::bail!("{:?} ended {} bytes into a {}-byte range", file_path, copied, len);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+impl FileDigest {
+    /// Whether `declared` names this file. The algorithm is told apart by length;
+    /// `None` when the declared value isn't a hash we can compute.
+    fn matches_hash(&self, declared: &str) -> Option<bool> {
+        let declared = declared.trim().to_lowercase();
+        if !declared.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        match declared.len() {
+            64 => Some(declared == self.sha256),
+            32 => Some(declared == self.md5),
+            _ => None,
+        }
+    }
+    
+    /// Problems with one declared size/hash pair, or `None` when neither can be checked
+    fn compare(&self, size: u64, hash: &str) -> Option<Vec<String>> {
+        let hash_matches = self.matches_hash(hash);
+        if size == 0 && hash_matches.is_none() {
+            return None;
+        }
+        
+        let mut problems = Vec::new();
+        if size > 0 && size != self.size {
+            problems.push(format!("size is {} bytes, expected {}", self.size, size));
+        }
+        if hash_matches == Some(false) {
+            problems.push(format!("hash does not match {}", hash));
+        }
+        Some(problems)
+    }
+    
+    /// Check the file against what Splice declared for the decoded audio. A file
+    /// whose hash matches the original download instead is accepted as well. When the
+    /// decoded size and hash are missing or in an unknown format, the download's size
+    /// and hash are checked instead; only a file with nothing usable declared at all
+    /// goes unchecked.
+    pub fn verify(&self, metadata: &SampleMetadata) -> std::result::Result<(), String> {
+        let encoding = &metadata.sample.encoding;
+        let original = &metadata.sample;
+        
+        let problems = match self.compare(encoding.decoded_size, &encoding.decoded_hash) {
+            Some(problems) if problems.is_empty() => return Ok(()),
+            Some(problems) => problems,
+            None => match self.compare(original.file_size, &original.file_hash) {
+                Some(problems) if !problems.is_empty() => return Err(problems.join(", ")),
+                _ => return Ok(()),
+            },
+        };
+        
+        let is_original = self.matches_hash(&original.file_hash) == Some(true)
+            && (original.file_size == 0 || original.file_size == self.size);
+        if is_original {
+            Ok(())
+        } else {
+            Err(problems.join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata;
+    
+    fn digest() -> FileDigest {
+        FileDigest {
+            size: 1000,
+            sha256: "ab".repeat(32),
+            md5: "cd".repeat(16),
+        }
+    }
+    
+    /// Metadata declaring the decoded and the downloaded file
+    fn declaring(decoded: (u64, &str), download: (u64, &str)) -> SampleMetadata {
+        let mut metadata = metadata::test_metadata();
+        metadata.sample.encoding.decoded_size = decoded.0;
+        metadata.sample.encoding.decoded_hash = decoded.1.to_string();
+        metadata.sample.file_size = download.0;
+        metadata.sample.file_hash = download.1.to_string();
+        metadata
+    }
+    
+    #[test]
+    fn accepts_matching_decoded_declarations() {
+        let sha256 = "ab".repeat(32);
+        let md5 = "CD".repeat(16);
+        assert_eq!(digest().verify(&declaring((1000, &sha256), (0, ""))), Ok(()));
+        assert_eq!(digest().verify(&declaring((0, &md5), (5, "bogus"))), Ok(()));
+    }
+    
+    #[test]
+    fn rejects_truncated_decoded_audio() {
+        let problem = digest().verify(&declaring((2000, ""), (0, ""))).unwrap_err();
+        assert_eq!(problem, "size is 1000 bytes, expected 2000");
+    }
+    
+    #[test]
+    fn accepts_the_original_download_when_decoded_differs() {
+        let sha256 = "ab".repeat(32);
+        assert_eq!(digest().verify(&declaring((2000, &"ef".repeat(32)), (1000, &sha256))), Ok(()));
+    }
+    
+    #[test]
+    fn falls_back_to_the_download_declarations() {
+        let sha256 = "ab".repeat(32);
+        // No usable decoded pair: the download's size and hash are what gets checked
+        assert_eq!(digest().verify(&declaring((0, "n/a"), (1000, &sha256))), Ok(()));
+        assert!(digest().verify(&declaring((0, ""), (1500, ""))).unwrap_err().contains("expected 1500"));
+        assert!(digest().verify(&declaring((0, "n/a"), (0, &"12".repeat(32)))).unwrap_err().contains("hash does not match"));
+    }
+    
+    #[test]
+    fn accepts_files_without_usable_declarations() {
+        assert_eq!(digest().verify(&declaring((0, ""), (0, ""))), Ok(()));
+        assert_eq!(digest().verify(&declaring((0, "xyz"), (0, "not-a-hash"))), Ok(()));
+    }
+}
@@ -0,0 +1,324 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use strum::{Display, EnumString};
+
+use crate::metadata::{sanitize_filename, AudioFormat, BitwigCategory, SampleMetaData};
+
+/// Layout used when no template is configured: `<category>/<pack>/<filename>`
+pub const DEFAULT_TEMPLATE: &str = "{category}/{pack}/{filename}";
+
+/// Rendered in place of a missing value when the placeholder has no fallback
+const MISSING_VALUE: &str = "Unknown";
+
+/// Placeholders that don't map one-to-one onto a `SampleMetaData` field
+const DERIVED_PLACEHOLDERS: &[&str] = &["category", "stem", "ext", "tag"];
+
+/// Short names for commonly used fields
+const ALIASES: &[(&str, &str)] = &[
+    ("key", "audio_key"),
+    ("chord", "chord_type"),
+    ("pack", "pack.name"),
+    ("provider", "provider_name"),
+    ("genre", "pack.main_genre"),
+    ("type", "sample_type"),
+];
+
+/// Every `SampleMetaData` field, with `Pack` fields under `pack.`
+const FIELD_PLACEHOLDERS: &[&str] = &[
+    "audio_key", "bpm", "chord_type", "dir", "duration", "file_hash", "filename",
+    "preview_url", "price", "provider_name", "provider_uuid", "provider_permalink",
+    "sample_type", "tags", "waveform_url", "published", "popularity", "trending",
+    "published_at", "purchased_at", "sas_id", "liked", "licensed", "asset_uuid",
+    "pack.uuid", "pack.name", "pack.description", "pack.provider_name",
+    "pack.provider_description", "pack.cover_url", "pack.banner_url", "pack.main_genre",
+    "pack.sample_count", "pack.preset_count", "pack.permalink", "pack.is_archived",
+];
+
+/// What an import does when its library path is already taken by another file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, EnumString, Display)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum CollisionPolicy {
+    /// Append the first eight characters of the sample's hash: `kick-1a2b3c4d.wav`
+    #[default]
+    HashSuffix,
+    /// Append the first free number: `kick-2.wav`, `kick-3.wav`, ...
+    Counter,
+    /// Leave the download in the watch directory
+    Skip,
+    /// Replace the existing file only when it has the same contents (or, for a WAV
+    /// tagged since import, the same audio), otherwise skip
+    OverwriteSameHash,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Literal(String),
+    Placeholder {
+        field: String,
+        fallback: Option<String>,
+    },
+}
+
+/// A validated library path template such as `{category}/{bpm|nobpm}bpm/{pack}/{filename}`
+#[derive(Debug, Clone)]
+pub struct PathTemplate {
+    template: String,
+    segments: Vec<Vec<Part>>,
+}
+
+impl Default for PathTemplate {
+    fn default() -> Self {
+        Self::parse(DEFAULT_TEMPLATE).expect("default layout template is valid")
+    }
+}
+
+impl std::fmt::Display for PathTemplate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.template)
+    }
+}
+
+fn resolve_alias(name: &str) -> &str {
+    ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == name)
+        .map(|(_, field)| *field)
+        .unwrap_or(name)
+}
+
+fn is_known_placeholder(name: &str) -> bool {
+    let field = resolve_alias(name);
+    DERIVED_PLACEHOLDERS.contains(&field) || FIELD_PLACEHOLDERS.contains(&field)
+}
+
+/// Names accepted inside `{...}`, for error messages and help output
+pub fn known_placeholders() -> Vec<&'static str> {
+    let mut names: Vec<&'static str> = DERIVED_PLACEHOLDERS.to_vec();
+    names.extend(ALIASES.iter().map(|(alias, _)| *alias));
+    names.extend(FIELD_PLACEHOLDERS);
+    names
+}
+
+fn parse_segment(segment: &str) -> Result<Vec<Part>> {
+    let mut parts = Vec::new();
+    let mut literal = String::new();
+    let mut chars = segment.chars();
+    
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => {
+                if !literal.is_empty() {
+                    parts.push(Part::Literal(std::mem::take(&mut literal)));
+                }
+                
+                let mut inner = String::new();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    match c {
+                        '}' => {
+                            closed = true;
+                            break;
+                        }
+                        '{' => anyhow::bail!("nested '{{' in segment '{}'", segment),
+                        c => inner.push(c),
+                    }
+                }
+                if !closed {
+                    anyhow::bail!("unclosed '{{' in segment '{}'", segment);
+                }
+                
+                let (name, fallback) = match inner.split_once('|') {
+                    Some((name, fallback)) => (name.trim(), Some(fallback.to_string())),
+                    None => (inner.trim(), None),
+                };
+                if !is_known_placeholder(name) {
+                    anyhow::bail!("unknown placeholder '{{{}}}'", name);
+                }
+                if let Some(fallback) = &fallback {
+                    if fallback.contains(['/', '\\']) || fallback == ".." {
+                        anyhow::bail!("fallback '{}' in '{{{}}}' must not contain path separators", fallback, inner);
+                    }
+                }
+                
+                parts.push(Part::Placeholder {
+                    field: resolve_alias(name).to_string(),
+                    fallback,
+                });
+            }
+            '}' => anyhow::bail!("unmatched '}}' in segment '{}'", segment),
+            c => literal.push(c),
+        }
+    }
+    
+    if !literal.is_empty() {
+        parts.push(Part::Literal(literal));
+    }
+    
+    Ok(parts)
+}
+
+/// Look up a dotted field path (e.g. `pack.main_genre`) in the serialized metadata
+fn lookup<'a>(value: &'a Value, field: &str) -> Option<&'a Value> {
+    field.split('.').try_fold(value, |value, key| value.get(key))
+}
+
+fn value_to_string(value: &Value) -> Option<String> {
+    let text = match value {
+        Value::Null => return None,
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items
+            .iter()
+            .filter_map(value_to_string)
+            .collect::<Vec<_>>()
+            .join(", "),
+        other => other.to_string(),
+    };
+    
+    if text.trim().is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
+impl PathTemplate {
+    /// Parse and validate a template, rejecting anything that could leave the library root
+    pub fn parse(template: &str) -> Result<Self> {
+        let trimmed = template.trim();
+        if trimmed.is_empty() {
+            anyhow::bail!("layout template is empty");
+        }
+        if trimmed.starts_with('/') || trimmed.starts_with('~') || trimmed.contains('\\') {
+            anyhow::bail!("layout template '{}' must be a relative path using '/' separators", template);
+        }
+        
+        let mut segments = Vec::new();
+        for segment in trimmed.split('/') {
+            if segment.is_empty() {
+                anyhow::bail!("layout template '{}' contains an empty path segment", template);
+            }
+            if segment == "." || segment == ".." {
+                anyhow::bail!("layout template '{}' must not contain '.' or '..' segments", template);
+            }
+            let parts = parse_segment(segment)
+                .map_err(|e| anyhow::anyhow!("Invalid layout template '{}': {}", template, e))?;
+            segments.push(parts);
+        }
+        
+        let file_names: Vec<&Option<String>> = segments.last().into_iter().flatten()
+            .filter_map(|part| match part {
+                Part::Placeholder { field, fallback } if field == "filename" || field == "stem" => Some(fallback),
+                _ => None,
+            })
+            .collect();
+        if file_names.is_empty() {
+            anyhow::bail!("layout template '{}' must end with a segment containing {{filename}} or {{stem}}", template);
+        }
+        // An empty file name segment would be dropped, leaving the sample named after its folder
+        if file_names.iter().any(|fallback| fallback.as_deref().is_some_and(|fallback| sanitize_filename(fallback).is_empty())) {
+            anyhow::bail!("layout template '{}' must not give {{filename}} or {{stem}} an empty fallback", template);
+        }
+        
+        Ok(Self {
+            template: trimmed.to_string(),
+            segments,
+        })
+    }
+    
+    fn placeholder_value(&self, field: &str, sample: &SampleMetaData, category: &BitwigCategory, fields: &Value) -> Option<String> {
+        let filename = Path::new(&sample.filename);
+        match field {
+            "category" => Some(category.as_str()),
+            "stem" => filename.file_stem().map(|s| s.to_string_lossy().to_string()),
+            "ext" => filename.extension().map(|s| s.to_string_lossy().to_string()),
+            "tag" => sample.tags.first().cloned(),
+            field => lookup(fields, field).and_then(value_to_string),
+        }
+    }
+    
+    /// Render the template for a sample into a path relative to the library root
+    pub fn render(&self, sample: &SampleMetaData, category: &BitwigCategory) -> PathBuf {
+        let fields = serde_json::to_value(sample).unwrap_or(Value::Null);
+        let mut path = PathBuf::new();
+        
+        for parts in &self.segments {
+            let mut segment = String::new();
+            for part in parts {
+                match part {
+                    Part::Literal(text) => segment.push_str(text),
+                    Part::Placeholder { field, fallback } => {
+                        // A value that sanitizes away (e.g. a filename of only "?") counts as missing
+                        let value = self.placeholder_value(field, sample, category, &fields)
+                            .map(|value| sanitize_filename(&value))
+                            .filter(|value| !value.is_empty())
+                            .or_else(|| fallback.as_deref().map(sanitize_filename))
+                            .unwrap_or_else(|| MISSING_VALUE.to_string());
+                        segment.push_str(&value);
+                    }
+                }
+            }
+            
+            // Segments that render empty (e.g. `{key|}` without a key) are dropped,
+            // and values like ".." can never become a traversal
+            let segment = sanitize_filename(&segment);
+            match segment.as_str() {
+                "" => continue,
+                "." | ".." => path.push("_"),
+                _ => path.push(segment),
+            }
+        }
+        
+        path
+    }
+}
+
+/// Give a rendered library path the extension of the audio file it will hold. A
+/// different audio extension (Splice's filename may say `.wav` for a FLAC) is
+/// replaced; anything else, such as a `{stem}` with dots in it, is kept and appended to.
+pub fn with_audio_extension(path: PathBuf, extension: &OsStr) -> PathBuf {
+    match path.extension() {
+        Some(current) if current.eq_ignore_ascii_case(extension) => path,
+        Some(_) if AudioFormat::from_path(&path).is_some() => path.with_extension(extension),
+        _ => {
+            let mut file_name = path.file_name().map(OsStr::to_os_string).unwrap_or_default();
+            file_name.push(".");
+            file_name.push(extension);
+            path.with_file_name(file_name)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata;
+    
+    fn render(template: &str, filename: &str) -> PathBuf {
+        let mut sample = metadata::test_metadata().sample_meta_data;
+        sample.filename = filename.to_string();
+        sample.audio_key = None;
+        PathTemplate::parse(template).unwrap().render(&sample, &BitwigCategory::Kick)
+    }
+    
+    #[test]
+    fn rejects_an_empty_file_name_fallback() {
+        for template in ["{pack}/{filename|}", "{pack}/{stem| }.{ext}", "{stem|?}", "{filename|x}{stem|}"] {
+            let error = PathTemplate::parse(template).expect_err(template).to_string();
+            assert!(error.contains("empty fallback"), "{}: {}", template, error);
+        }
+        assert!(PathTemplate::parse("{pack}/{stem|untitled}.{ext}").is_ok());
+        assert!(PathTemplate::parse("{key|}/{filename}").is_ok());
+    }
+    
+    #[test]
+    fn drops_empty_folders_but_never_the_file_name() {
+        assert_eq!(render("{category}/{key|}/{filename}", "kick_one.wav"), PathBuf::from("Kick/kick_one.wav"));
+        assert_eq!(render("{category}/{stem|untitled}.{ext}", "?.wav"), PathBuf::from("Kick/untitled.wav"));
+        assert_eq!(render("{category}/{filename}", "??"), PathBuf::from("Kick/Unknown"));
+        assert_eq!(render("{category}/{filename|untitled.wav}", "*"), PathBuf::from("Kick/untitled.wav"));
+    }
+}
@@ -0,0 +1,613 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::f64::consts::PI;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{watch, Notify};
+
+use crate::config::Config;
+use crate::db::{self, SampleRecord};
+use crate::metadata::AudioFormat;
+use crate::output;
+use crate::wav;
+
+/// EBU R128 gating: 400 ms blocks overlapping by 75%, so a new block every 100 ms
+const BLOCK_STEPS: usize = 4;
+const STEPS_PER_SECOND: f64 = 10.0;
+/// Blocks quieter than this never count towards the integrated loudness
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+/// Blocks more than this far below the ungated loudness are dropped as well
+const RELATIVE_GATE_LU: f64 = -10.0;
+/// BS.1770 offset that makes a 0 dBFS 1 kHz sine read -3.01 LUFS
+const LOUDNESS_OFFSET: f64 = -0.691;
+
+/// True peak is found by interpolating 4x between samples with a 48-tap filter, as BS.1770 suggests
+const OVERSAMPLING: usize = 4;
+const TAPS_PER_PHASE: usize = 12;
+
+/// Files the background job hasn't measured yet are looked for again this often once it has caught up
+const JOB_POLL: Duration = Duration::from_secs(60);
+
+/// Loudness and level measurements of one file; levels are `None` for digital silence
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+pub struct Loudness {
+    /// EBU R128 integrated loudness, `None` if every block falls below the absolute gate
+    pub integrated_lufs: Option<f64>,
+    pub sample_peak_dbfs: Option<f64>,
+    /// Peak of the 4x oversampled signal, catching overs between samples
+    pub true_peak_dbtp: Option<f64>,
+    /// Unweighted RMS over all channels
+    pub rms_dbfs: Option<f64>,
+    /// Mean sample value over all channels, from -1.0 to 1.0
+    pub dc_offset: f64,
+}
+
+impl Loudness {
+    /// One-line summary for progress output
+    pub fn describe(&self) -> String {
+        let level = |value: Option<f64>, unit: &str| {
+            value.map_or_else(|| format!("-inf {}", unit), |v| format!("{:.1} {}", v, unit))
+        };
+        if self.sample_peak_dbfs.is_none() {
+            return "silent".to_string();
+        }
+        format!(
+            "{}, peak {}, true peak {}, RMS {}, DC {:+.4}",
+            level(self.integrated_lufs, "LUFS"),
+            level(self.sample_peak_dbfs, "dBFS"),
+            level(self.true_peak_dbtp, "dBTP"),
+            level(self.rms_dbfs, "dBFS"),
+            self.dc_offset
+        )
+    }
+}
+
+/// Second-order IIR section in direct form I
+#[derive(Debug, Clone, Copy)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    x: [f64; 2],
+    y: [f64; 2],
+}
+
+impl Biquad {
+    fn new(b: [f64; 3], a: [f64; 2]) -> Self {
+        Self { b, a, x: [0.0; 2], y: [0.0; 2] }
+    }
+    
+    fn process(&mut self, input: f64) -> f64 {
+        let output = self.b[0] * input + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[0] * self.y[0] - self.a[1] * self.y[1];
+        self.x = [input, self.x[0]];
+        self.y = [output, self.y[0]];
+        output
+    }
+}
+
+/// The BS.1770 K-weighting filter (a high shelf modelling the head, then a high pass),
+/// with coefficients derived for any sample rate the way libebur128 does
+fn k_weighting(sample_rate: u32) -> [Biquad; 2] {
+    let rate = sample_rate as f64;
+    
+    let (f0, gain_db, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+    let k = (PI * f0 / rate).tan();
+    let vh = 10f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad::new(
+        [(vh + vb * k / q + k * k) / a0, 2.0 * (k * k - vh) / a0, (vh - vb * k / q + k * k) / a0],
+        [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    );
+    
+    let (f0, q) = (38.13547087602444, 0.5003270373238773);
+    let k = (PI * f0 / rate).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad::new([1.0, -2.0, 1.0], [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0]);
+    
+    [shelf, high_pass]
+}
+
+/// BS.1770 channel weights: surrounds count 1.41 and the LFE not at all in a 5.1 file
+/// (L, R, C, LFE, Ls, Rs); everything else counts fully
+fn channel_weights(channels: u16) -> Vec<f64> {
+    if channels == 6 {
+        vec![1.0, 1.0, 1.0, 0.0, 1.41, 1.41]
+    } else {
+        vec![1.0; channels as usize]
+    }
+}
+
+/// Hann-windowed sinc that interpolates `OVERSAMPLING` points per input sample, split into
+/// one set of taps per output phase
+fn interpolation_phases() -> Vec<[f64; TAPS_PER_PHASE]> {
+    let length = OVERSAMPLING * TAPS_PER_PHASE;
+    let centre = (length - 1) as f64 / 2.0;
+    (0..OVERSAMPLING)
+        .map(|phase| {
+            let mut taps = [0.0; TAPS_PER_PHASE];
+            for (k, tap) in taps.iter_mut().enumerate() {
+                let n = phase + OVERSAMPLING * k;
+                let t = (n as f64 - centre) / OVERSAMPLING as f64;
+                let sinc = if t == 0.0 { 1.0 } else { (PI * t).sin() / (PI * t) };
+                let window = 0.5 - 0.5 * (2.0 * PI * (n as f64 + 0.5) / length as f64).cos();
+                *tap = sinc * window;
+            }
+            taps
+        })
+        .collect()
+}
+
+fn to_db(amplitude: f64) -> Option<f64> {
+    (amplitude > 0.0).then(|| 20.0 * amplitude.log10())
+}
+
+/// Loudness of a mean weighted power
+fn power_to_lufs(power: f64) -> f64 {
+    LOUDNESS_OFFSET + 10.0 * power.log10()
+}
+
+/// EBU R128 integrated loudness over the weighted block powers
+fn gated_loudness(blocks: &[f64]) -> Option<f64> {
+    let absolute = 10f64.powf((ABSOLUTE_GATE_LUFS - LOUDNESS_OFFSET) / 10.0);
+    let loud: Vec<f64> = blocks.iter().copied().filter(|&power| power > absolute).collect();
+    if loud.is_empty() {
+        return None;
+    }
+    
+    let ungated = power_to_lufs(loud.iter().sum::<f64>() / loud.len() as f64);
+    let relative = 10f64.powf((ungated + RELATIVE_GATE_LU - LOUDNESS_OFFSET) / 10.0);
+    let kept: Vec<f64> = loud.into_iter().filter(|&power| power > relative).collect();
+    Some(power_to_lufs(kept.iter().sum::<f64>() / kept.len() as f64))
+}
+
+/// Measure the loudness and levels of a PCM or float WAV file
+pub fn measure_file(path: &Path) -> Result<Loudness> {
+    let info = wav::read_info(path)?;
+    let channels = info.channels as usize;
+    let weights = channel_weights(info.channels);
+    let phases = interpolation_phases();
+    let step = ((info.sample_rate as f64 / STEPS_PER_SECOND).round() as usize).max(1);
+    
+    let mut filters = vec![k_weighting(info.sample_rate); channels];
+    let mut history = vec![[0f64; TAPS_PER_PHASE]; channels];
+    // Weighted energy of each completed 100 ms step, and of the step in progress
+    let mut steps: Vec<f64> = Vec::new();
+    let mut step_energy = 0.0;
+    let mut step_frames = 0;
+    let mut total_energy = 0.0;
+    
+    let (mut sum, mut sum_squares, mut samples) = (0.0, 0.0, 0u64);
+    let (mut sample_peak, mut true_peak) = (0f64, 0f64);
+    
+    wav::for_each_frame(path, None, |frame| {
+        let mut weighted = 0.0;
+        for (channel, &sample) in frame.iter().enumerate() {
+            let sample = sample as f64;
+            sum += sample;
+            sum_squares += sample * sample;
+            sample_peak = sample_peak.max(sample.abs());
+            
+            let [shelf, high_pass] = &mut filters[channel];
+            let filtered = high_pass.process(shelf.process(sample));
+            weighted += weights[channel] * filtered * filtered;
+            
+            let recent = &mut history[channel];
+            recent.copy_within(0..TAPS_PER_PHASE - 1, 1);
+            recent[0] = sample;
+            for taps in &phases {
+                let value: f64 = taps.iter().zip(recent.iter()).map(|(tap, x)| tap * x).sum();
+                true_peak = true_peak.max(value.abs());
+            }
+        }
+        samples += frame.len() as u64;
+        total_energy += weighted;
+        
+        step_energy += weighted;
+        step_frames += 1;
+        if step_frames == step {
+            steps.push(step_energy);
+            step_energy = 0.0;
+            step_frames = 0;
+        }
+    })?;
+    
+    // Each gating block is four consecutive steps; files shorter than one block
+    // are measured as a single block over their whole length
+    let blocks: Vec<f64> = if steps.len() >= BLOCK_STEPS {
+        steps
+            .windows(BLOCK_STEPS)
+            .map(|window| window.iter().sum::<f64>() / (BLOCK_STEPS * step) as f64)
+            .collect()
+    } else if info.frames > 0 {
+        vec![total_energy / info.frames as f64]
+    } else {
+        Vec::new()
+    };
+    
+    let samples = samples.max(1) as f64;
+    Ok(Loudness {
+        integrated_lufs: gated_loudness(&blocks),
+        sample_peak_dbfs: to_db(sample_peak),
+        // The interpolated signal passes through every sample, so it is never below the sample peak
+        true_peak_dbtp: to_db(true_peak.max(sample_peak)),
+        rms_dbfs: to_db((sum_squares / samples).sqrt()),
+        dc_offset: sum / samples,
+    })
+}
+
+/// Result of measuring the library
+#[derive(Debug, Default, Serialize)]
+pub struct LoudnessSummary {
+    pub measured: usize,
+    /// Samples measured before, left alone without `--force`
+    pub already_measured: usize,
+    /// Samples in formats other than WAV
+    pub skipped: usize,
+    /// Records whose library file no longer exists
+    pub missing: Vec<PathBuf>,
+    #[serde(serialize_with = "output::failures")]
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+impl output::PlainText for LoudnessSummary {}
+
+/// Library samples still to be measured, WAVs only
+fn unmeasured_wavs(database_path: &Path) -> Result<Vec<SampleRecord>> {
+    Ok(db::get_samples_without_loudness(database_path)?
+        .into_iter()
+        .filter(|record| AudioFormat::from_path(Path::new(&record.file_path)) == Some(AudioFormat::Wav))
+        .collect())
+}
+
+/// Measure one library sample and store the result
+fn measure_sample(database_path: &Path, record: &SampleRecord) -> Result<Loudness> {
+    let path = Path::new(&record.file_path);
+    if !path.is_file() {
+        anyhow::bail!("file is missing from the library");
+    }
+    let loudness = measure_file(path)?;
+    db::store_loudness(database_path, &record.file_hash, &loudness)?;
+    Ok(loudness)
+}
+
+/// Measure every WAV in the library that hasn't been yet, or all of them with `force`.
+/// Each result is stored as soon as it is known, so an interrupted run resumes where it stopped.
+pub fn measure_library(database_path: &Path, force: bool) -> Result<LoudnessSummary> {
+    let mut summary = LoudnessSummary::default();
+    if force {
+        db::clear_loudness(database_path)?;
+    }
+    
+    let samples = db::get_all_samples(database_path)?;
+    let wavs = samples.iter().filter(|record| AudioFormat::from_path(Path::new(&record.file_path)) == Some(AudioFormat::Wav));
+    summary.skipped = samples.len() - wavs.clone().count();
+    
+    for record in wavs {
+        let path = PathBuf::from(&record.file_path);
+        if record.loudness_analyzed_at.is_some() {
+            summary.already_measured += 1;
+            continue;
+        }
+        if !path.is_file() {
+            summary.missing.push(path);
+            continue;
+        }
+        
+        match measure_sample(database_path, record) {
+            Ok(loudness) => {
+                say!("   🔊 {}: {}", record.filename, loudness.describe());
+                summary.measured += 1;
+            }
+            Err(e) => {
+                eprintln!("🚨 Failed to measure {:?}: {}", path, e);
+                summary.failed.push((path, e.to_string()));
+            }
+        }
+    }
+    
+    Ok(summary)
+}
+
+/// Progress of the daemon's background loudness job, as reported over the control socket
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct JobStatus {
+    /// Whether the job is working through the library (or waiting for new files)
+    pub running: bool,
+    /// File being measured right now
+    pub current: Option<String>,
+    /// Samples measured and failed since the daemon started
+    pub measured: usize,
+    pub failed: usize,
+    /// WAVs in the library still waiting to be measured, failures included
+    pub remaining: usize,
+}
+
+impl output::PlainText for JobStatus {}
+
+/// Background job measuring library samples one at a time while the daemon runs.
+/// Progress lives in the database, so a stopped job or restarted daemon carries on
+/// with the samples that are left.
+#[derive(Default)]
+pub struct LoudnessJob {
+    running: AtomicBool,
+    wake: Notify,
+    status: Mutex<JobStatus>,
+}
+
+impl LoudnessJob {
+    pub fn status(&self) -> JobStatus {
+        let mut status = self.status.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        status.running = self.running.load(Ordering::Relaxed);
+        status
+    }
+    
+    /// Start or stop the job, returning whether that changed anything. A job that is
+    /// stopped finishes the file it is measuring first; starting one that is waiting
+    /// for new files makes it look again right away.
+    pub fn set_running(&self, running: bool) -> bool {
+        let changed = self.running.swap(running, Ordering::Relaxed) != running;
+        if running {
+            self.wake.notify_one();
+        }
+        changed
+    }
+    
+    fn update(&self, change: impl FnOnce(&mut JobStatus)) {
+        change(&mut self.status.lock().unwrap_or_else(|e| e.into_inner()));
+    }
+    
+    /// Take in a reloaded config. The job only follows `watcher.loudness_job` when that
+    /// setting itself changed (`enabled` is the value last seen), so a reload for
+    /// anything else keeps a start or stop from the control socket.
+    fn follow_config(&self, config: &mut watch::Receiver<Config>, enabled: &mut bool) {
+        let configured = config.borrow_and_update().watcher.loudness_job;
+        if configured != *enabled {
+            *enabled = configured;
+            self.running.store(configured, Ordering::Relaxed);
+        }
+    }
+    
+    /// Run until the task is dropped, starting and stopping with `watcher.loudness_job`
+    /// and the control socket
+    pub async fn run(self: Arc<Self>, mut config: watch::Receiver<Config>) {
+        let mut enabled = config.borrow_and_update().watcher.loudness_job;
+        self.running.store(enabled, Ordering::Relaxed);
+        // Samples that failed are not tried again until the daemon restarts
+        let mut failed: HashSet<String> = HashSet::new();
+        
+        loop {
+            if !self.running.load(Ordering::Relaxed) {
+                tokio::select! {
+                    _ = self.wake.notified() => {}
+                    changed = config.changed() => {
+                        if changed.is_err() {
+                            return;
+                        }
+                        self.follow_config(&mut config, &mut enabled);
+                    }
+                }
+                continue;
+            }
+            
+            let database = config.borrow().paths.database.clone();
+            let lookup = database.clone();
+            let queue = match tokio::task::spawn_blocking(move || unmeasured_wavs(&lookup)).await {
+                Ok(Ok(queue)) => queue,
+                Ok(Err(e)) => {
+                    eprintln!("⚠️  Loudness job could not read the library: {}", e);
+                    Vec::new()
+                }
+                Err(_) => return,
+            };
+            let total = queue.len();
+            let queue: Vec<SampleRecord> = queue.into_iter().filter(|record| !failed.contains(&record.file_hash)).collect();
+            self.update(|status| status.remaining = total);
+            
+            if queue.is_empty() {
+                tokio::select! {
+                    _ = self.wake.notified() => {}
+                    _ = tokio::time::sleep(JOB_POLL) => {}
+                    changed = config.changed() => {
+                        if changed.is_err() {
+                            return;
+                        }
+                        self.follow_config(&mut config, &mut enabled);
+                    }
+                }
+                continue;
+            }
+            
+            say!("🔊 Measuring loudness of {} library samples", queue.len());
+            let mut finished = true;
+            for record in queue {
+                // A config reload can switch the job off (or move the database) mid-run
+                match config.has_changed() {
+                    Ok(true) => {
+                        self.follow_config(&mut config, &mut enabled);
+                        if config.borrow().paths.database != database {
+                            finished = false;
+                            break;
+                        }
+                    }
+                    Ok(false) => {}
+                    Err(_) => return,
+                }
+                if !self.running.load(Ordering::Relaxed) {
+                    say!("🔊 Loudness job stopped, {} samples left", self.status().remaining);
+                    finished = false;
+                    break;
+                }
+                self.update(|status| status.current = Some(record.file_path.clone()));
+                
+                let database = database.clone();
+                let (hash, path) = (record.file_hash.clone(), record.file_path.clone());
+                let result = tokio::task::spawn_blocking(move || {
+                    measure_sample(&database, &record).map(|loudness| (record, loudness))
+                }).await;
+                match result {
+                    Ok(Ok((record, loudness))) => {
+                        say!("   🔊 {}: {}", record.filename, loudness.describe());
+                        self.update(|status| {
+                            status.measured += 1;
+                            status.remaining = status.remaining.saturating_sub(1);
+                        });
+                    }
+                    Ok(Err(e)) => {
+                        eprintln!("🚨 Failed to measure the loudness of {:?}: {}", path, e);
+                        failed.insert(hash);
+                        self.update(|status| status.failed += 1);
+                    }
+                    Err(_) => return,
+                }
+                self.update(|status| status.current = None);
+            }
+            if finished {
+                say!("🔊 Loudness job caught up with the library");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const RATE: u32 = 48000;
+    
+    fn measure(channels: u16, samples: &[f32]) -> Loudness {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("signal.wav");
+        wav::write_float(&path, channels, RATE, samples).unwrap();
+        measure_file(&path).unwrap()
+    }
+    
+    fn sine(hz: f64, amplitude: f64, phase: f64, seconds: f64) -> Vec<f32> {
+        (0..(seconds * RATE as f64) as usize)
+            .map(|i| (amplitude * (2.0 * PI * hz * i as f64 / RATE as f64 + phase).sin()) as f32)
+            .collect()
+    }
+    
+    fn assert_near(value: Option<f64>, expected: f64, tolerance: f64) {
+        let value = value.unwrap();
+        assert!((value - expected).abs() <= tolerance, "{} is not within {} of {}", value, tolerance, expected);
+    }
+    
+    /// Weighted block power that reads as `lufs`
+    fn block(lufs: f64) -> f64 {
+        10f64.powf((lufs - LOUDNESS_OFFSET) / 10.0)
+    }
+    
+    #[test]
+    fn full_scale_sine_reads_minus_three_lufs() {
+        let loudness = measure(1, &sine(1000.0, 1.0, 0.0, 5.0));
+        assert_near(loudness.integrated_lufs, -3.01, 0.05);
+        assert_near(loudness.sample_peak_dbfs, 0.0, 0.01);
+        assert_near(loudness.true_peak_dbtp, 0.0, 0.05);
+        assert_near(loudness.rms_dbfs, -3.01, 0.01);
+        assert!(loudness.dc_offset.abs() < 1e-4);
+    }
+    
+    #[test]
+    fn stereo_channels_add_up() {
+        let interleaved: Vec<f32> = sine(1000.0, 1.0, 0.0, 5.0).into_iter().flat_map(|s| [s, s]).collect();
+        let loudness = measure(2, &interleaved);
+        assert_near(loudness.integrated_lufs, 0.0, 0.05);
+        assert_near(loudness.rms_dbfs, -3.01, 0.01);
+    }
+    
+    #[test]
+    fn true_peak_catches_overs_between_samples() {
+        // A quarter of the sample rate at 45° is sampled at 0.707 but peaks at 1.0 in between
+        let loudness = measure(1, &sine(12000.0, 1.0, PI / 4.0, 1.0));
+        assert_near(loudness.sample_peak_dbfs, -3.01, 0.01);
+        assert_near(loudness.true_peak_dbtp, 0.0, 0.2);
+    }
+    
+    #[test]
+    fn silence_has_no_levels() {
+        let loudness = measure(1, &vec![0.0; RATE as usize]);
+        assert_eq!(loudness, Loudness::default());
+        assert_eq!(loudness.describe(), "silent");
+    }
+    
+    #[test]
+    fn audio_shorter_than_a_block_is_measured_whole() {
+        // 200 ms is half a gating block
+        let loudness = measure(1, &sine(1000.0, 0.1, 0.0, 0.2));
+        assert_near(loudness.integrated_lufs, -23.01, 0.1);
+        assert_near(loudness.sample_peak_dbfs, -20.0, 0.01);
+    }
+    
+    #[test]
+    fn dc_offset_is_the_mean_sample() {
+        let shifted: Vec<f32> = sine(1000.0, 0.5, 0.0, 1.0).into_iter().map(|s| s + 0.25).collect();
+        let loudness = measure(1, &shifted);
+        assert!((loudness.dc_offset - 0.25).abs() < 1e-4);
+        assert_near(loudness.sample_peak_dbfs, -2.5, 0.01);
+    }
+    
+    #[tokio::test]
+    async fn reloads_only_override_the_socket_when_the_setting_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.paths.database = dir.path().join("samples.db");
+        db::init_database(&config.paths.database).unwrap();
+        let (config_tx, config_rx) = watch::channel(config.clone());
+        let job = Arc::new(LoudnessJob::default());
+        let task = tokio::spawn(job.clone().run(config_rx));
+        let settle = || tokio::time::sleep(Duration::from_millis(100));
+        settle().await;
+        assert!(!job.status().running);
+        
+        // Started over the socket, then a reload that leaves `loudness_job` alone
+        job.set_running(true);
+        config.watcher.debounce_ms += 1;
+        config_tx.send(config.clone()).unwrap();
+        settle().await;
+        assert!(job.status().running);
+        
+        // Switched on in the file and stopped over the socket: another reload keeps it stopped
+        config.watcher.loudness_job = true;
+        config_tx.send(config.clone()).unwrap();
+        settle().await;
+        job.set_running(false);
+        config.watcher.debounce_ms += 1;
+        config_tx.send(config.clone()).unwrap();
+        settle().await;
+        assert!(!job.status().running);
+        
+        // Changing the setting itself applies it
+        config.watcher.loudness_job = false;
+        config_tx.send(config.clone()).unwrap();
+        settle().await;
+        job.set_running(true);
+        settle().await;
+        config.watcher.loudness_job = true;
+        config_tx.send(config.clone()).unwrap();
+        settle().await;
+        assert!(job.status().running);
+        config.watcher.loudness_job = false;
+        config_tx.send(config.clone()).unwrap();
+        settle().await;
+        assert!(!job.status().running);
+        
+        task.abort();
+    }
+    
+    #[test]
+    fn gating_drops_quiet_blocks() {
+        assert_eq!(gated_loudness(&[]), None);
+        assert_eq!(gated_loudness(&[block(-75.0), block(-80.0)]), None);
+        
+        // Below the absolute gate or more than 10 LU under the rest: left out
+        let blocks = [block(-23.0), block(-23.0), block(-23.0), block(-40.0), block(-75.0)];
+        assert_near(gated_loudness(&blocks), -23.0, 1e-9);
+        // Within 10 LU: averaged in by power
+        let blocks = [block(-20.0), block(-26.0)];
+        assert_near(gated_loudness(&blocks), -22.0, 0.05);
+    }
+}
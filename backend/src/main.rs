@@ -1,6 +1,7 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use console::style;
+use serde::Serialize;
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
 use std::fs;
@@ -8,9 +9,25 @@ use std::env;
 use tracing::{warn, error};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+#[macro_use]
+mod output;
 mod watcher;
+mod analysis;
+mod config;
+mod control;
 mod db;
+mod debounce;
+mod integrity;
+mod loudness;
+mod migrations;
+mod layout;
 mod metadata;
+mod quarantine;
+mod reorganize;
+mod rules;
+mod search;
+mod tagging;
+mod wav;
 
 #[derive(Parser)]
 #[command(name = "splice-alt-daemon")]
@@ -19,6 +36,10 @@ struct Args {
     #[command(subcommand)]
     command: Option<Commands>,
     
+    /// Configuration file (default: ~/.config/splice-alt/config.toml)
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+    
     /// Directory to watch for new samples (default: ~/Downloads)
     #[arg(short, long)]
     watch_dir: Option<PathBuf>,
@@ -30,6 +51,14 @@ struct Args {
     /// Database file path (default: ~/.local/share/splice-alt/samples.db)
     #[arg(short, long)]
     database: Option<PathBuf>,
+    
+    /// Library path template (default: {category}/{pack}/{filename})
+    #[arg(long)]
+    layout: Option<String>,
+    
+    /// Output format for command results
+    #[arg(short, long, value_enum, global = true, default_value_t = output::OutputFormat::Table)]
+    output: output::OutputFormat,
 }
 
 #[derive(Subcommand)]
@@ -47,15 +76,48 @@ enum Commands {
     Stop,
     /// Check daemon status
     Status,
+    /// Send a request to the running daemon over its control socket
+    #[command(alias = "ctl")]
+    Control {
+        #[command(subcommand)]
+        action: ControlCommands,
+    },
+    /// Import any audio/JSON pairs already sitting in the watch directory
+    Scan,
+    /// Inspect and manage files still waiting for their audio/JSON partner
+    Pending {
+        #[command(subcommand)]
+        action: PendingCommands,
+    },
+    /// Inspect, retry or discard pairs set aside after failing to import
+    Quarantine {
+        #[command(subcommand)]
+        action: QuarantineCommands,
+    },
     /// Test metadata parsing with a JSON file
     Test {
         /// Path to JSON metadata file
         metadata_file: PathBuf,
     },
-    /// Process a specific WAV and JSON file pair directly
+    /// Manage the sample database
+    Db {
+        #[command(subcommand)]
+        action: DbCommands,
+    },
+    /// Show or check the daemon configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommands,
+    },
+    /// Inspect the category mapping rules
+    Rules {
+        #[command(subcommand)]
+        action: RulesCommands,
+    },
+    /// Process a specific audio and JSON file pair directly
     Process {
-        /// Path to WAV file
-        wav_file: PathBuf,
+        /// Path to the audio file (WAV, AIFF, FLAC, MP3 or OGG)
+        audio_file: PathBuf,
         /// Path to JSON metadata file
         json_file: PathBuf,
         /// Target library directory
@@ -73,6 +135,116 @@ enum Commands {
         #[arg(short, long)]
         database: Option<PathBuf>,
     },
+    /// Search samples by text and structured filters
+    Search {
+        /// Words to look for in filenames, pack and provider names and tags
+        query: Vec<String>,
+        /// BPM or inclusive range (e.g. 120, 120..128, 140..)
+        #[arg(long)]
+        bpm: Option<search::BpmRange>,
+        /// Musical key (e.g. A, Am, F#min, Bbmaj)
+        #[arg(long)]
+        key: Option<search::KeyFilter>,
+        /// Sample type (loop or oneshot)
+        #[arg(long = "type")]
+        sample_type: Option<String>,
+        /// Bitwig category (e.g., Bass, Lead, Drum Loop)
+        #[arg(long)]
+        category: Option<String>,
+        /// Only samples carrying this tag (repeat to require several)
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+        /// Pack name contains this text
+        #[arg(long)]
+        pack: Option<String>,
+        /// Provider name contains this text
+        #[arg(long)]
+        provider: Option<String>,
+        /// Only samples downloaded on or after this date (YYYY-MM-DD)
+        #[arg(long, value_parser = search::parse_date)]
+        downloaded_after: Option<String>,
+        /// Sample rate in Hz (e.g. 44100, 48000)
+        #[arg(long)]
+        sample_rate: Option<u32>,
+        /// Bits per sample (e.g. 16, 24, 32)
+        #[arg(long)]
+        bit_depth: Option<u16>,
+        /// Number of channels (1 for mono, 2 for stereo)
+        #[arg(long)]
+        channels: Option<u16>,
+        #[command(flatten)]
+        levels: Box<LevelFilters>,
+        /// Result order
+        #[arg(long, value_enum, default_value_t = search::SearchSort::Relevance)]
+        sort: search::SearchSort,
+        /// Maximum number of results (0 for no limit)
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+        /// Database file path
+        #[arg(short, long)]
+        database: Option<PathBuf>,
+    },
+    /// Browse the packs samples were downloaded from
+    Packs {
+        /// Only packs whose name or provider contains this text
+        filter: Option<String>,
+        /// Database file path
+        #[arg(short, long)]
+        database: Option<PathBuf>,
+    },
+    /// List tags by how many samples use them
+    Tags {
+        /// Database file path
+        #[arg(short, long)]
+        database: Option<PathBuf>,
+    },
+    /// Move library files to match the current category rules and layout
+    Reorganize {
+        /// Perform the moves instead of only showing the plan
+        #[arg(long)]
+        apply: bool,
+    },
+    /// Estimate BPM and key from the audio of library samples, or of any WAV files
+    Analyze {
+        /// WAV files to analyze instead of the library; the estimates are printed, not stored
+        files: Vec<PathBuf>,
+        /// Analyze library samples again even if they already have estimates
+        #[arg(long)]
+        force: bool,
+        /// Database file path
+        #[arg(short, long)]
+        database: Option<PathBuf>,
+    },
+    /// Measure loudness (LUFS), sample and true peak, RMS and DC offset of library samples, or of any WAV files
+    Loudness {
+        /// WAV files to measure instead of the library; the results are printed, not stored
+        files: Vec<PathBuf>,
+        /// Measure library samples again even if they already have measurements
+        #[arg(long)]
+        force: bool,
+        /// Database file path
+        #[arg(short, long)]
+        database: Option<PathBuf>,
+    },
+    /// Embed the Splice metadata into every WAV already in the library
+    Retag {
+        /// Also write acid and smpl chunks (tempo, beats, root note, loop points),
+        /// as with `embed_loop_chunks = true`
+        #[arg(long)]
+        loop_chunks: bool,
+        /// Database file path
+        #[arg(short, long)]
+        database: Option<PathBuf>,
+    },
+    /// List samples whose category was chosen with low confidence
+    Review {
+        /// Show samples below this confidence (0.0 - 1.0)
+        #[arg(long, default_value_t = 0.6)]
+        below: f64,
+        /// Database file path
+        #[arg(short, long)]
+        database: Option<PathBuf>,
+    },
     /// Update file path in database (useful when files are moved)
     UpdatePath {
         /// File hash of the sample to update
@@ -85,23 +257,161 @@ enum Commands {
     },
 }
 
-/// Helper function to get the default database path
-fn default_db_path() -> PathBuf {
-    let base_dir = dirs::data_local_dir()
-        .unwrap_or_else(|| PathBuf::from("."));
-    let db_dir = base_dir.join("splice-alt");
-    
-    // Create parent directory if it doesn't exist
-    if let Err(e) = fs::create_dir_all(&db_dir) {
-        warn!("Failed to create database directory {:?}: {}", db_dir, e);
+/// Loudness filters of `search`, ranges of what the loudness pass measured
+#[derive(clap::Args)]
+struct LevelFilters {
+    /// Integrated loudness range in LUFS (e.g. -14..-8, ..-20)
+    #[arg(long, allow_hyphen_values = true)]
+    lufs: Option<search::LevelRange>,
+    /// True peak range in dBTP (e.g. -1.. for files that may clip when converted)
+    #[arg(long, allow_hyphen_values = true)]
+    true_peak: Option<search::LevelRange>,
+    /// Sample peak range in dBFS
+    #[arg(long, allow_hyphen_values = true)]
+    peak: Option<search::LevelRange>,
+    /// RMS level range in dBFS
+    #[arg(long, allow_hyphen_values = true)]
+    rms: Option<search::LevelRange>,
+    /// Range of the DC offset's size, as a fraction of full scale (e.g. 0.01..)
+    #[arg(long)]
+    dc_offset: Option<search::LevelRange>,
+}
+
+#[derive(Subcommand)]
+enum PendingCommands {
+    /// List files waiting for their partner
+    List,
+    /// Import pending files whose partner has appeared
+    Resolve {
+        /// Resolve only this pending entry
+        id: Option<i64>,
+        /// JSON metadata file to pair with the pending audio file
+        #[arg(short, long, requires = "id")]
+        metadata: Option<PathBuf>,
+    },
+    /// Remove entries from the pending queue
+    Purge {
+        /// Only purge entries first seen more than this many days ago
+        #[arg(long)]
+        older_than: Option<u32>,
+        /// Also delete the pending files from disk
+        #[arg(long)]
+        delete_files: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum QuarantineCommands {
+    /// List quarantined pairs and why they failed
+    List,
+    /// Import quarantined pairs again, removing the ones that succeed
+    Retry {
+        /// Retry only this entry
+        id: Option<String>,
+        /// Import even if the audio file doesn't match its metadata
+        #[arg(long)]
+        force: bool,
+    },
+    /// Delete quarantined pairs from disk
+    Discard {
+        /// Entry to delete
+        #[arg(required_unless_present = "all", conflicts_with = "all")]
+        id: Option<String>,
+        /// Delete every quarantined pair
+        #[arg(long)]
+        all: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ControlCommands {
+    /// Import, duplicate and error counts since the daemon started
+    Counters,
+    /// Files the daemon has queued waiting for their partner
+    Pending,
+    /// Most recently imported samples
+    Recent {
+        /// Number of samples to show
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Stop handling new files until resumed
+    Pause,
+    /// Resume handling files and pick up anything that arrived while paused
+    Resume,
+    /// Scan the watch directory and retry the pending queue now
+    Rescan,
+    /// Re-read the configuration file
+    Reload,
+    /// Progress of the background loudness job
+    LoudnessStatus,
+    /// Start measuring the loudness of library samples in the background
+    LoudnessStart {
+        /// Measure every sample again, not just the ones without measurements
+        #[arg(long)]
+        force: bool,
+    },
+    /// Stop the background loudness job after the file it is measuring
+    LoudnessStop,
+    /// Ask the daemon to exit
+    Shutdown,
+}
+
+#[derive(Subcommand)]
+enum DbCommands {
+    /// Apply pending schema migrations (a backup is taken first)
+    Migrate {
+        /// Only show which migrations are applied and which are pending
+        #[arg(long)]
+        status: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Print the effective settings after applying the file, environment and flags
+    Show,
+    /// Check a configuration file for errors
+    Validate {
+        /// File to check (default: the file that would be loaded)
+        file: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum RulesCommands {
+    /// Show which rule fires for a JSON metadata file and why
+    Test {
+        /// Path to JSON metadata file
+        metadata_file: PathBuf,
+    },
+}
+
+impl Args {
+    /// Command-line settings that take precedence over the config file and environment
+    fn overrides(&self) -> config::Overrides {
+        config::Overrides {
+            config_file: self.config.clone(),
+            watch_dir: self.watch_dir.clone(),
+            library_dir: self.library_dir.clone(),
+            database: self.database.clone(),
+            layout: self.layout.clone(),
+        }
     }
-    
-    db_dir.join("samples.db")
+}
+
+/// Load the configuration, letting a subcommand's own `--database` win
+fn load_config(overrides: &config::Overrides, database: Option<PathBuf>) -> Result<config::Config> {
+    let mut overrides = overrides.clone();
+    if database.is_some() {
+        overrides.database = database;
+    }
+    config::Config::load(&overrides)
 }
 
 fn get_pid_file_path() -> PathBuf {
     dirs::runtime_dir()
-        .or_else(|| dirs::cache_dir())
+        .or_else(dirs::cache_dir)
         .unwrap_or_else(|| PathBuf::from("/tmp"))
         .join("splice-alt-daemon.pid")
 }
@@ -135,14 +445,14 @@ fn write_pid_file(pid: u32) -> Result<()> {
     }
     
     // Use create_new to avoid race conditions
-    if let Err(_) = fs::OpenOptions::new()
+    if fs::OpenOptions::new()
         .write(true)
         .create_new(true)
         .open(&pid_file)
         .and_then(|mut f| {
             use std::io::Write;
             f.write_all(pid.to_string().as_bytes())
-        }) {
+        }).is_err() {
         anyhow::bail!("PID file already exists - daemon may already be running");
     }
     
@@ -161,34 +471,86 @@ fn is_process_running(pid: u32) -> bool {
     use nix::sys::signal;
     use nix::unistd::Pid;
     
-    match signal::kill(Pid::from_raw(pid as i32), None) {
-        Ok(_) => true,  // Process exists
-        Err(_) => false, // Process doesn't exist
-    }
+    // Signal 0 only checks whether the process exists
+    signal::kill(Pid::from_raw(pid as i32), None).is_ok()
 }
 
+/// Result of `start`
+#[derive(Serialize)]
+struct StartReport {
+    started: bool,
+    already_running: bool,
+    pid: Option<u32>,
+    log_file: PathBuf,
+}
+
+impl output::PlainText for StartReport {}
+
+/// Result of `stop`
+#[derive(Serialize)]
+struct StopReport {
+    was_running: bool,
+    pid: Option<u32>,
+    stopped: bool,
+    /// Had to be killed with SIGKILL after not stopping gracefully
+    forced: bool,
+}
+
+impl output::PlainText for StopReport {}
+
+/// Result of `status`
+#[derive(Serialize)]
+struct StatusReport {
+    running: bool,
+    pid: Option<u32>,
+    /// Elapsed time as reported by `ps`
+    runtime: Option<String>,
+    pid_file: PathBuf,
+    log_file: PathBuf,
+    log_size: Option<u64>,
+    recent_log: Vec<String>,
+    /// Live details from the control socket, absent when only the PID file was checked
+    daemon: Option<control::DaemonStatus>,
+}
+
+impl output::PlainText for StatusReport {}
+
 fn start_daemon(args: Args) -> Result<()> {
+    let log_file = get_log_file_path();
+    
+    // Validate up front so a bad config fails here rather than in the background
+    config::Config::load(&args.overrides())?;
+    
     // Check if daemon is already running
     if let Some(pid) = read_pid_file() {
         if is_process_running(pid) {
-            println!("{} Daemon is already running (PID: {})", style("✅").green(), pid);
-            println!("   Use stop to stop it first, or status to check");
+            if output::is_table() {
+                println!("{} Daemon is already running (PID: {})", style("✅").green(), pid);
+                println!("   Use stop to stop it first, or status to check");
+            } else {
+                output::emit(&StartReport { started: false, already_running: true, pid: Some(pid), log_file })?;
+            }
             return Ok(());
         } else {
-            println!("{} Cleaning up stale PID file...", style("🧹").yellow());
+            say!("{} Cleaning up stale PID file...", style("🧹").yellow());
             let _ = remove_pid_file();
         }
     }
     
     let executable = get_current_executable()?;
-    let log_file = get_log_file_path();
     
-    println!("{} Starting Splice Alt daemon in background...", style("🚀").green());
-    println!("{} Log file: {:?}", style("📁").blue(), log_file);
+    say!("{} Starting Splice Alt daemon in background...", style("🚀").green());
+    say!("{} Log file: {:?}", style("📁").blue(), log_file);
     
     // Prepare command arguments
     let mut cmd = Command::new(&executable);
-    cmd.arg("run");
+    
+    // Only flags given explicitly are forwarded; everything else comes from the
+    // config file, which the daemon re-reads on SIGHUP. They belong to the top-level
+    // command, so they go before the subcommand.
+    if let Some(config_file) = args.config {
+        cmd.arg("--config").arg(config_file);
+    }
     
     if let Some(watch_dir) = args.watch_dir {
         cmd.arg("--watch-dir").arg(watch_dir);
@@ -202,6 +564,12 @@ fn start_daemon(args: Args) -> Result<()> {
         cmd.arg("--database").arg(database);
     }
     
+    if let Some(layout) = args.layout {
+        cmd.arg("--layout").arg(layout);
+    }
+    
+    cmd.arg("run");
+    
     // Set up background process
     let log_file_for_stdout = fs::File::create(&log_file)?;
     let log_file_for_stderr = log_file_for_stdout.try_clone()?;
@@ -221,22 +589,77 @@ fn start_daemon(args: Args) -> Result<()> {
     std::thread::sleep(std::time::Duration::from_millis(500));
     
     // Verify it's still running
-    if is_process_running(pid) {
-        println!("{} Daemon started successfully (PID: {})", style("✅").green(), pid);
-        println!("   Monitor logs: tail -f {:?}", log_file);
-        println!("   Stop daemon: {} stop", executable.file_name().unwrap().to_string_lossy());
+    let started = is_process_running(pid);
+    if started {
+        say!("{} Daemon started successfully (PID: {})", style("✅").green(), pid);
+        say!("   Monitor logs: tail -f {:?}", log_file);
+        say!("   Stop daemon: {} stop", executable.file_name().unwrap().to_string_lossy());
     } else {
-        println!("{} Daemon failed to start. Check logs: {:?}", style("❌").red(), log_file);
+        say!("{} Daemon failed to start. Check logs: {:?}", style("❌").red(), log_file);
         let _ = remove_pid_file();
     }
     
+    if !output::is_table() {
+        output::emit(&StartReport { started, already_running: false, pid: Some(pid), log_file })?;
+    }
+    
     Ok(())
 }
 
-fn stop_daemon() -> Result<()> {
-    if let Some(pid) = read_pid_file() {
+/// Poll until the process exits or `timeout` runs out
+fn wait_for_exit(pid: u32, timeout: std::time::Duration) -> bool {
+    let started = std::time::Instant::now();
+    let mut announced = false;
+    while started.elapsed() < timeout {
+        std::thread::sleep(std::time::Duration::from_millis(250));
+        if !is_process_running(pid) {
+            return true;
+        }
+        if !announced && started.elapsed() >= std::time::Duration::from_secs(2) {
+            say!("{} Waiting for graceful shutdown...", style("⏳").yellow());
+            announced = true;
+        }
+    }
+    false
+}
+
+fn stop_daemon(overrides: &config::Overrides) -> Result<()> {
+    // The daemon may spend up to its shutdown timeout finishing the sample in progress
+    let shutdown_timeout = config::Config::load(overrides)
+        .map(|config| config.watcher.shutdown_timeout_secs)
+        .unwrap_or_else(|_| config::WatcherConfig::default().shutdown_timeout_secs);
+    let grace = std::time::Duration::from_secs(shutdown_timeout + 5);
+    
+    let mut report = StopReport { was_running: false, pid: read_pid_file(), stopped: false, forced: false };
+    
+    // Ask over the control socket first; signals are the fallback when it is unreachable
+    if let Ok(mut client) = control::Client::connect() {
+        match client.call("shutdown", serde_json::Value::Null) {
+            Ok(result) => {
+                let pid = result["pid"].as_u64().map(|pid| pid as u32).or(report.pid);
+                report.was_running = true;
+                report.pid = pid;
+                say!("{} Stopping daemon{} via control socket...", style("🛑").red(),
+                    pid.map_or(String::new(), |pid| format!(" (PID: {})", pid)));
+                
+                if let Some(pid) = pid {
+                    if wait_for_exit(pid, grace) {
+                        say!("{} Daemon stopped successfully", style("✅").green());
+                        remove_pid_file()?;
+                        report.stopped = true;
+                        return finish_stop(&report);
+                    }
+                    say!("{} Daemon did not exit, falling back to signals", style("⚠️").yellow());
+                }
+            }
+            Err(e) => say!("{} Control socket did not accept shutdown: {}", style("⚠️").yellow(), e),
+        }
+    }
+    
+    if let Some(pid) = report.pid {
         if is_process_running(pid) {
-            println!("{} Stopping daemon (PID: {})...", style("🛑").red(), pid);
+            report.was_running = true;
+            say!("{} Stopping daemon (PID: {})...", style("🛑").red(), pid);
             
             use nix::sys::signal::{self, Signal};
             use nix::unistd::Pid;
@@ -244,100 +667,244 @@ fn stop_daemon() -> Result<()> {
             // Send SIGTERM first
             if let Err(e) = signal::kill(Pid::from_raw(pid as i32), Signal::SIGTERM) {
                 error!("Failed to send SIGTERM: {}", e);
-                return Ok(());
+                return finish_stop(&report);
             }
             
-            // Use nix waitpid for more reliable process monitoring
-            let pid_struct = Pid::from_raw(pid as i32);
-            for i in 0..20 {
-                std::thread::sleep(std::time::Duration::from_millis(250));
-                if !is_process_running(pid) {
-                    println!("{} Daemon stopped successfully", style("✅").green());
-                    remove_pid_file()?;
-                    return Ok(());
-                }
-                if i == 8 {
-                    println!("{} Waiting for graceful shutdown...", style("⏳").yellow());
-                }
+            if wait_for_exit(pid, grace) {
+                say!("{} Daemon stopped successfully", style("✅").green());
+                remove_pid_file()?;
+                report.stopped = true;
+                return finish_stop(&report);
             }
             
             // Force kill if still running
-            println!("{} Forcing daemon shutdown...", style("⚠️").yellow());
-            if let Err(e) = signal::kill(pid_struct, Signal::SIGKILL) {
+            say!("{} Forcing daemon shutdown...", style("⚠️").yellow());
+            if let Err(e) = signal::kill(Pid::from_raw(pid as i32), Signal::SIGKILL) {
                 error!("Failed to force kill: {}", e);
             } else {
-                println!("{} Daemon force stopped", style("✅").green());
+                say!("{} Daemon force stopped", style("✅").green());
+                report.stopped = true;
+                report.forced = true;
             }
             
             remove_pid_file()?;
         } else {
-            println!("{} Daemon not running, cleaning up PID file", style("🧹").yellow());
+            say!("{} Daemon not running, cleaning up PID file", style("🧹").yellow());
             remove_pid_file()?;
         }
-    } else {
-        println!("{} Daemon is not running", style("ℹ️").blue());
+    } else if !report.was_running {
+        say!("{} Daemon is not running", style("ℹ️").blue());
+    }
+    
+    finish_stop(&report)
+}
+
+fn finish_stop(report: &StopReport) -> Result<()> {
+    if !output::is_table() {
+        output::emit(report)?;
+    }
+    Ok(())
+}
+
+fn control_daemon(action: &ControlCommands) -> Result<()> {
+    let (method, params, heading) = match action {
+        ControlCommands::Counters => ("counters", serde_json::Value::Null, "📊 Counters since the daemon started"),
+        ControlCommands::Pending => ("pending", serde_json::Value::Null, "⏳ Pending queue"),
+        ControlCommands::Recent { limit } => ("recent_imports", serde_json::json!({ "limit": limit }), "🎵 Recent imports"),
+        ControlCommands::Pause => ("pause", serde_json::Value::Null, "⏸️  Watching paused"),
+        ControlCommands::Resume => ("resume", serde_json::Value::Null, "▶️  Watching resumed"),
+        ControlCommands::Rescan => ("rescan", serde_json::Value::Null, "🔎 Rescan finished"),
+        ControlCommands::Reload => ("reload", serde_json::Value::Null, "🔄 Configuration reloaded"),
+        ControlCommands::LoudnessStatus => ("loudness_status", serde_json::Value::Null, "🔊 Loudness job"),
+        ControlCommands::LoudnessStart { force } => ("loudness_start", serde_json::json!({ "force": force }), "🔊 Loudness job started"),
+        ControlCommands::LoudnessStop => ("loudness_stop", serde_json::Value::Null, "🔊 Loudness job stopping"),
+        ControlCommands::Shutdown => ("shutdown", serde_json::Value::Null, "🛑 Daemon is shutting down"),
+    };
+    
+    let mut client = control::Client::connect()
+        .map_err(|e| anyhow::anyhow!("{} (is the daemon running?)", e))?;
+    let result = client.call(method, params)?;
+    
+    if !output::is_table() {
+        return match &result {
+            serde_json::Value::Array(items) => output::emit_list(items),
+            other => output::emit(other),
+        };
+    }
+    
+    println!("{}", heading);
+    match &result {
+        serde_json::Value::Array(items) if items.is_empty() => println!("   (none)"),
+        serde_json::Value::Array(items) => {
+            for item in items {
+                // Samples are named by filename, pending entries by path
+                let name_key = if item.get("filename").is_some() { "filename" } else { "file_path" };
+                println!("   {}", item[name_key].as_str().unwrap_or_default());
+                for key in ["file_kind", "pack_name", "mapped_category", "file_path", "first_seen", "last_error"] {
+                    match item.get(key).and_then(|v| v.as_str()) {
+                        Some(value) if key != name_key => println!("      {}: {}", key, value),
+                        _ => {}
+                    }
+                }
+            }
+        }
+        other => {
+            for line in output::PlainText::plain_lines(other) {
+                println!("   {}", line.replacen('\t', ": ", 1));
+            }
+        }
     }
     
     Ok(())
 }
 
+/// Elapsed time in the same `[[DD-]HH:]MM:SS` form `ps` uses
+fn format_elapsed(secs: u64) -> String {
+    let (days, hours, minutes, seconds) = (secs / 86400, secs / 3600 % 24, secs / 60 % 60, secs % 60);
+    if days > 0 {
+        format!("{}-{:02}:{:02}:{:02}", days, hours, minutes, seconds)
+    } else if hours > 0 {
+        format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{:02}:{:02}", minutes, seconds)
+    }
+}
+
 fn check_daemon_status() -> Result<()> {
     let pid_file = get_pid_file_path();
     let log_file = get_log_file_path();
+    let mut report = StatusReport {
+        running: false,
+        pid: None,
+        runtime: None,
+        pid_file: pid_file.clone(),
+        log_file: log_file.clone(),
+        log_size: None,
+        recent_log: Vec::new(),
+        daemon: None,
+    };
     
-    println!("{} Splice Alt Daemon Status", style("🔍").blue());
-    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    say!("{} Splice Alt Daemon Status", style("🔍").blue());
+    say!("━━━━━━━━━━━━━━━━━━━━━━━━━━━");
     
-    if let Some(pid) = read_pid_file() {
+    // The control socket knows far more than the PID file, which is only the fallback
+    let live = control::Client::connect()
+        .and_then(|mut client| client.call("status", serde_json::Value::Null))
+        .and_then(|result| Ok(serde_json::from_value::<control::DaemonStatus>(result)?));
+    
+    if let Ok(status) = live {
+        report.running = true;
+        report.pid = Some(status.pid);
+        report.runtime = Some(format_elapsed(status.uptime_secs));
+        
+        if status.paused {
+            say!("{} Status: Running (paused)", style("⏸️").yellow());
+        } else {
+            say!("{} Status: Running", style("✅").green());
+        }
+        say!("{} PID: {}", style("🆔").blue(), status.pid);
+        say!("{} Runtime: {}", style("⏱️").blue(), format_elapsed(status.uptime_secs));
+        say!("{} Config: {}", style("⚙️").blue(),
+            status.config_file.as_ref().map_or("built-in defaults".to_string(), |path| format!("{:?}", path)));
+        say!("{} Watching: {:?}", style("👀").blue(), status.watch_dir);
+        say!("{} Library: {:?}", style("📚").blue(), status.library_dir);
+        say!("{} Database: {:?}", style("🗄️").blue(), status.database);
+        say!("{} Imported: {}, restored: {}, duplicates: {}, queued: {}, errors: {}", style("📊").blue(),
+            status.counters.imported,
+            status.counters.restored,
+            status.counters.duplicates,
+            status.counters.queued,
+            status.counters.errors);
+        say!("{} Pending files: {}", style("⏳").blue(), status.pending);
+        report.daemon = Some(status);
+    } else if let Some(pid) = read_pid_file() {
         if is_process_running(pid) {
-            println!("{} Status: Running", style("✅").green());
-            println!("{} PID: {}", style("🆔").blue(), pid);
+            report.running = true;
+            report.pid = Some(pid);
+            say!("{} Status: Running", style("✅").green());
+            say!("{} PID: {}", style("🆔").blue(), pid);
             
             // Try to get process info
-            if let Ok(output) = Command::new("ps").args(&["-p", &pid.to_string(), "-o", "pid,ppid,etime,cmd"]).output() {
+            if let Ok(output) = Command::new("ps").args(["-p", &pid.to_string(), "-o", "pid,ppid,etime,cmd"]).output() {
                 if output.status.success() {
                     let ps_output = String::from_utf8_lossy(&output.stdout);
                     let lines: Vec<&str> = ps_output.lines().collect();
                     if lines.len() > 1 {
                         if let Some(runtime) = lines[1].split_whitespace().nth(2) {
-                            println!("{} Runtime: {}", style("⏱️").blue(), runtime);
+                            say!("{} Runtime: {}", style("⏱️").blue(), runtime);
+                            report.runtime = Some(runtime.to_string());
                         }
                     }
                 }
             }
         } else {
-            println!("{} Status: Not running (stale PID file)", style("❌").red());
-            println!("{} Cleaning up stale PID file...", style("🧹").yellow());
+            say!("{} Status: Not running (stale PID file)", style("❌").red());
+            say!("{} Cleaning up stale PID file...", style("🧹").yellow());
             let _ = remove_pid_file();
         }
     } else {
-        println!("⭕ Status: Not running");
+        say!("⭕ Status: Not running");
     }
     
-    println!("📁 PID file: {:?}", pid_file);
-    println!("📄 Log file: {:?}", log_file);
+    say!("📁 PID file: {:?}", pid_file);
+    say!("📄 Log file: {:?}", log_file);
     
     if log_file.exists() {
         if let Ok(metadata) = fs::metadata(&log_file) {
-            println!("📊 Log size: {} bytes", metadata.len());
+            say!("📊 Log size: {} bytes", metadata.len());
+            report.log_size = Some(metadata.len());
             
             // Show last few lines of log
-            if let Ok(output) = Command::new("tail").args(&["-n", "5", &log_file.to_string_lossy()]).output() {
+            if let Ok(output) = Command::new("tail").args(["-n", "5", &log_file.to_string_lossy()]).output() {
                 if output.status.success() && !output.stdout.is_empty() {
-                    println!("📋 Recent log entries:");
+                    say!("📋 Recent log entries:");
                     for line in String::from_utf8_lossy(&output.stdout).lines() {
-                        println!("   {}", line);
+                        say!("   {}", line);
+                        report.recent_log.push(line.to_string());
                     }
                 }
             }
         }
     } else {
-        println!("📄 Log file: Not found");
+        say!("📄 Log file: Not found");
+    }
+    
+    if !output::is_table() {
+        output::emit(&report)?;
     }
     
     Ok(())
 }
 
+type LogFilterHandle = tracing_subscriber::reload::Handle<tracing_subscriber::EnvFilter, tracing_subscriber::Registry>;
+
+/// Lets the configured log level be changed after the subscriber is installed
+static LOG_FILTER: std::sync::OnceLock<LogFilterHandle> = std::sync::OnceLock::new();
+
+/// Apply the configured log level; `RUST_LOG`, when set, keeps precedence
+fn set_log_level(level: &str) {
+    if env::var_os("RUST_LOG").is_some() {
+        return;
+    }
+    if let Some(handle) = LOG_FILTER.get() {
+        let filter = tracing_subscriber::EnvFilter::new(format!("splice_alt_daemon={}", level.to_lowercase()));
+        if let Err(e) = handle.reload(filter) {
+            warn!("Failed to change log level: {}", e);
+        }
+    }
+}
+
+/// Writer guard for the log file; dropping it writes out buffered lines
+static LOG_GUARD: std::sync::Mutex<Option<tracing_appender::non_blocking::WorkerGuard>> = std::sync::Mutex::new(None);
+
+/// Write out everything still buffered before the process exits
+fn flush_logs() {
+    drop(LOG_GUARD.lock().unwrap_or_else(|e| e.into_inner()).take());
+    use std::io::Write;
+    let _ = std::io::stdout().flush();
+    let _ = std::io::stderr().flush();
+}
+
 /// Initialize tracing subscriber
 fn init_tracing(log_to_file: bool) -> Result<()> {
     use std::sync::Once;
@@ -346,6 +913,8 @@ fn init_tracing(log_to_file: bool) -> Result<()> {
     INIT.call_once(|| {
         let filter = tracing_subscriber::EnvFilter::try_from_default_env()
             .unwrap_or_else(|_| "splice_alt_daemon=info".into());
+        let (filter, handle) = tracing_subscriber::reload::Layer::new(filter);
+        let _ = LOG_FILTER.set(handle);
 
         if log_to_file {
             let log_file = get_log_file_path();
@@ -353,22 +922,28 @@ fn init_tracing(log_to_file: bool) -> Result<()> {
                 log_file.parent().unwrap_or_else(|| std::path::Path::new("/tmp")),
                 log_file.file_name().unwrap_or_else(|| std::ffi::OsStr::new("splice-alt-daemon.log"))
             );
-            let (non_blocking, _guard) = tracing_appender::non_blocking(file_appender);
+            let (non_blocking, guard) = tracing_appender::non_blocking(file_appender);
             
-            if let Err(_) = tracing_subscriber::registry()
+            if tracing_subscriber::registry()
                 .with(filter)
                 .with(tracing_subscriber::fmt::layer().with_writer(non_blocking))
-                .try_init() {
+                .try_init().is_err() {
                 // Already initialized, ignore error
             }
                 
-            // Store the guard to prevent it from being dropped
-            std::mem::forget(_guard);
+            // Keep the guard until shutdown, when flush_logs drops it
+            *LOG_GUARD.lock().unwrap_or_else(|e| e.into_inner()) = Some(guard);
         } else {
-            if let Err(_) = tracing_subscriber::registry()
+            // Keep stdout for results when a machine-readable format is selected
+            let writer = if output::is_table() {
+                tracing_subscriber::fmt::writer::BoxMakeWriter::new(std::io::stdout)
+            } else {
+                tracing_subscriber::fmt::writer::BoxMakeWriter::new(std::io::stderr)
+            };
+            if tracing_subscriber::registry()
                 .with(filter)
-                .with(tracing_subscriber::fmt::layer())
-                .try_init() {
+                .with(tracing_subscriber::fmt::layer().with_writer(writer))
+                .try_init().is_err() {
                 // Already initialized, ignore error
             }
         }
@@ -380,6 +955,8 @@ fn init_tracing(log_to_file: bool) -> Result<()> {
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
+    output::set_format(args.output);
+    let overrides = args.overrides();
     
     match args.command {
         Some(Commands::Start) => {
@@ -390,93 +967,171 @@ async fn main() -> Result<()> {
                 start_daemon(args)
             } else {
                 init_tracing(false)?;
-                run_daemon(args).await
+                run_daemon(&overrides).await
             }
         }
         Some(Commands::Stop) => {
-            stop_daemon()
+            stop_daemon(&overrides)
         }
         Some(Commands::Status) => {
             check_daemon_status()
         }
+        Some(Commands::Control { action }) => {
+            control_daemon(&action)
+        }
+        Some(Commands::Scan) => {
+            init_tracing(false)?;
+            scan_watch_directory(&load_config(&overrides, None)?).await
+        }
+        Some(Commands::Pending { ref action }) => {
+            init_tracing(false)?;
+            manage_pending(action, &load_config(&overrides, None)?).await
+        }
+        Some(Commands::Quarantine { ref action }) => {
+            init_tracing(false)?;
+            manage_quarantine(action, &load_config(&overrides, None)?).await
+        }
         Some(Commands::Test { metadata_file }) => {
             init_tracing(false)?;
-            test_metadata_parsing(&metadata_file).await
+            test_metadata_parsing(&metadata_file, &load_config(&overrides, None)?).await
+        }
+        Some(Commands::Db { action: DbCommands::Migrate { status } }) => {
+            init_tracing(false)?;
+            migrate_database(status, &load_config(&overrides, None)?).await
+        }
+        Some(Commands::Config { action }) => {
+            init_tracing(false)?;
+            manage_config(&action, &overrides).await
+        }
+        Some(Commands::Rules { action: RulesCommands::Test { metadata_file } }) => {
+            init_tracing(false)?;
+            test_category_rules(&metadata_file, &load_config(&overrides, None)?).await
         }
-        Some(Commands::Process { wav_file, json_file, library_dir, database }) => {
+        Some(Commands::Process { audio_file, json_file, library_dir, database }) => {
             init_tracing(false)?;
-            process_files_directly(&wav_file, &json_file, &library_dir, &database).await
+            let mut config = load_config(&overrides, Some(database))?;
+            config.paths.library_dir = library_dir;
+            process_files_directly(&audio_file, &json_file, &config).await
         }
         Some(Commands::List { category, database }) => {
             init_tracing(false)?;
-            list_samples_by_category(&category, database).await
+            list_samples_by_category(&category, &load_config(&overrides, database)?).await
+        }
+        Some(Commands::Search { query, bpm, key, sample_type, category, tags, pack, provider, downloaded_after, sample_rate, bit_depth, channels, levels, sort, limit, database }) => {
+            init_tracing(false)?;
+            let category = category
+                .map(|name| name.parse::<metadata::BitwigCategory>()
+                    .map_err(|_| anyhow::anyhow!("Invalid category '{}'", name)))
+                .transpose()?;
+            let text = query.join(" ");
+            let config = load_config(&overrides, database)?;
+            let query = search::SearchQuery {
+                text: (!text.trim().is_empty()).then_some(text),
+                bpm,
+                key,
+                sample_type,
+                category,
+                tags,
+                pack,
+                provider,
+                downloaded_after,
+                sample_rate,
+                bit_depth,
+                channels,
+                lufs: levels.lufs,
+                true_peak: levels.true_peak,
+                peak: levels.peak,
+                rms: levels.rms,
+                dc_offset: levels.dc_offset,
+                min_detection_confidence: config.library.min_detection_confidence,
+                sort,
+                limit: (limit > 0).then_some(limit),
+            };
+            search_samples(query, &config).await
+        }
+        Some(Commands::Packs { filter, database }) => {
+            init_tracing(false)?;
+            list_packs(filter.as_deref(), &load_config(&overrides, database)?).await
+        }
+        Some(Commands::Tags { database }) => {
+            init_tracing(false)?;
+            list_tags(&load_config(&overrides, database)?).await
+        }
+        Some(Commands::Reorganize { apply }) => {
+            init_tracing(false)?;
+            reorganize_library(apply, &load_config(&overrides, None)?).await
+        }
+        Some(Commands::Analyze { files, force, database }) => {
+            init_tracing(false)?;
+            if files.is_empty() {
+                analyze_library(force, &load_config(&overrides, database)?).await
+            } else {
+                analyze_files(&files)
+            }
+        }
+        Some(Commands::Loudness { files, force, database }) => {
+            init_tracing(false)?;
+            if files.is_empty() {
+                measure_library(force, &load_config(&overrides, database)?).await
+            } else {
+                measure_files(&files)
+            }
+        }
+        Some(Commands::Retag { loop_chunks, database }) => {
+            init_tracing(false)?;
+            retag_library(loop_chunks, &load_config(&overrides, database)?).await
+        }
+        Some(Commands::Review { below, database }) => {
+            init_tracing(false)?;
+            review_low_confidence(below, &load_config(&overrides, database)?).await
         }
         Some(Commands::UpdatePath { file_hash, new_path, database }) => {
             init_tracing(false)?;
-            update_sample_path(&file_hash, &new_path, database).await
+            update_sample_path(&file_hash, &new_path, &load_config(&overrides, database)?).await
         }
         None => {
             // Default command is run (not daemonized)
             init_tracing(false)?;
-            run_daemon(args).await
+            run_daemon(&overrides).await
         }
     }
 }
 
-async fn list_samples_by_category(category: &str, database: Option<PathBuf>) -> Result<()> {
-    let database_path = database.unwrap_or_else(default_db_path);
+async fn list_samples_by_category(category: &str, config: &config::Config) -> Result<()> {
+    let database_path = config.paths.database.clone();
     
     // Initialize database if it doesn't exist
     if !database_path.exists() {
-        println!("{} Database doesn't exist, initializing...", style("📦").blue());
+        say!("{} Database doesn't exist, initializing...", style("📦").blue());
         db::init_database(&database_path)?;
     }
     
     // Parse the category string to BitwigCategory using strum
     let bitwig_category: metadata::BitwigCategory = category.parse()
         .map_err(|_| {
-            println!("{} Invalid category '{}'. Available categories:", style("❌").red(), category);
-            println!("   Bass, Bell, Brass, Chip, Cymbal, Drone, Drum Loop,");
-            println!("   Guitar, Hi-hat, Keyboards, Kick, Lead, Mallet,");
-            println!("   Orchestral, Organ, Other Drums, Pad, Percussion,");
-            println!("   Piano, Snare, Sound FX, Strings, Synth, Tom,");
-            println!("   Unknown, Vocal, Winds");
+            say!("{} Invalid category '{}'. Available categories:", style("❌").red(), category);
+            say!("   Bass, Bell, Brass, Chip, Cymbal, Drone, Drum Loop,");
+            say!("   Guitar, Hi-hat, Keyboards, Kick, Lead, Mallet,");
+            say!("   Orchestral, Organ, Other Drums, Pad, Percussion,");
+            say!("   Piano, Snare, Sound FX, Strings, Synth, Tom,");
+            say!("   Unknown, Vocal, Winds");
             anyhow::anyhow!("Invalid category")
         })?;
     
-    println!("{} Listing samples in category: {}", style("📂").blue(), bitwig_category.as_str());
+    say!("{} Listing samples in category: {}", style("📂").blue(), bitwig_category.as_str());
     
     match db::get_samples_by_category(&database_path, bitwig_category) {
+        Ok(samples) if !output::is_table() => output::emit_list(&samples)?,
         Ok(samples) => {
             if samples.is_empty() {
                 println!("No samples found in category '{}'", category);
             } else {
                 println!("Found {} samples:", samples.len());
                 println!();
-                
-                let mut current_pack = String::new();
-                for sample in samples {
-                    if sample.pack_name != current_pack {
-                        current_pack = sample.pack_name.clone();
-                        println!("{} {}", style("📦").blue(), current_pack);
-                    }
-                    
-                    let bpm_str = sample.bpm.map_or("--".to_string(), |b| b.to_string());
-                    let key_str = sample.audio_key.unwrap_or_else(|| "--".to_string());
-                    
-                    println!("   {} {} ({}bpm, {})", style("🎵").cyan(), sample.filename, bpm_str, key_str);
-                    println!("      {} {}", style("📁").dim(), sample.file_path);
-                    
-                    // Parse and display tags
-                    if let Ok(tags) = serde_json::from_str::<Vec<String>>(&sample.tags) {
-                        if !tags.is_empty() {
-                            println!("      {} {}", style("🏷️").dim(), tags.join(", "));
-                        }
-                    }
-                    println!();
-                }
+                print_samples(samples, false, config.library.min_detection_confidence);
             }
         }
+        Err(e) if !output::is_table() => return Err(e),
         Err(e) => {
             error!("Failed to query database: {}", e);
             println!("{} Failed to query database: {}", style("❌").red(), e);
@@ -487,41 +1142,497 @@ async fn list_samples_by_category(category: &str, database: Option<PathBuf>) ->
     Ok(())
 }
 
-async fn update_sample_path(file_hash: &str, new_path: &PathBuf, database: Option<PathBuf>) -> Result<()> {
-    let database_path = database.unwrap_or_else(default_db_path);
-    
-    // Initialize database if it doesn't exist
-    if !database_path.exists() {
-        println!("{} Database doesn't exist, initializing...", style("📦").blue());
-        db::init_database(&database_path)?;
+/// Print samples grouped under a header whenever the pack changes
+/// Estimates standing in for a missing BPM or key are marked with `~`
+fn print_samples(samples: Vec<db::SampleRecord>, show_category: bool, min_confidence: f64) {
+    let mut current_pack = String::new();
+    for sample in samples {
+        if sample.pack_name != current_pack {
+            current_pack = sample.pack_name.clone();
+            println!("{} {}", style("📦").blue(), current_pack);
+        }
+        
+        let detected = sample.analysis().unwrap_or_default();
+        let bpm_str = match (sample.bpm, detected.confident_bpm(min_confidence)) {
+            (Some(bpm), _) => bpm.to_string(),
+            (None, Some(bpm)) => format!("~{}", bpm),
+            (None, None) => "--".to_string(),
+        };
+        let key_str = match (sample.audio_key, detected.confident_key(min_confidence)) {
+            (Some(key), _) => key,
+            (None, Some((key, chord_type))) => format!("~{}{}", key, if chord_type == "minor" { "m" } else { "" }),
+            (None, None) => "--".to_string(),
+        };
+        
+        println!("   {} {} ({}bpm, {})", style("🎵").cyan(), sample.filename, bpm_str, key_str);
+        if show_category {
+            println!("      {} {}", style("📂").dim(), sample.mapped_category);
+        }
+        println!("      {} {}", style("📁").dim(), sample.file_path);
+        if let Some(lufs) = sample.loudness_lufs {
+            let true_peak = sample.true_peak_dbtp.map_or_else(|| "-inf".to_string(), |peak| format!("{:.1}", peak));
+            println!("      {} {:.1} LUFS, true peak {} dBTP", style("🔊").dim(), lufs, true_peak);
+        }
+        
+        // Parse and display tags
+        if let Ok(tags) = serde_json::from_str::<Vec<String>>(&sample.tags) {
+            if !tags.is_empty() {
+                println!("      {} {}", style("🏷️").dim(), tags.join(", "));
+            }
+        }
+        println!();
     }
+}
+
+async fn search_samples(query: search::SearchQuery, config: &config::Config) -> Result<()> {
+    let database_path = config.paths.database.clone();
     
-    println!("{} Updating file path for sample with hash: {}", style("🔧").blue(), file_hash);
-    println!("{} New path: {:?}", style("📁").blue(), new_path);
+    // Also brings older databases up to date so the full-text index exists
+    db::init_database(&database_path)?;
     
-    // Verify the new file actually exists
-    if !new_path.exists() {
-        let msg = "Error: File does not exist at the specified path";
-        error!("{}", msg);
-        println!("{} {}", style("❌").red(), msg);
-        println!("Please make sure the file exists before updating the database");
-        return Ok(());
+    match &query.text {
+        Some(text) => say!("{} Searching samples for: {}", style("🔍").blue(), text),
+        None => say!("{} Searching samples", style("🔍").blue()),
     }
     
-    if !new_path.is_file() {
-        let msg = "Error: Path exists but is not a file";
-        error!("{}", msg);
-        println!("{} {}", style("❌").red(), msg);
-        return Ok(());
+    match search::search_samples(&database_path, &query) {
+        Ok(samples) if !output::is_table() => output::emit_list(&samples)?,
+        Ok(samples) => {
+            if samples.is_empty() {
+                println!("No samples match the search");
+            } else {
+                let limited = query.limit.is_some_and(|limit| samples.len() == limit);
+                if limited {
+                    println!("Showing the first {} matches (use --limit to see more):", samples.len());
+                } else {
+                    println!("Found {} samples:", samples.len());
+                }
+                println!();
+                print_samples(samples, query.category.is_none(), query.min_detection_confidence);
+            }
+        }
+        Err(e) if !output::is_table() => return Err(e),
+        Err(e) => {
+            error!("Failed to search database: {}", e);
+            println!("{} Failed to search database: {}", style("❌").red(), e);
+        }
     }
     
-    match db::update_file_path(&database_path, file_hash, &new_path.to_string_lossy()) {
-        Ok(()) => {
-            println!("{} Successfully updated file path in database", style("✅").green());
-            
-            // Verify the update by fetching the sample
-            if let Ok(Some(sample)) = db::get_sample_by_hash(&database_path, file_hash) {
-                println!("{} Sample details:", style("📊").blue());
+    Ok(())
+}
+
+async fn list_packs(filter: Option<&str>, config: &config::Config) -> Result<()> {
+    let database_path = config.paths.database.clone();
+    db::init_database(&database_path)?;
+    
+    say!("{} Packs in the library", style("📦").blue());
+    
+    let packs = db::get_packs(&database_path, filter)?;
+    if !output::is_table() {
+        return output::emit_list(&packs);
+    }
+    if packs.is_empty() {
+        println!("No packs found");
+        return Ok(());
+    }
+    
+    println!("Found {} packs:", packs.len());
+    println!();
+    
+    for pack in packs {
+        let provider = pack.provider_name.unwrap_or_else(|| "--".to_string());
+        let genre = pack.main_genre.unwrap_or_else(|| "--".to_string());
+        let total = pack.sample_count.map_or("?".to_string(), |c| c.to_string());
+        
+        println!("{} {} ({}, {})", style("📦").blue(), pack.name, provider, genre);
+        println!("   {} {} of {} samples in library", style("🎵").cyan(), pack.samples_in_library, total);
+        if let Some(permalink) = pack.permalink {
+            println!("   {} {}", style("🔗").dim(), permalink);
+        }
+        if let Some(description) = pack.description {
+            println!("   {} {}", style("📝").dim(), description);
+        }
+        println!();
+    }
+    
+    Ok(())
+}
+
+async fn list_tags(config: &config::Config) -> Result<()> {
+    let database_path = config.paths.database.clone();
+    db::init_database(&database_path)?;
+    
+    let tags = db::get_tag_counts(&database_path)?;
+    if !output::is_table() {
+        return output::emit_list(&tags);
+    }
+    if tags.is_empty() {
+        println!("No tags found");
+        return Ok(());
+    }
+    
+    println!("{} {} tags:", style("🏷️").blue(), tags.len());
+    for tag in tags {
+        println!("   {:>5}  {}", tag.samples, tag.name);
+    }
+    
+    Ok(())
+}
+
+async fn reorganize_library(apply: bool, config: &config::Config) -> Result<()> {
+    let library_dir = config.paths.library_dir.clone();
+    let database_path = config.paths.database.clone();
+    let layout = config.layout()?;
+    let rules = config.category_rules()?;
+    
+    db::init_database(&database_path)?;
+    
+    say!("{} Reorganizing library: {:?}", style("🗂️").blue(), library_dir);
+    say!("{} Rules: {}", style("📐").dim(), rules.source_description());
+    say!("{} Layout: {}", style("📁").dim(), layout);
+    say!();
+    
+    let plan = reorganize::plan(&database_path, &library_dir, &rules, &layout, config.library.on_collision, config.library.min_detection_confidence)?;
+    
+    for planned in &plan.moves {
+        if planned.is_category_only() {
+            say!("   {} {:?}", style("🏷️").cyan(), planned.from);
+            say!("      {} → {}", planned.old_category, planned.new_category);
+        } else {
+            say!("   {} {:?}", style("📦").cyan(), planned.from);
+            say!("      → {:?}", planned.to);
+            if planned.old_category != planned.new_category {
+                say!("      {} → {}", planned.old_category, planned.new_category);
+            }
+        }
+    }
+    for (from, to) in &plan.conflicts {
+        say!("   {} {:?}", style("⚠️").yellow(), from);
+        say!("      target already taken: {:?}", to);
+    }
+    for missing in &plan.missing {
+        say!("   {} Missing from library: {:?}", style("❓").yellow(), missing);
+    }
+    
+    say!();
+    say!("{} Plan: {} to update, {} unchanged, {} conflicts, {} missing",
+        style("📊").blue(),
+        plan.moves.len(),
+        plan.unchanged,
+        plan.conflicts.len(),
+        plan.missing.len());
+    
+    if plan.moves.is_empty() {
+        say!("{} Library already matches the current rules and layout", style("✅").green());
+        return finish_reorganize(&plan, None);
+    }
+    
+    if !apply {
+        say!("Dry run only. Re-run with --apply to perform these changes.");
+        return finish_reorganize(&plan, None);
+    }
+    
+    let summary = reorganize::apply(&plan, &database_path, &library_dir);
+    say!("{} Moved {} files, recategorized {} in place, removed {} empty directories",
+        style("✅").green(),
+        summary.moved,
+        summary.recategorized,
+        summary.removed_dirs);
+    if !summary.failed.is_empty() {
+        say!("{} {} changes failed:", style("🚨").red(), summary.failed.len());
+        for (path, error) in &summary.failed {
+            say!("   {:?}: {}", path, error);
+        }
+    }
+    
+    finish_reorganize(&plan, Some(&summary))
+}
+
+/// Result of `reorganize`; `summary` is only present with --apply
+#[derive(Serialize)]
+struct ReorganizeReport<'a> {
+    plan: &'a reorganize::ReorganizePlan,
+    summary: Option<&'a reorganize::ReorganizeSummary>,
+}
+
+impl output::PlainText for ReorganizeReport<'_> {}
+
+fn finish_reorganize(plan: &reorganize::ReorganizePlan, summary: Option<&reorganize::ReorganizeSummary>) -> Result<()> {
+    if !output::is_table() {
+        output::emit(&ReorganizeReport { plan, summary })?;
+    }
+    Ok(())
+}
+
+async fn analyze_library(force: bool, config: &config::Config) -> Result<()> {
+    let database_path = config.paths.database.clone();
+    db::init_database(&database_path)?;
+    
+    say!("{} Estimating BPM and key of library samples...", style("🎼").blue());
+    let summary = analysis::analyze_library(&database_path, force)?;
+    
+    for missing in &summary.missing {
+        say!("   {} Missing from library: {:?}", style("❓").yellow(), missing);
+    }
+    say!("{} Analyzed {} samples, {} already analyzed, skipped {} in other formats, {} missing",
+        style("✅").green(),
+        summary.analyzed,
+        summary.already_analyzed,
+        summary.skipped,
+        summary.missing.len());
+    if summary.already_analyzed > 0 && !force {
+        say!("   Use --force to analyze those again");
+    }
+    if !summary.failed.is_empty() {
+        say!("{} {} samples failed:", style("🚨").red(), summary.failed.len());
+        for (path, error) in &summary.failed {
+            say!("   {:?}: {}", path, error);
+        }
+    }
+    
+    if !output::is_table() {
+        output::emit(&summary)?;
+    }
+    Ok(())
+}
+
+/// Estimates for one file given to `analyze`
+#[derive(Serialize)]
+struct FileAnalysis {
+    file: PathBuf,
+    #[serde(flatten)]
+    analysis: analysis::Analysis,
+}
+
+impl output::PlainText for FileAnalysis {}
+
+fn analyze_files(files: &[PathBuf]) -> Result<()> {
+    let mut results = Vec::new();
+    let mut failed = 0;
+    for file in files {
+        match analysis::analyze_file(file) {
+            Ok(analysis) => {
+                say!("{} {:?}: {}", style("🎼").cyan(), file, analysis.describe());
+                results.push(FileAnalysis { file: file.clone(), analysis });
+            }
+            Err(e) => {
+                eprintln!("{} Failed to analyze {:?}: {}", style("🚨").red(), file, e);
+                failed += 1;
+            }
+        }
+    }
+    
+    if !output::is_table() {
+        output::emit_list(&results)?;
+    }
+    if failed == files.len() {
+        anyhow::bail!("No files could be analyzed");
+    }
+    Ok(())
+}
+
+async fn measure_library(force: bool, config: &config::Config) -> Result<()> {
+    let database_path = config.paths.database.clone();
+    db::init_database(&database_path)?;
+    
+    say!("{} Measuring loudness of library samples...", style("🔊").blue());
+    let summary = loudness::measure_library(&database_path, force)?;
+    
+    for missing in &summary.missing {
+        say!("   {} Missing from library: {:?}", style("❓").yellow(), missing);
+    }
+    say!("{} Measured {} samples, {} already measured, skipped {} in other formats, {} missing",
+        style("✅").green(),
+        summary.measured,
+        summary.already_measured,
+        summary.skipped,
+        summary.missing.len());
+    if summary.already_measured > 0 && !force {
+        say!("   Use --force to measure those again");
+    }
+    if !summary.failed.is_empty() {
+        say!("{} {} samples failed:", style("🚨").red(), summary.failed.len());
+        for (path, error) in &summary.failed {
+            say!("   {:?}: {}", path, error);
+        }
+    }
+    
+    if !output::is_table() {
+        output::emit(&summary)?;
+    }
+    Ok(())
+}
+
+/// Measurements for one file given to `loudness`
+#[derive(Serialize)]
+struct FileLoudness {
+    file: PathBuf,
+    #[serde(flatten)]
+    loudness: loudness::Loudness,
+}
+
+impl output::PlainText for FileLoudness {}
+
+fn measure_files(files: &[PathBuf]) -> Result<()> {
+    let mut results = Vec::new();
+    let mut failed = 0;
+    for file in files {
+        match loudness::measure_file(file) {
+            Ok(loudness) => {
+                say!("{} {:?}: {}", style("🔊").cyan(), file, loudness.describe());
+                results.push(FileLoudness { file: file.clone(), loudness });
+            }
+            Err(e) => {
+                eprintln!("{} Failed to measure {:?}: {}", style("🚨").red(), file, e);
+                failed += 1;
+            }
+        }
+    }
+    
+    if !output::is_table() {
+        output::emit_list(&results)?;
+    }
+    if failed == files.len() {
+        anyhow::bail!("No files could be measured");
+    }
+    Ok(())
+}
+
+async fn retag_library(loop_chunks: bool, config: &config::Config) -> Result<()> {
+    let database_path = config.paths.database.clone();
+    db::init_database(&database_path)?;
+    
+    say!("{} Embedding metadata into library WAV files...", style("🏷️").blue());
+    let options = tagging::EmbedOptions {
+        metadata: true,
+        loop_chunks: loop_chunks || config.watcher.embed_loop_chunks,
+    };
+    let summary = tagging::retag_library(&database_path, options)?;
+    
+    for missing in &summary.missing {
+        say!("   {} Missing from library: {:?}", style("❓").yellow(), missing);
+    }
+    say!("{} Tagged {} files, skipped {} in other formats, {} missing",
+        style("✅").green(),
+        summary.tagged,
+        summary.skipped,
+        summary.missing.len());
+    if !summary.failed.is_empty() {
+        say!("{} {} files failed:", style("🚨").red(), summary.failed.len());
+        for (path, error) in &summary.failed {
+            say!("   {:?}: {}", path, error);
+        }
+    }
+    
+    if !output::is_table() {
+        output::emit(&summary)?;
+    }
+    Ok(())
+}
+
+async fn review_low_confidence(threshold: f64, config: &config::Config) -> Result<()> {
+    let database_path = config.paths.database.clone();
+    
+    // Initialize database if it doesn't exist
+    if !database_path.exists() {
+        say!("{} Database doesn't exist, initializing...", style("📦").blue());
+        db::init_database(&database_path)?;
+    }
+    
+    say!("{} Samples categorized with less than {:.0}% confidence", style("🔍").blue(), threshold * 100.0);
+    
+    let samples = db::get_low_confidence_samples(&database_path, threshold)?;
+    if !output::is_table() {
+        return output::emit_list(&samples);
+    }
+    if samples.is_empty() {
+        println!("No low-confidence samples found");
+        return Ok(());
+    }
+    
+    println!("Found {} samples:", samples.len());
+    println!();
+    
+    for sample in samples {
+        let confidence = sample.category_confidence.unwrap_or(0.0);
+        let runner_up = sample.runner_up_category.unwrap_or_else(|| "--".to_string());
+        
+        println!("   {} {} [{}]", style("🎵").cyan(), sample.filename, sample.pack_name);
+        println!("      {} {} ({:.0}%), runner-up: {}", style("📂").dim(), sample.mapped_category, confidence * 100.0, runner_up);
+        println!("      {} {}", style("📁").dim(), sample.file_path);
+        
+        if let Ok(tags) = serde_json::from_str::<Vec<String>>(&sample.tags) {
+            if !tags.is_empty() {
+                println!("      {} {}", style("🏷️").dim(), tags.join(", "));
+            }
+        }
+        println!();
+    }
+    
+    Ok(())
+}
+
+/// Result of `update-path`
+#[derive(Serialize)]
+struct UpdatePathReport {
+    file_hash: String,
+    /// Whether a sample with this hash exists
+    updated: bool,
+    sample: Option<db::SampleRecord>,
+}
+
+impl output::PlainText for UpdatePathReport {}
+
+async fn update_sample_path(file_hash: &str, new_path: &PathBuf, config: &config::Config) -> Result<()> {
+    let database_path = config.paths.database.clone();
+    
+    // Initialize database if it doesn't exist
+    if !database_path.exists() {
+        say!("{} Database doesn't exist, initializing...", style("📦").blue());
+        db::init_database(&database_path)?;
+    }
+    
+    say!("{} Updating file path for sample with hash: {}", style("🔧").blue(), file_hash);
+    say!("{} New path: {:?}", style("📁").blue(), new_path);
+    
+    // Verify the new file actually exists
+    if !new_path.exists() {
+        let msg = "Error: File does not exist at the specified path";
+        error!("{}", msg);
+        if !output::is_table() {
+            anyhow::bail!("File does not exist at {:?}", new_path);
+        }
+        println!("{} {}", style("❌").red(), msg);
+        println!("Please make sure the file exists before updating the database");
+        return Ok(());
+    }
+    
+    if !new_path.is_file() {
+        let msg = "Error: Path exists but is not a file";
+        error!("{}", msg);
+        if !output::is_table() {
+            anyhow::bail!("{:?} exists but is not a file", new_path);
+        }
+        println!("{} {}", style("❌").red(), msg);
+        return Ok(());
+    }
+    
+    match db::update_file_path(&database_path, file_hash, &new_path.to_string_lossy()) {
+        Ok(()) if !output::is_table() => {
+            let sample = db::get_sample_by_hash(&database_path, file_hash)?;
+            output::emit(&UpdatePathReport {
+                file_hash: file_hash.to_string(),
+                updated: sample.is_some(),
+                sample,
+            })?;
+        }
+        Err(e) if !output::is_table() => return Err(e),
+        Ok(()) => {
+            println!("{} Successfully updated file path in database", style("✅").green());
+            
+            // Verify the update by fetching the sample
+            if let Ok(Some(sample)) = db::get_sample_by_hash(&database_path, file_hash) {
+                println!("{} Sample details:", style("📊").blue());
                 println!("   {} Pack: {}", style("📦").dim(), sample.pack_name);
                 println!("   {} File: {}", style("🎵").dim(), sample.filename);
                 println!("   {} Path: {}", style("📁").dim(), sample.file_path);
@@ -540,21 +1651,584 @@ async fn update_sample_path(file_hash: &str, new_path: &PathBuf, database: Optio
     Ok(())
 }
 
-async fn process_files_directly(wav_file: &PathBuf, json_file: &PathBuf, library_dir: &PathBuf, database: &PathBuf) -> Result<()> {
-    println!("{} Direct file processing test", style("🔧").blue());
-    println!("WAV: {:?}", wav_file);
-    println!("JSON: {:?}", json_file);
-    println!("Library: {:?}", library_dir);
-    println!("Database: {:?}", database);
+/// Result of `process`
+#[derive(Serialize)]
+struct ProcessReport {
+    outcome: watcher::PairOutcome,
+    sample: Option<db::SampleRecord>,
+}
+
+impl output::PlainText for ProcessReport {}
+
+async fn process_files_directly(audio_file: &PathBuf, json_file: &PathBuf, config: &config::Config) -> Result<()> {
+    let database = &config.paths.database;
+    
+    say!("{} Direct file processing test", style("🔧").blue());
+    say!("Audio: {:?}", audio_file);
+    say!("JSON: {:?}", json_file);
+    say!("Library: {:?}", config.paths.library_dir);
+    say!("Database: {:?}", database);
     
     // Initialize database
     db::init_database(database)?;
     
+    // The audio file is moved by the import, so note which sample it is first
+    let file_hash = integrity::sha256_file(audio_file).ok();
+    
     // Process the files
-    watcher::process_sample_pair(wav_file, json_file, library_dir, database).await
+    let outcome = watcher::process_sample_pair(audio_file, json_file, config).await?;
+    
+    if !output::is_table() {
+        let sample = match file_hash {
+            Some(file_hash) => db::get_sample_by_hash(database, &file_hash)?,
+            None => None,
+        };
+        output::emit(&ProcessReport { outcome, sample })?;
+    }
+    
+    Ok(())
+}
+
+async fn scan_watch_directory(config: &config::Config) -> Result<()> {
+    say!("{} Reconciliation scan", style("🔎").blue());
+    say!("Watching: {:?}", config.paths.watch_dir);
+    say!("Library: {:?}", config.paths.library_dir);
+    say!("Database: {:?}", config.paths.database);
+    
+    db::init_database(&config.paths.database)?;
+    
+    let summary = watcher::scan_directory(config).await?;
+    if output::is_table() {
+        summary.print();
+    } else {
+        output::emit(&summary)?;
+    }
+    
+    Ok(())
+}
+
+/// A pending-queue entry as reported by `pending list`
+#[derive(Serialize)]
+struct PendingEntry {
+    #[serde(flatten)]
+    record: db::PendingRecord,
+    file_exists: bool,
+    partner_path: PathBuf,
+    partner_present: bool,
 }
 
-async fn test_metadata_parsing(metadata_file: &PathBuf) -> Result<()> {
+impl output::PlainText for PendingEntry {
+    fn plain_lines(&self) -> Vec<String> {
+        vec![output::plain_row([
+            self.record.id.to_string(),
+            self.record.file_kind.to_string(),
+            self.record.file_path.clone(),
+            self.partner_present.to_string(),
+            self.record.first_seen.clone(),
+            self.record.last_error.clone().unwrap_or_default(),
+        ])]
+    }
+}
+
+/// Result of `pending purge`
+#[derive(Serialize)]
+struct PurgeReport {
+    purged: Vec<db::PendingRecord>,
+    /// Files removed from disk with --delete-files
+    deleted: Vec<PathBuf>,
+    #[serde(serialize_with = "output::failures")]
+    failed: Vec<(PathBuf, String)>,
+}
+
+impl output::PlainText for PurgeReport {}
+
+async fn manage_pending(action: &PendingCommands, config: &config::Config) -> Result<()> {
+    let database_path = config.paths.database.clone();
+    
+    db::init_database(&database_path)?;
+    
+    match action {
+        PendingCommands::List => {
+            let pending = db::get_pending_files(&database_path)?;
+            if !output::is_table() {
+                let entries: Vec<PendingEntry> = pending
+                    .into_iter()
+                    .map(|record| {
+                        let path = PathBuf::from(&record.file_path);
+                        let partner_path = record.file_kind.partner_path(&path, &config.watcher);
+                        PendingEntry {
+                            file_exists: path.exists(),
+                            partner_present: partner_path.exists(),
+                            partner_path,
+                            record,
+                        }
+                    })
+                    .collect();
+                return output::emit_list(&entries);
+            }
+            if pending.is_empty() {
+                println!("{} No files waiting for a partner", style("✅").green());
+                return Ok(());
+            }
+            
+            println!("{} {} file(s) waiting for a partner:", style("⏳").yellow(), pending.len());
+            println!();
+            for record in pending {
+                let path = PathBuf::from(&record.file_path);
+                let partner = record.file_kind.partner_path(&path, &config.watcher);
+                let icon = match record.file_kind {
+                    db::PendingKind::Audio => "🎵",
+                    db::PendingKind::Json => "📄",
+                };
+                
+                println!("   {} [{}] {}", style(icon).cyan(), record.id, record.file_path);
+                println!("      {} Waiting for: {:?}{}", style("🔗").dim(), partner,
+                    if partner.exists() { " (present, run `pending resolve`)" } else { "" });
+                if !path.exists() {
+                    println!("      {} File no longer exists", style("⚠️").yellow());
+                }
+                println!("      {} First seen: {}, last seen: {}", style("🕒").dim(), record.first_seen, record.last_seen);
+                if let Some(error) = &record.last_error {
+                    println!("      {} Last error: {}", style("🚨").red(), error);
+                }
+                println!();
+            }
+        }
+        PendingCommands::Resolve { id: None, .. } => {
+            say!("{} Resolving pending pairs...", style("🔗").blue());
+            let summary = watcher::resolve_pending(config).await?;
+            if output::is_table() {
+                summary.print();
+            } else {
+                output::emit(&summary)?;
+            }
+        }
+        PendingCommands::Resolve { id: Some(id), metadata } => {
+            let Some(record) = db::get_pending_file(&database_path, *id)? else {
+                if !output::is_table() {
+                    anyhow::bail!("No pending entry with ID {}", id);
+                }
+                println!("{} No pending entry with ID {}", style("❌").red(), id);
+                return Ok(());
+            };
+            
+            if metadata.is_some() && record.file_kind == db::PendingKind::Json {
+                if !output::is_table() {
+                    anyhow::bail!("Pending entry {} is a JSON file; --metadata only applies to audio entries", id);
+                }
+                println!("{} Pending entry {} is a JSON file; --metadata only applies to audio entries", style("❌").red(), id);
+                return Ok(());
+            }
+            
+            let path = PathBuf::from(&record.file_path);
+            let partner = metadata.clone().unwrap_or_else(|| record.file_kind.partner_path(&path, &config.watcher));
+            if !partner.exists() {
+                if !output::is_table() {
+                    anyhow::bail!("Partner file not found: {:?}", partner);
+                }
+                println!("{} Partner file not found: {:?}", style("❌").red(), partner);
+                println!("   Pass --metadata <json> to pair an audio file with a specific metadata file");
+                return Ok(());
+            }
+            
+            say!("{} Resolving pending entry {} with {:?}", style("🔗").blue(), id, partner);
+            let summary = watcher::resolve_pending_with(&record, &partner, config).await?;
+            if output::is_table() {
+                summary.print();
+            } else {
+                output::emit(&summary)?;
+            }
+        }
+        PendingCommands::Purge { older_than, delete_files } => {
+            let purged = db::purge_pending_files(&database_path, *older_than)?;
+            let mut report = PurgeReport { purged: Vec::new(), deleted: Vec::new(), failed: Vec::new() };
+            for record in purged {
+                if *delete_files {
+                    match fs::remove_file(&record.file_path) {
+                        Ok(()) => {
+                            say!("   {} Deleted {}", style("🗑️").dim(), record.file_path);
+                            report.deleted.push(PathBuf::from(&record.file_path));
+                        }
+                        Err(e) => {
+                            say!("   {} Failed to delete {}: {}", style("⚠️").yellow(), record.file_path, e);
+                            report.failed.push((PathBuf::from(&record.file_path), e.to_string()));
+                        }
+                    }
+                } else {
+                    say!("   {} Removed {} from queue", style("🧹").dim(), record.file_path);
+                }
+                report.purged.push(record);
+            }
+            say!("{} Purged {} pending entr{}", style("✅").green(), report.purged.len(),
+                if report.purged.len() == 1 { "y" } else { "ies" });
+            if !output::is_table() {
+                output::emit(&report)?;
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+impl output::PlainText for quarantine::QuarantineEntry {
+    fn plain_lines(&self) -> Vec<String> {
+        let report = self.report.as_ref();
+        vec![output::plain_row([
+            self.id.clone(),
+            report.map(|r| r.reason.to_string()).unwrap_or_default(),
+            report.map(|r| r.quarantined_at.to_string()).unwrap_or_default(),
+            self.audio_path.to_string_lossy().to_string(),
+            report.map(|r| r.error.clone()).unwrap_or_default(),
+        ])]
+    }
+}
+
+/// Result of `quarantine retry` and `quarantine discard`
+#[derive(Serialize, Default)]
+struct QuarantineReport {
+    /// Entries imported (or found to be duplicates) and removed from quarantine
+    retried: Vec<String>,
+    /// Entries deleted with `discard`
+    discarded: Vec<String>,
+    /// Entries still in quarantine, with the error that kept them there
+    #[serde(serialize_with = "output::failures")]
+    failed: Vec<(PathBuf, String)>,
+}
+
+impl output::PlainText for QuarantineReport {}
+
+async fn manage_quarantine(action: &QuarantineCommands, config: &config::Config) -> Result<()> {
+    let quarantine_dir = config.quarantine_dir();
+    
+    let select = |id: &Option<String>| -> Result<Vec<quarantine::QuarantineEntry>> {
+        match id {
+            None => quarantine::list(&quarantine_dir),
+            Some(id) => match quarantine::find(&quarantine_dir, id)? {
+                Some(entry) => Ok(vec![entry]),
+                None => anyhow::bail!("No quarantined pair with ID '{}' in {:?}", id, quarantine_dir),
+            },
+        }
+    };
+    
+    match action {
+        QuarantineCommands::List => {
+            let entries = quarantine::list(&quarantine_dir)?;
+            if !output::is_table() {
+                return output::emit_list(&entries);
+            }
+            if entries.is_empty() {
+                println!("{} Nothing in quarantine ({:?})", style("✅").green(), quarantine_dir);
+                return Ok(());
+            }
+            
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            println!("{} {} pair(s) in quarantine ({:?}):", style("🚫").red(), entries.len(), quarantine_dir);
+            println!();
+            for entry in entries {
+                println!("   {} [{}] {:?}", style("🎵").cyan(), entry.id, entry.audio_path.file_name().unwrap_or_default());
+                match &entry.report {
+                    Some(report) => {
+                        println!("      {} Reason: {}, {} ago{}", style("🕒").dim(), report.reason,
+                            format_elapsed(now.saturating_sub(report.quarantined_at)),
+                            if report.retries > 0 { format!(", {} failed retr{}", report.retries, if report.retries == 1 { "y" } else { "ies" }) } else { String::new() });
+                        println!("      {} From: {:?}", style("📂").dim(), report.audio_file);
+                        println!("      {} Error: {}", style("🚨").red(), report.error);
+                    }
+                    None => println!("      {} No readable {}", style("⚠️").yellow(), quarantine::REPORT_FILE),
+                }
+                println!();
+            }
+        }
+        QuarantineCommands::Retry { id, force } => {
+            db::init_database(&config.paths.database)?;
+            let mut report = QuarantineReport::default();
+            for mut entry in select(id)? {
+                say!("{} Retrying quarantined pair {}", style("🔄").blue(), entry.id);
+                match watcher::retry_quarantined(&entry, *force, config).await {
+                    Ok(watcher::PairOutcome::Skipped) => {
+                        let error = "library path is taken and the collision policy is skip".to_string();
+                        say!("   {} Still in quarantine: {}", style("⚠️").yellow(), error);
+                        entry.record_retry_failure(&error)?;
+                        report.failed.push((entry.dir, error));
+                    }
+                    Ok(_) => report.retried.push(entry.id),
+                    Err(e) => {
+                        say!("   {} Still in quarantine: {}", style("🚨").red(), e);
+                        entry.record_retry_failure(&e.to_string())?;
+                        report.failed.push((entry.dir, e.to_string()));
+                    }
+                }
+            }
+            say!("{} Retried {} pair(s), {} still in quarantine", style("✅").green(), report.retried.len(), report.failed.len());
+            if !output::is_table() {
+                output::emit(&report)?;
+            }
+        }
+        QuarantineCommands::Discard { id, .. } => {
+            let mut report = QuarantineReport::default();
+            for entry in select(id)? {
+                match quarantine::discard(&entry) {
+                    Ok(()) => {
+                        say!("   {} Deleted {}", style("🗑️").dim(), entry.id);
+                        report.discarded.push(entry.id);
+                    }
+                    Err(e) => {
+                        say!("   {} {}", style("⚠️").yellow(), e);
+                        report.failed.push((entry.dir, e.to_string()));
+                    }
+                }
+            }
+            say!("{} Discarded {} pair(s)", style("✅").green(), report.discarded.len());
+            if !output::is_table() {
+                output::emit(&report)?;
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// Result of `db migrate`
+#[derive(Serialize)]
+struct MigrationReport {
+    database: PathBuf,
+    current_version: u32,
+    latest_version: u32,
+    migrations: Vec<MigrationState>,
+}
+
+#[derive(Serialize)]
+struct MigrationState {
+    version: u32,
+    description: &'static str,
+    applied: bool,
+}
+
+impl output::PlainText for MigrationReport {}
+
+impl MigrationReport {
+    fn new(database: PathBuf, current_version: u32) -> Self {
+        let migrations = migrations::MIGRATIONS
+            .iter()
+            .map(|migration| MigrationState {
+                version: migration.version,
+                description: migration.description,
+                applied: migration.version <= current_version,
+            })
+            .collect();
+        Self { database, current_version, latest_version: migrations::latest_version(), migrations }
+    }
+}
+
+async fn migrate_database(status_only: bool, config: &config::Config) -> Result<()> {
+    let database_path = config.paths.database.clone();
+    
+    if status_only {
+        // Don't create a database just to report on it
+        let current = if database_path.exists() {
+            let conn = rusqlite::Connection::open(&database_path)?;
+            migrations::current_version(&conn)?
+        } else {
+            0
+        };
+        
+        let report = MigrationReport::new(database_path, current);
+        if !output::is_table() {
+            return output::emit(&report);
+        }
+        
+        println!("{} Schema migrations for {:?}", style("🗄️").blue(), report.database);
+        println!("Current version: {} (latest: {})", report.current_version, report.latest_version);
+        println!();
+        for migration in &report.migrations {
+            let state = if migration.applied {
+                style("applied").green()
+            } else {
+                style("pending").yellow()
+            };
+            println!("   {:>3}  {:<8} {}", migration.version, state, migration.description);
+        }
+        if report.current_version > report.latest_version {
+            println!();
+            println!("{} Database is newer than this build supports", style("⚠️").yellow());
+        }
+        return Ok(());
+    }
+    
+    if let Some(parent) = database_path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| anyhow::anyhow!("Failed to create database directory {:?}: {}", parent, e))?;
+    }
+    
+    let mut conn = rusqlite::Connection::open(&database_path)?;
+    let applied = migrations::migrate(&mut conn, &database_path)?;
+    
+    if !output::is_table() {
+        let current = migrations::current_version(&conn)?;
+        return output::emit(&MigrationReport::new(database_path, current));
+    }
+    
+    if applied.is_empty() {
+        println!("{} Database is up to date (version {})", style("✅").green(), migrations::latest_version());
+    } else {
+        println!("{} Applied {} migrations, database is at version {}", style("✅").green(), applied.len(), migrations::latest_version());
+    }
+    
+    Ok(())
+}
+
+/// Result of `config show`
+#[derive(Serialize)]
+struct ConfigReport<'a> {
+    /// File the settings were read from, `None` when only defaults apply
+    file: Option<&'a PathBuf>,
+    #[serde(flatten)]
+    config: &'a config::Config,
+}
+
+impl output::PlainText for ConfigReport<'_> {}
+
+/// Result of `config validate`
+#[derive(Serialize)]
+struct ConfigValidateReport {
+    file: Option<PathBuf>,
+    exists: bool,
+    valid: bool,
+}
+
+impl output::PlainText for ConfigValidateReport {}
+
+async fn manage_config(action: &ConfigCommands, overrides: &config::Overrides) -> Result<()> {
+    match action {
+        ConfigCommands::Show => {
+            let config = config::Config::load(overrides)?;
+            if !output::is_table() {
+                return output::emit(&ConfigReport { file: config.source.as_ref(), config: &config });
+            }
+            
+            println!("{} Effective configuration ({})", style("⚙️").blue(), config.source_description());
+            println!();
+            print!("{}", toml::to_string_pretty(&config)?);
+        }
+        ConfigCommands::Validate { file: Some(file) } => {
+            config::Config::from_file(file)?;
+            if !output::is_table() {
+                return output::emit(&ConfigValidateReport { file: Some(file.clone()), exists: true, valid: true });
+            }
+            println!("{} {:?} is valid", style("✅").green(), file);
+        }
+        ConfigCommands::Validate { file: None } => {
+            let file = config::Config::file_path(overrides)?;
+            let exists = file.as_ref().is_some_and(|path| path.exists());
+            
+            // Check the file together with environment overrides and flags, as the daemon would
+            config::Config::load(overrides)?;
+            if !output::is_table() {
+                return output::emit(&ConfigValidateReport { file, exists, valid: true });
+            }
+            
+            match &file {
+                Some(path) if exists => println!("{} {:?} is valid", style("✅").green(), path),
+                Some(path) => println!("{} No config file at {:?}, defaults and overrides are valid", style("✅").green(), path),
+                None => println!("{} No config directory, defaults and overrides are valid", style("✅").green()),
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// Result of `rules test`
+#[derive(Serialize)]
+struct RulesTestReport {
+    rules: String,
+    filename: String,
+    #[serde(flatten)]
+    categorization: rules::Categorization,
+}
+
+impl output::PlainText for RulesTestReport {}
+
+async fn test_category_rules(metadata_file: &PathBuf, config: &config::Config) -> Result<()> {
+    let metadata = metadata::SampleMetadata::from_file(metadata_file)
+        .map_err(|e| anyhow::anyhow!("Failed to parse metadata from {:?}: {}", metadata_file, e))?;
+    let rules = config.category_rules()?;
+    let sample = &metadata.sample_meta_data;
+    
+    if !output::is_table() {
+        return output::emit(&RulesTestReport {
+            rules: rules.source_description(),
+            filename: sample.filename.clone(),
+            categorization: metadata.categorize(&rules),
+        });
+    }
+    
+    println!("{} Testing category rules", style("🧪").blue());
+    println!("{} Rules: {}", style("📐").dim(), rules.source_description());
+    println!("{} File: {}", style("🎵").dim(), sample.filename);
+    println!("{} Tags: {}", style("🏷️").dim(), sample.tags.join(", "));
+    println!("{} Type: {}, genre: {}, key: {}", style("🎼").dim(),
+        sample.sample_type,
+        sample.pack.main_genre,
+        sample.audio_key.as_deref().unwrap_or("--"));
+    println!();
+    
+    let result = metadata.categorize(&rules);
+    match result.fired() {
+        Some(fired) => {
+            println!("{} Category: {} (confidence {:.0}%)", style("✅").green(), result.category.as_str(), result.confidence * 100.0);
+            if let Some(runner_up) = &result.runner_up {
+                println!("   Runner-up: {}", runner_up.as_str());
+            }
+            println!("   Fired rule #{} (score {:.2}, priority {})", fired.rule_index + 1, fired.score, fired.priority);
+            for signal in &fired.signals {
+                println!("      • {}", signal);
+            }
+        }
+        None => {
+            println!("{} Category: {} (no rule found any evidence)", style("❓").yellow(), result.category.as_str());
+        }
+    }
+    
+    if result.matches.len() > 1 {
+        println!();
+        println!("{} Other candidates:", style("📋").dim());
+        for candidate in &result.matches[1..] {
+            println!("   #{} {} (score {:.2}, priority {})",
+                candidate.rule_index + 1,
+                candidate.category.as_str(),
+                candidate.score,
+                candidate.priority);
+            for signal in &candidate.signals {
+                println!("      • {}", signal);
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// Result of `test`
+#[derive(Serialize)]
+struct MetadataTestReport {
+    file: PathBuf,
+    metadata: metadata::SampleMetadata,
+    categorization: rules::Categorization,
+}
+
+impl output::PlainText for MetadataTestReport {}
+
+async fn test_metadata_parsing(metadata_file: &PathBuf, config: &config::Config) -> Result<()> {
+    if !output::is_table() {
+        let metadata = metadata::SampleMetadata::from_file(metadata_file)
+            .map_err(|e| anyhow::anyhow!("Failed to parse metadata from {:?}: {}", metadata_file, e))?;
+        let categorization = metadata.categorize(&config.category_rules()?);
+        return output::emit(&MetadataTestReport { file: metadata_file.clone(), metadata, categorization });
+    }
+    
     println!("{} Testing metadata parsing", style("🧪").blue());
     println!("File: {:?}", metadata_file);
     
@@ -580,8 +2254,11 @@ async fn test_metadata_parsing(metadata_file: &PathBuf) -> Result<()> {
             println!("{} Tags: {:?}", style("🏷️").dim(), metadata.sample_meta_data.tags);
             
             // Test category mapping
-            let category = metadata::map_tags_to_category(&metadata.sample_meta_data.tags);
-            println!("{} Mapped category: {}", style("📂").dim(), category.as_str());
+            let rules = config.category_rules()?;
+            let categorization = metadata.categorize(&rules);
+            println!("{} Mapped category: {} (confidence {:.0}%)", style("📂").dim(),
+                categorization.category.as_str(),
+                categorization.confidence * 100.0);
         }
         Err(e) => {
             error!("Failed to parse metadata: {}", e);
@@ -593,52 +2270,159 @@ async fn test_metadata_parsing(metadata_file: &PathBuf) -> Result<()> {
     Ok(())
 }
 
-async fn run_daemon(args: Args) -> Result<()> {
+/// Re-read the configuration and hand it to the watcher, keeping the current one on error
+fn reload_config(overrides: &config::Overrides, config_tx: &tokio::sync::watch::Sender<config::Config>) -> Result<()> {
+    match config::Config::load(overrides) {
+        Ok(config) => {
+            set_log_level(&config.logging.level);
+            // The watcher owns the settings; it reports once they are applied
+            let _ = config_tx.send(config);
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to reload configuration: {}", e);
+            say!("{} Keeping the current configuration: {}", style("❌").red(), e);
+            Err(e)
+        }
+    }
+}
+
+async fn run_daemon(overrides: &config::Overrides) -> Result<()> {
     // Initialize tracing for daemon mode with file logging
     init_tracing(true)?;
     
-    let watch_dir = args.watch_dir.unwrap_or_else(|| {
-        dirs::download_dir().unwrap_or_else(|| PathBuf::from("./downloads"))
-    });
-    
-    let library_dir = args.library_dir.unwrap_or_else(|| {
-        dirs::audio_dir()
-            .unwrap_or_else(|| dirs::home_dir().unwrap_or_else(|| PathBuf::from(".")).join("Music"))
-            .join("Samples")
-            .join("SpliceLib")
-    });
+    let config = config::Config::load(overrides)?;
+    set_log_level(&config.logging.level);
     
-    let database_path = args.database.unwrap_or_else(default_db_path);
+    say!("{} Splice Alt Daemon Starting", style("🎵").green());
+    say!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    say!("{} Config: {}", style("⚙️").blue(), config.source_description());
+    say!("{} Watching: {:?}", style("👀").blue(), config.paths.watch_dir);
+    say!("{} Library: {:?}", style("📚").blue(), config.paths.library_dir);
+    say!("{} Database: {:?}", style("🗄️").blue(), config.paths.database);
+    say!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
     
-    println!("{} Splice Alt Daemon Starting", style("🎵").green());
-    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-    println!("{} Watching: {:?}", style("👀").blue(), watch_dir);
-    println!("{} Library: {:?}", style("📚").blue(), library_dir);
-    println!("{} Database: {:?}", style("🗄️").blue(), database_path);
-    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    // Refuse to start next to a daemon that is already answering on the socket
+    let socket_path = control::socket_path();
+    let listener = control::bind(&socket_path)?;
     
     // Initialize database
-    db::init_database(&database_path)?;
+    db::init_database(&config.paths.database)?;
     
-    // Start the watcher
-    let watcher_handle = tokio::spawn(async move {
-        if let Err(e) = watcher::watch_directory(&watch_dir, &library_dir, &database_path).await {
+    // Start the watcher; reloaded settings and control requests reach it through channels
+    let stats = std::sync::Arc::new(watcher::WatcherStats::default());
+    let (config_tx, config_rx) = tokio::sync::watch::channel(config.clone());
+    let (command_tx, command_rx) = tokio::sync::mpsc::channel(8);
+    let (request_tx, mut request_rx) = tokio::sync::mpsc::channel(8);
+    
+    // Measures loudness in the background when enabled in the config or over the socket
+    let loudness_job = std::sync::Arc::new(loudness::LoudnessJob::default());
+    let loudness_handle = tokio::spawn(loudness_job.clone().run(config_tx.subscribe()));
+    
+    let control_handle = tokio::spawn(control::serve(listener, control::ControlContext {
+        started: std::time::SystemTime::now(),
+        stats: stats.clone(),
+        config: config_tx.subscribe(),
+        watcher: command_tx.clone(),
+        daemon: request_tx,
+        loudness: loudness_job,
+    }));
+    say!("{} Control socket: {:?}", style("🔌").blue(), socket_path);
+    
+    let watcher_stats = stats.clone();
+    let mut watcher_handle = tokio::spawn(async move {
+        if let Err(e) = watcher::watch_directory(&config, watcher_stats, config_rx, command_rx).await {
             error!("Watcher error: {}", e);
         }
     });
     
+    use tokio::signal::unix::{signal, SignalKind};
+    let mut interrupt = signal(SignalKind::interrupt())?;
+    let mut terminate = signal(SignalKind::terminate())?;
+    let mut hangup = signal(SignalKind::hangup())?;
+    
     // Wait for shutdown signal
-    println!("{} Daemon is running. Press Ctrl+C to stop.", style("✅").green());
+    say!("{} Daemon is running. Press Ctrl+C or send SIGTERM to stop, send SIGHUP to reload the config.", style("✅").green());
+    
+    let mut watcher_done = false;
+    loop {
+        tokio::select! {
+            _ = interrupt.recv() => {
+                say!("\n{} Received SIGINT, shutting down", style("🛑").red());
+                break;
+            }
+            _ = terminate.recv() => {
+                say!("{} Received SIGTERM, shutting down", style("🛑").red());
+                break;
+            }
+            _ = hangup.recv() => {
+                say!("{} Received SIGHUP, reloading configuration...", style("🔄").blue());
+                let _ = reload_config(overrides, &config_tx);
+            }
+            Some(request) = request_rx.recv() => {
+                match request {
+                    control::DaemonRequest::Reload(reply) => {
+                        say!("{} Reload requested via control socket", style("🔄").blue());
+                        let _ = reply.send(reload_config(overrides, &config_tx).map_err(|e| e.to_string()));
+                    }
+                    control::DaemonRequest::Shutdown => {
+                        say!("{} Shutdown requested via control socket", style("🛑").red());
+                        break;
+                    }
+                }
+            }
+            _ = &mut watcher_handle => {
+                say!("{} Watcher task completed", style("👀").yellow());
+                watcher_done = true;
+                break;
+            }
+        }
+    }
+    
+    say!("{} Daemon stopping...", style("👋").yellow());
+    
+    // No more control requests; clients see the socket disappear. A loudness measurement
+    // in flight is lost, but every finished one is already stored.
+    control_handle.abort();
+    loudness_handle.abort();
+    if let Err(e) = fs::remove_file(&socket_path) {
+        warn!("Failed to remove control socket {:?}: {}", socket_path, e);
+    }
     
-    tokio::select! {
-        _ = tokio::signal::ctrl_c() => {
-            println!("\n{} Received shutdown signal", style("🛑").red());
+    // Stop taking new events and let the pair in progress finish, or roll back on failure
+    if !watcher_done {
+        stats.stopping.store(true, std::sync::atomic::Ordering::Relaxed);
+        let _ = command_tx.send(watcher::WatcherCommand::Shutdown).await;
+        
+        let shutdown_timeout = std::time::Duration::from_secs(config_tx.borrow().watcher.shutdown_timeout_secs);
+        say!("{} Waiting up to {}s for in-flight imports to finish...", style("⏳").yellow(), shutdown_timeout.as_secs());
+        tokio::select! {
+            result = tokio::time::timeout(shutdown_timeout, &mut watcher_handle) => {
+                if result.is_err() {
+                    warn!("Watcher did not stop within {:?}, abandoning in-flight imports", shutdown_timeout);
+                    say!("{} Watcher did not stop in time; unfinished files stay in the watch directory", style("⚠️").yellow());
+                    watcher_handle.abort();
+                }
+            }
+            _ = interrupt.recv() => {
+                say!("{} Received second signal, stopping immediately", style("⚠️").yellow());
+                watcher_handle.abort();
+            }
+            _ = terminate.recv() => {
+                say!("{} Received second signal, stopping immediately", style("⚠️").yellow());
+                watcher_handle.abort();
+            }
         }
-        _ = watcher_handle => {
-            println!("{} Watcher task completed", style("👀").yellow());
+    }
+    
+    // A PID file written by `start` for this process would otherwise look stale
+    if read_pid_file() == Some(std::process::id()) {
+        if let Err(e) = remove_pid_file() {
+            warn!("Failed to remove PID file: {}", e);
         }
     }
     
-    println!("{} Daemon stopping...", style("👋").yellow());
+    say!("{} Daemon stopped", style("✅").green());
+    flush_logs();
     Ok(())
 }
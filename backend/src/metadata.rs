@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 use strum::{EnumString, Display};
 
+use crate::layout::PathTemplate;
+use crate::rules::{Categorization, CategoryRules};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SampleMetadata {
     pub sample: Sample,
@@ -29,6 +32,35 @@ pub struct Encoding {
     pub decoded_size: u64,
 }
 
+/// Audio containers the importer accepts, stored in `samples.format`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumString, Display)]
+#[serde(rename_all = "lowercase")]
+#[strum(ascii_case_insensitive)]
+pub enum AudioFormat {
+    #[strum(serialize = "wave", to_string = "wav")]
+    Wav,
+    #[strum(serialize = "aif", serialize = "aifc", to_string = "aiff")]
+    Aiff,
+    #[strum(to_string = "flac")]
+    Flac,
+    #[strum(to_string = "mp3")]
+    Mp3,
+    #[strum(serialize = "oga", to_string = "ogg")]
+    Ogg,
+}
+
+impl AudioFormat {
+    /// Every spelling accepted as a file extension, for error messages
+    pub const EXTENSIONS: &'static [&'static str] = &["wav", "wave", "aif", "aiff", "aifc", "flac", "mp3", "ogg", "oga"];
+    
+    /// The container a file holds, judged by its extension
+    pub fn from_path(path: &std::path::Path) -> Option<Self> {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(|ext| ext.parse().ok())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SampleMetaData {
     pub audio_key: Option<String>,
@@ -123,49 +155,6 @@ impl BitwigCategory {
 
 
 
-/// Maps Splice tags to Bitwig categories
-pub fn map_tags_to_category(tags: &[String]) -> BitwigCategory {
-    let tags_lower: Vec<String> = tags.iter().map(|t| t.to_lowercase()).collect();
-    
-    // Define mapping rules based on common Splice tags
-    for tag in &tags_lower {
-        match tag.as_str() {
-            // Drum elements
-            "kick" | "kicks" => return BitwigCategory::Kick,
-            "snare" | "snares" => return BitwigCategory::Snare,
-            "hihat" | "hi-hat" | "hihats" | "hi-hats" => return BitwigCategory::HiHat,
-            "cymbal" | "cymbals" => return BitwigCategory::Cymbal,
-            "tom" | "toms" => return BitwigCategory::Tom,
-            "percussion" | "perc" => return BitwigCategory::Percussion,
-            "drum loop" | "drum loops" | "drums" => return BitwigCategory::DrumLoop,
-            
-            // Melodic elements
-            "bass" | "bassline" | "sub bass" => return BitwigCategory::Bass,
-            "lead" | "leads" | "lead synth" => return BitwigCategory::Lead,
-            "pad" | "pads" | "ambient" => return BitwigCategory::Pad,
-            "synth" | "synthesizer" => return BitwigCategory::Synth,
-            
-            // Instruments
-            "piano" => return BitwigCategory::Piano,
-            "guitar" => return BitwigCategory::Guitar,
-            "organ" => return BitwigCategory::Organ,
-            "bell" | "bells" => return BitwigCategory::Bell,
-            "brass" => return BitwigCategory::Brass,
-            "strings" | "string" => return BitwigCategory::Strings,
-            "vocal" | "vocals" | "voice" => return BitwigCategory::Vocal,
-            
-            // Effects
-            "fx" | "sfx" | "sound fx" | "effects" => return BitwigCategory::SoundFX,
-            "drone" | "texture" => return BitwigCategory::Drone,
-            
-            _ => continue,
-        }
-    }
-    
-    // Default to Unknown if no mapping found
-    BitwigCategory::Unknown
-}
-
 impl SampleMetadata {
     /// Load metadata from a JSON file
     pub fn from_file(path: &std::path::Path) -> anyhow::Result<Self> {
@@ -174,24 +163,20 @@ impl SampleMetadata {
         Ok(metadata)
     }
     
+    /// Score this sample against the category rules
+    pub fn categorize(&self, rules: &CategoryRules) -> Categorization {
+        rules.categorize(&self.sample_meta_data)
+    }
+    
     /// Get the mapped Bitwig category for this sample
-    pub fn get_category(&self) -> BitwigCategory {
-        map_tags_to_category(&self.sample_meta_data.tags)
+    pub fn get_category(&self, rules: &CategoryRules) -> BitwigCategory {
+        self.categorize(rules).category
     }
     
     /// Generate the target library path for this sample
-    pub fn get_library_path(&self, library_base: &std::path::Path) -> std::path::PathBuf {
-        let category = self.get_category();
-        let pack_name = &self.sample_meta_data.pack.name;
-        let filename = &self.sample_meta_data.filename;
-        
-        // Sanitize pack name for filesystem
-        let safe_pack_name = sanitize_filename(pack_name);
-        
-        library_base
-            .join(category.as_str())
-            .join(safe_pack_name)
-            .join(filename)
+    pub fn get_library_path(&self, library_base: &std::path::Path, rules: &CategoryRules, layout: &PathTemplate) -> std::path::PathBuf {
+        let category = self.get_category(rules);
+        library_base.join(layout.render(&self.sample_meta_data, &category))
     }
 }
 
@@ -212,4 +197,23 @@ pub fn sanitize_filename(name: &str) -> String {
         .collect::<String>()
         .trim()
         .to_string()
-} 
\ No newline at end of file
+} 
+
+/// Complete Splice metadata for a one-shot kick, for tests to adjust
+#[cfg(test)]
+pub fn test_metadata() -> SampleMetadata {
+    serde_json::from_str(r#"{
+        "sample": {"url": "u", "path": "p", "sas_id": "s", "file_hash": "", "file_size": 4, "type": 1,
+            "encoding": {"name": "wav", "decoded_format": "wav", "decoded_hash": "", "decoded_size": 0}},
+        "sample_meta_data": {"audio_key": "a", "bpm": 120, "chord_type": "minor", "dir": "", "duration": 1000,
+            "file_hash": "", "filename": "kick_one.wav",
+            "pack": {"uuid": "pu", "name": "Pack", "description": "", "provider_name": "Prov", "provider_description": "",
+                "cover_url": "", "banner_url": "", "main_genre": "house", "sample_count": 1, "preset_count": 0,
+                "permalink": "", "is_archived": false},
+            "preview_url": "", "price": 1, "provider_name": "Prov", "provider_uuid": "", "provider_permalink": "",
+            "sample_type": "oneshot", "tags": ["kick", "drums"], "waveform_url": "", "published": true, "popularity": 0,
+            "trending": 0, "published_at": "", "purchased_at": "2024", "sas_id": "", "liked": false, "licensed": true,
+            "asset_uuid": "au"},
+        "remaining_credits": null, "purchase_etag": null
+    }"#).expect("test metadata parses")
+}
@@ -0,0 +1,727 @@
+use anyhow::Result;
+use rusqlite::{Connection, Transaction};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A single schema change, identified by the `PRAGMA user_version` it brings the database to
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    apply: fn(&Transaction) -> Result<()>,
+    /// Checked inside the same transaction; a failure rolls the step back
+    verify: fn(&Transaction) -> Result<()>,
+}
+
+/// Every schema change in order. Never edit or reorder an entry that has shipped;
+/// append a new one instead.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "Create samples table and indexes",
+        apply: m001_initial_schema,
+        verify: v001_initial_schema,
+    },
+    Migration {
+        version: 2,
+        description: "Add pending_pairs queue for unmatched WAV/JSON files",
+        apply: m002_pending_pairs,
+        verify: v002_pending_pairs,
+    },
+    Migration {
+        version: 3,
+        description: "Record category confidence and runner-up",
+        apply: m003_category_confidence,
+        verify: v003_category_confidence,
+    },
+    Migration {
+        version: 4,
+        description: "Keep the original Splice metadata JSON",
+        apply: m004_metadata_json,
+        verify: v004_metadata_json,
+    },
+    Migration {
+        version: 5,
+        description: "Full-text index over filename, pack, provider and tags",
+        apply: m005_samples_fts,
+        verify: v005_samples_fts,
+    },
+    Migration {
+        version: 6,
+        description: "Normalize packs, providers and tags into their own tables",
+        apply: m006_normalize_packs_and_tags,
+        verify: v006_normalize_packs_and_tags,
+    },
+    Migration {
+        version: 7,
+        description: "Add import_journal for recovering interrupted imports",
+        apply: m007_import_journal,
+        verify: v007_import_journal,
+    },
+    Migration {
+        version: 8,
+        description: "Record the audio container format of each sample",
+        apply: m008_audio_format,
+        verify: v008_audio_format,
+    },
+    Migration {
+        version: 9,
+        description: "Record sample rate, bit depth, channels and exact length from the WAV header",
+        apply: m009_wav_properties,
+        verify: v009_wav_properties,
+    },
+    Migration {
+        version: 10,
+        description: "Store BPM and key estimated from the audio",
+        apply: m010_detected_bpm_key,
+        verify: v010_detected_bpm_key,
+    },
+    Migration {
+        version: 11,
+        description: "Store loudness, peak, RMS and DC offset measurements",
+        apply: m011_loudness,
+        verify: v011_loudness,
+    },
+];
+
+/// Schema version this build expects
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+pub fn current_version(conn: &Connection) -> Result<u32> {
+    Ok(conn.query_row("PRAGMA user_version", [], |row| row.get(0))?)
+}
+
+/// Migrations that have not been applied to this database yet
+fn pending_migrations(conn: &Connection, migrations: &'static [Migration]) -> Result<Vec<&'static Migration>> {
+    let current = current_version(conn)?;
+    Ok(migrations.iter().filter(|m| m.version > current).collect())
+}
+
+fn table_exists(conn: &Connection, table: &str) -> Result<bool> {
+    let mut stmt = conn.prepare("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1")?;
+    Ok(stmt.exists([table])?)
+}
+
+fn column_exists(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .filter_map(|name| name.ok())
+        .any(|name| name == column);
+    Ok(exists)
+}
+
+/// Add a column unless it is already there (databases created before versioning
+/// may have picked some columns up from the unversioned schema)
+fn add_column(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    if !column_exists(conn, table, column)? {
+        conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition), [])?;
+    }
+    Ok(())
+}
+
+fn require_columns(conn: &Connection, table: &str, columns: &[&str]) -> Result<()> {
+    if !table_exists(conn, table)? {
+        anyhow::bail!("table '{}' is missing", table);
+    }
+    for column in columns {
+        if !column_exists(conn, table, column)? {
+            anyhow::bail!("column '{}.{}' is missing", table, column);
+        }
+    }
+    Ok(())
+}
+
+/// Snapshot the database next to itself before changing its schema
+pub fn backup_database(conn: &Connection, db_path: &Path, from_version: u32) -> Result<PathBuf> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let file_name = db_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "samples.db".to_string());
+    let backup_path = db_path.with_file_name(format!("{}.v{}-{}.bak", file_name, from_version, timestamp));
+    
+    // VACUUM INTO produces a consistent copy even while other connections are open
+    conn.execute("VACUUM INTO ?1", [backup_path.to_string_lossy()])
+        .map_err(|e| anyhow::anyhow!("Failed to back up database to {:?}: {}", backup_path, e))?;
+    
+    Ok(backup_path)
+}
+
+/// Bring the database up to the latest schema, backing it up first if it already holds data
+pub fn migrate(conn: &mut Connection, db_path: &Path) -> Result<Vec<&'static Migration>> {
+    apply_migrations(conn, db_path, MIGRATIONS)
+}
+
+fn apply_migrations(conn: &mut Connection, db_path: &Path, migrations: &'static [Migration]) -> Result<Vec<&'static Migration>> {
+    let current = current_version(conn)?;
+    let latest = migrations.last().map_or(0, |m| m.version);
+    
+    if current > latest {
+        anyhow::bail!(
+            "Database {:?} has schema version {}, but this build only supports up to {}. Please upgrade splice-alt-daemon.",
+            db_path, current, latest
+        );
+    }
+    
+    let pending = pending_migrations(conn, migrations)?;
+    if pending.is_empty() {
+        return Ok(pending);
+    }
+    
+    // Fresh databases have nothing worth backing up
+    if table_exists(conn, "samples")? {
+        let backup_path = backup_database(conn, db_path, current)?;
+        say!("💾 Backed up database before migrating: {:?}", backup_path);
+    }
+    
+    for migration in &pending {
+        let tx = conn.transaction()?;
+        (migration.apply)(&tx)
+            .and_then(|_| (migration.verify)(&tx))
+            .map_err(|e| anyhow::anyhow!("Migration {} ({}) failed: {}", migration.version, migration.description, e))?;
+        // user_version is transactional, so a failed step leaves the old version in place
+        tx.execute_batch(&format!("PRAGMA user_version = {}", migration.version))?;
+        tx.commit()?;
+        say!("🔧 Applied database migration {}: {}", migration.version, migration.description);
+    }
+    
+    Ok(pending)
+}
+
+fn m001_initial_schema(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS samples (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            file_path TEXT NOT NULL UNIQUE,
+            pack_name TEXT NOT NULL,
+            pack_uuid TEXT NOT NULL,
+            filename TEXT NOT NULL,
+            file_hash TEXT NOT NULL UNIQUE,
+            bpm INTEGER,
+            audio_key TEXT,
+            chord_type TEXT,
+            tags TEXT, -- JSON array of tags
+            mapped_category TEXT NOT NULL,
+            sample_type TEXT NOT NULL,
+            duration INTEGER NOT NULL,
+            file_size INTEGER NOT NULL,
+            provider_name TEXT NOT NULL,
+            date_downloaded TEXT NOT NULL,
+            date_processed DATETIME DEFAULT CURRENT_TIMESTAMP,
+            splice_url TEXT,
+            preview_url TEXT,
+            asset_uuid TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_file_hash ON samples(file_hash);
+        CREATE INDEX IF NOT EXISTS idx_pack_name ON samples(pack_name);
+        CREATE INDEX IF NOT EXISTS idx_category ON samples(mapped_category);
+        CREATE INDEX IF NOT EXISTS idx_tags ON samples(tags);",
+    )?;
+    Ok(())
+}
+
+fn v001_initial_schema(tx: &Transaction) -> Result<()> {
+    require_columns(tx, "samples", &[
+        "id", "file_path", "pack_name", "pack_uuid", "filename", "file_hash", "bpm",
+        "audio_key", "chord_type", "tags", "mapped_category", "sample_type", "duration",
+        "file_size", "provider_name", "date_downloaded", "date_processed", "splice_url",
+        "preview_url", "asset_uuid",
+    ])
+}
+
+fn m002_pending_pairs(tx: &Transaction) -> Result<()> {
+    // Files seen in the watch directory whose partner (WAV or JSON) has not arrived yet
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS pending_pairs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            file_path TEXT NOT NULL UNIQUE,
+            file_kind TEXT NOT NULL, -- 'wav' or 'json'
+            first_seen DATETIME DEFAULT CURRENT_TIMESTAMP,
+            last_seen DATETIME DEFAULT CURRENT_TIMESTAMP,
+            last_error TEXT
+        );",
+    )?;
+    Ok(())
+}
+
+fn v002_pending_pairs(tx: &Transaction) -> Result<()> {
+    require_columns(tx, "pending_pairs", &["id", "file_path", "file_kind", "first_seen", "last_seen", "last_error"])
+}
+
+fn m003_category_confidence(tx: &Transaction) -> Result<()> {
+    add_column(tx, "samples", "category_confidence", "REAL")?;
+    add_column(tx, "samples", "runner_up_category", "TEXT")?;
+    tx.execute_batch("CREATE INDEX IF NOT EXISTS idx_category_confidence ON samples(category_confidence);")?;
+    Ok(())
+}
+
+fn v003_category_confidence(tx: &Transaction) -> Result<()> {
+    require_columns(tx, "samples", &["category_confidence", "runner_up_category"])
+}
+
+fn m004_metadata_json(tx: &Transaction) -> Result<()> {
+    // Original Splice metadata, used to recompute categories and paths
+    add_column(tx, "samples", "metadata_json", "TEXT")
+}
+
+fn v004_metadata_json(tx: &Transaction) -> Result<()> {
+    require_columns(tx, "samples", &["metadata_json"])
+}
+
+fn m005_samples_fts(tx: &Transaction) -> Result<()> {
+    // External content table: the text lives in `samples`, triggers keep the index in step
+    tx.execute_batch(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS samples_fts USING fts5(
+            filename, pack_name, provider_name, tags,
+            content = 'samples', content_rowid = 'id'
+        );
+        CREATE TRIGGER IF NOT EXISTS samples_fts_insert AFTER INSERT ON samples BEGIN
+            INSERT INTO samples_fts(rowid, filename, pack_name, provider_name, tags)
+            VALUES (new.id, new.filename, new.pack_name, new.provider_name, new.tags);
+        END;
+        CREATE TRIGGER IF NOT EXISTS samples_fts_delete AFTER DELETE ON samples BEGIN
+            INSERT INTO samples_fts(samples_fts, rowid, filename, pack_name, provider_name, tags)
+            VALUES ('delete', old.id, old.filename, old.pack_name, old.provider_name, old.tags);
+        END;
+        CREATE TRIGGER IF NOT EXISTS samples_fts_update AFTER UPDATE OF filename, pack_name, provider_name, tags ON samples BEGIN
+            INSERT INTO samples_fts(samples_fts, rowid, filename, pack_name, provider_name, tags)
+            VALUES ('delete', old.id, old.filename, old.pack_name, old.provider_name, old.tags);
+            INSERT INTO samples_fts(rowid, filename, pack_name, provider_name, tags)
+            VALUES (new.id, new.filename, new.pack_name, new.provider_name, new.tags);
+        END;
+        -- Index the samples imported before the table existed
+        INSERT INTO samples_fts(samples_fts) VALUES ('rebuild');",
+    )?;
+    Ok(())
+}
+
+fn v005_samples_fts(tx: &Transaction) -> Result<()> {
+    require_columns(tx, "samples_fts", &["filename", "pack_name", "provider_name", "tags"])?;
+    tx.execute("INSERT INTO samples_fts(samples_fts) VALUES ('integrity-check')", [])?;
+    Ok(())
+}
+
+fn m006_normalize_packs_and_tags(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS providers (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            uuid TEXT,
+            permalink TEXT,
+            description TEXT
+        );
+        CREATE TABLE IF NOT EXISTS packs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            uuid TEXT NOT NULL UNIQUE,
+            name TEXT NOT NULL,
+            provider_id INTEGER REFERENCES providers(id),
+            description TEXT,
+            main_genre TEXT,
+            cover_url TEXT,
+            banner_url TEXT,
+            sample_count INTEGER,
+            preset_count INTEGER,
+            permalink TEXT,
+            is_archived INTEGER NOT NULL DEFAULT 0
+        );
+        CREATE TABLE IF NOT EXISTS tags (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE -- trimmed and lowercased
+        );
+        CREATE TABLE IF NOT EXISTS sample_tags (
+            sample_id INTEGER NOT NULL REFERENCES samples(id),
+            tag_id INTEGER NOT NULL REFERENCES tags(id),
+            position INTEGER NOT NULL, -- order the tag had in Splice's list
+            PRIMARY KEY (sample_id, tag_id)
+        ) WITHOUT ROWID;
+        CREATE INDEX IF NOT EXISTS idx_sample_tags_tag ON sample_tags(tag_id, sample_id);
+        CREATE INDEX IF NOT EXISTS idx_packs_provider ON packs(provider_id);
+        CREATE INDEX IF NOT EXISTS idx_samples_pack_uuid ON samples(pack_uuid);
+        
+        -- Foreign keys aren't enforced, so clean up tag links explicitly
+        CREATE TRIGGER IF NOT EXISTS sample_tags_delete AFTER DELETE ON samples BEGIN
+            DELETE FROM sample_tags WHERE sample_id = old.id;
+        END;
+        
+        -- A B-tree over a JSON string can't answer tag queries
+        DROP INDEX IF EXISTS idx_tags;",
+    )?;
+    
+    // Backfill from existing samples, preferring rows that kept the full Splice metadata
+    tx.execute_batch(
+        "CREATE TEMP TABLE sample_meta AS
+            SELECT id, pack_uuid, pack_name, provider_name, tags,
+                CASE WHEN json_valid(metadata_json) THEN json_extract(metadata_json, '$.sample_meta_data') END AS meta
+            FROM samples;
+        
+        INSERT INTO providers (name, uuid, permalink, description)
+            SELECT provider_name,
+                NULLIF(json_extract(meta, '$.provider_uuid'), ''),
+                NULLIF(json_extract(meta, '$.provider_permalink'), ''),
+                NULLIF(json_extract(meta, '$.pack.provider_description'), '')
+            FROM sample_meta
+            WHERE provider_name <> ''
+            ORDER BY meta IS NULL
+            ON CONFLICT(name) DO NOTHING;
+        
+        INSERT INTO packs (uuid, name, provider_id, description, main_genre, cover_url, banner_url,
+                           sample_count, preset_count, permalink, is_archived)
+            SELECT pack_uuid, pack_name,
+                (SELECT id FROM providers WHERE providers.name = sample_meta.provider_name),
+                NULLIF(json_extract(meta, '$.pack.description'), ''),
+                NULLIF(json_extract(meta, '$.pack.main_genre'), ''),
+                NULLIF(json_extract(meta, '$.pack.cover_url'), ''),
+                NULLIF(json_extract(meta, '$.pack.banner_url'), ''),
+                json_extract(meta, '$.pack.sample_count'),
+                json_extract(meta, '$.pack.preset_count'),
+                NULLIF(json_extract(meta, '$.pack.permalink'), ''),
+                COALESCE(json_extract(meta, '$.pack.is_archived'), 0)
+            FROM sample_meta
+            WHERE pack_uuid <> ''
+            ORDER BY meta IS NULL
+            ON CONFLICT(uuid) DO NOTHING;
+        
+        INSERT OR IGNORE INTO tags (name)
+            SELECT DISTINCT lower(trim(tag.value))
+            FROM sample_meta, json_each(CASE WHEN json_valid(sample_meta.tags) THEN sample_meta.tags ELSE '[]' END) AS tag
+            WHERE tag.type = 'text' AND trim(tag.value) <> '';
+        
+        INSERT OR IGNORE INTO sample_tags (sample_id, tag_id, position)
+            SELECT sample_meta.id, tags.id, tag.key
+            FROM sample_meta, json_each(CASE WHEN json_valid(sample_meta.tags) THEN sample_meta.tags ELSE '[]' END) AS tag
+            JOIN tags ON tags.name = lower(trim(tag.value))
+            WHERE tag.type = 'text';
+        
+        DROP TABLE sample_meta;",
+    )?;
+    Ok(())
+}
+
+fn v006_normalize_packs_and_tags(tx: &Transaction) -> Result<()> {
+    require_columns(tx, "providers", &["id", "name", "uuid", "permalink", "description"])?;
+    require_columns(tx, "packs", &[
+        "id", "uuid", "name", "provider_id", "description", "main_genre", "cover_url",
+        "banner_url", "sample_count", "preset_count", "permalink", "is_archived",
+    ])?;
+    require_columns(tx, "tags", &["id", "name"])?;
+    require_columns(tx, "sample_tags", &["sample_id", "tag_id", "position"])?;
+    
+    let untagged: i64 = tx.query_row(
+        "SELECT COUNT(*) FROM samples
+         WHERE json_valid(tags)
+           AND EXISTS (SELECT 1 FROM json_each(samples.tags) AS tag WHERE tag.type = 'text' AND trim(tag.value) <> '')
+           AND NOT EXISTS (SELECT 1 FROM sample_tags WHERE sample_id = samples.id)",
+        [],
+        |row| row.get(0),
+    )?;
+    if untagged > 0 {
+        anyhow::bail!("{} samples have tags that were not copied into sample_tags", untagged);
+    }
+    
+    let packless: i64 = tx.query_row(
+        "SELECT COUNT(*) FROM samples
+         WHERE pack_uuid <> '' AND NOT EXISTS (SELECT 1 FROM packs WHERE packs.uuid = samples.pack_uuid)",
+        [],
+        |row| row.get(0),
+    )?;
+    if packless > 0 {
+        anyhow::bail!("{} samples reference packs that were not copied into packs", packless);
+    }
+    
+    Ok(())
+}
+
+fn m007_import_journal(tx: &Transaction) -> Result<()> {
+    // One row per import in progress, advanced before each step and deleted once the
+    // import is complete; rows left behind by a crash are resolved on startup
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS import_journal (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            operation TEXT NOT NULL, -- 'import' or 'restore'
+            state TEXT NOT NULL, -- 'moving', 'moved' or 'recorded'
+            wav_path TEXT NOT NULL,
+            json_path TEXT NOT NULL,
+            target_path TEXT NOT NULL,
+            file_hash TEXT NOT NULL,
+            started_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );",
+    )?;
+    Ok(())
+}
+
+fn v007_import_journal(tx: &Transaction) -> Result<()> {
+    require_columns(tx, "import_journal", &[
+        "id", "operation", "state", "wav_path", "json_path", "target_path", "file_hash",
+        "started_at", "updated_at",
+    ])
+}
+
+fn m008_audio_format(tx: &Transaction) -> Result<()> {
+    add_column(tx, "samples", "format", "TEXT")?;
+    // Only WAVs were imported before formats were tracked, and pending files were
+    // always either a WAV or a JSON
+    tx.execute_batch(
+        "UPDATE samples SET format = 'wav' WHERE format IS NULL;
+        UPDATE pending_pairs SET file_kind = 'audio' WHERE file_kind = 'wav';
+        CREATE INDEX IF NOT EXISTS idx_format ON samples(format);",
+    )?;
+    Ok(())
+}
+
+fn v008_audio_format(tx: &Transaction) -> Result<()> {
+    require_columns(tx, "samples", &["format"])
+}
+
+fn m009_wav_properties(tx: &Transaction) -> Result<()> {
+    add_column(tx, "samples", "sample_rate", "INTEGER")?;
+    add_column(tx, "samples", "bit_depth", "INTEGER")?;
+    add_column(tx, "samples", "channels", "INTEGER")?;
+    add_column(tx, "samples", "frame_count", "INTEGER")?;
+    add_column(tx, "samples", "duration_secs", "REAL")?;
+    tx.execute_batch("CREATE INDEX IF NOT EXISTS idx_audio_properties ON samples(sample_rate, bit_depth);")?;
+    Ok(())
+}
+
+fn v009_wav_properties(tx: &Transaction) -> Result<()> {
+    require_columns(tx, "samples", &["sample_rate", "bit_depth", "channels", "frame_count", "duration_secs"])
+}
+
+fn m010_detected_bpm_key(tx: &Transaction) -> Result<()> {
+    add_column(tx, "samples", "detected_bpm", "REAL")?;
+    add_column(tx, "samples", "detected_bpm_confidence", "REAL")?;
+    add_column(tx, "samples", "detected_key", "TEXT")?;
+    add_column(tx, "samples", "detected_chord_type", "TEXT")?;
+    add_column(tx, "samples", "detected_key_confidence", "REAL")?;
+    add_column(tx, "samples", "analyzed_at", "DATETIME")?;
+    Ok(())
+}
+
+fn v010_detected_bpm_key(tx: &Transaction) -> Result<()> {
+    require_columns(tx, "samples", &[
+        "detected_bpm", "detected_bpm_confidence", "detected_key",
+        "detected_chord_type", "detected_key_confidence", "analyzed_at",
+    ])
+}
+
+fn m011_loudness(tx: &Transaction) -> Result<()> {
+    add_column(tx, "samples", "loudness_lufs", "REAL")?;
+    add_column(tx, "samples", "sample_peak_dbfs", "REAL")?;
+    add_column(tx, "samples", "true_peak_dbtp", "REAL")?;
+    add_column(tx, "samples", "rms_dbfs", "REAL")?;
+    add_column(tx, "samples", "dc_offset", "REAL")?;
+    add_column(tx, "samples", "loudness_analyzed_at", "DATETIME")?;
+    tx.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_loudness ON samples(loudness_lufs);
+         CREATE INDEX IF NOT EXISTS idx_loudness_pending ON samples(loudness_analyzed_at);",
+    )?;
+    Ok(())
+}
+
+fn v011_loudness(tx: &Transaction) -> Result<()> {
+    require_columns(tx, "samples", &[
+        "loudness_lufs", "sample_peak_dbfs", "true_peak_dbtp",
+        "rms_dbfs", "dc_offset", "loudness_analyzed_at",
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::params;
+    
+    /// The `samples` table as unversioned builds created it, before `user_version` was used
+    const BASELINE_SCHEMA: &str = "
+        CREATE TABLE samples (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            file_path TEXT NOT NULL UNIQUE,
+            pack_name TEXT NOT NULL,
+            pack_uuid TEXT NOT NULL,
+            filename TEXT NOT NULL,
+            file_hash TEXT NOT NULL UNIQUE,
+            bpm INTEGER,
+            audio_key TEXT,
+            chord_type TEXT,
+            tags TEXT,
+            mapped_category TEXT NOT NULL,
+            sample_type TEXT NOT NULL,
+            duration INTEGER NOT NULL,
+            file_size INTEGER NOT NULL,
+            provider_name TEXT NOT NULL,
+            date_downloaded TEXT NOT NULL,
+            date_processed DATETIME DEFAULT CURRENT_TIMESTAMP,
+            splice_url TEXT,
+            preview_url TEXT,
+            asset_uuid TEXT NOT NULL
+        );
+        CREATE INDEX idx_file_hash ON samples(file_hash);
+        CREATE INDEX idx_pack_name ON samples(pack_name);
+        CREATE INDEX idx_category ON samples(mapped_category);
+        CREATE INDEX idx_tags ON samples(tags);";
+    
+    fn baseline_database() -> (tempfile::TempDir, PathBuf, Connection) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("samples.db");
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(BASELINE_SCHEMA).unwrap();
+        (dir, path, conn)
+    }
+    
+    fn insert_baseline_sample(conn: &Connection, id: i64, pack: (&str, &str), provider: &str, tags: &str) {
+        conn.execute(
+            "INSERT INTO samples (id, file_path, pack_name, pack_uuid, filename, file_hash, tags,
+                mapped_category, sample_type, duration, file_size, provider_name, date_downloaded, asset_uuid)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 'Kick', 'oneshot', 1, 100, ?8, '2024-01-01', ?9)",
+            params![
+                id,
+                format!("lib/sample{}.wav", id),
+                pack.0,
+                pack.1,
+                format!("sample{}.wav", id),
+                format!("hash{}", id),
+                tags,
+                provider,
+                format!("asset{}", id),
+            ],
+        ).unwrap();
+    }
+    
+    fn count(conn: &Connection, sql: &str) -> i64 {
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+    
+    #[test]
+    fn migrates_baseline_schema_to_latest() {
+        let (_dir, path, mut conn) = baseline_database();
+        insert_baseline_sample(&conn, 1, ("Pack", "pack-1"), "Provider", r#"["kick"]"#);
+        assert_eq!(current_version(&conn).unwrap(), 0);
+        
+        let applied = migrate(&mut conn, &path).unwrap();
+        
+        assert_eq!(applied.len(), MIGRATIONS.len());
+        assert_eq!(current_version(&conn).unwrap(), latest_version());
+        assert!(pending_migrations(&conn, MIGRATIONS).unwrap().is_empty());
+        // Every column the record mapping reads is there
+        conn.prepare(&format!("SELECT {} FROM samples", crate::db::SAMPLE_COLUMNS)).unwrap();
+        for table in ["pending_pairs", "samples_fts", "providers", "packs", "tags", "sample_tags", "import_journal"] {
+            assert!(table_exists(&conn, table).unwrap(), "table {} is missing", table);
+        }
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM sqlite_master WHERE type = 'index' AND name = 'idx_tags'"), 0);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM samples"), 1);
+        
+        // Running again changes nothing
+        assert!(migrate(&mut conn, &path).unwrap().is_empty());
+    }
+    
+    #[test]
+    fn normalize_backfills_packs_providers_and_tags() {
+        let (_dir, path, mut conn) = baseline_database();
+        insert_baseline_sample(&conn, 1, ("Pack One", "pack-1"), "Provider A", r#"["Kick", " acoustic "]"#);
+        insert_baseline_sample(&conn, 2, ("Pack One", "pack-1"), "Provider A", r#"["kick"]"#);
+        insert_baseline_sample(&conn, 3, ("Pack Two", "pack-2"), "Provider B", "not json");
+        
+        // Stop just before the normalization so a row can carry the full Splice metadata
+        apply_migrations(&mut conn, &path, &MIGRATIONS[..5]).unwrap();
+        conn.execute(
+            "UPDATE samples SET metadata_json = ?1 WHERE id = 3",
+            [r#"{"sample_meta_data": {"provider_uuid": "prov-b", "pack": {"description": "Second pack", "main_genre": "house"}}}"#],
+        ).unwrap();
+        apply_migrations(&mut conn, &path, MIGRATIONS).unwrap();
+        
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM providers"), 2);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM packs"), 2);
+        let (provider_uuid, description, genre): (Option<String>, Option<String>, Option<String>) = conn.query_row(
+            "SELECT providers.uuid, packs.description, packs.main_genre
+             FROM packs JOIN providers ON providers.id = packs.provider_id WHERE packs.uuid = 'pack-2'",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        ).unwrap();
+        assert_eq!(provider_uuid.as_deref(), Some("prov-b"));
+        assert_eq!(description.as_deref(), Some("Second pack"));
+        assert_eq!(genre.as_deref(), Some("house"));
+        
+        // Tags are trimmed and lowercased, shared between samples and keep their order
+        let tags: Vec<String> = conn.prepare("SELECT name FROM tags ORDER BY name").unwrap()
+            .query_map([], |row| row.get(0)).unwrap()
+            .collect::<rusqlite::Result<_>>().unwrap();
+        assert_eq!(tags, ["acoustic", "kick"]);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM sample_tags"), 3);
+        assert_eq!(count(&conn, "SELECT position FROM sample_tags JOIN tags ON tags.id = tag_id WHERE sample_id = 1 AND name = 'acoustic'"), 1);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM sample_tags WHERE sample_id = 3"), 0);
+    }
+    
+    fn create_probe(tx: &Transaction) -> Result<()> {
+        tx.execute_batch("CREATE TABLE probe (id INTEGER)")?;
+        Ok(())
+    }
+    
+    fn create_second_probe(tx: &Transaction) -> Result<()> {
+        tx.execute_batch("CREATE TABLE second_probe (id INTEGER)")?;
+        Ok(())
+    }
+    
+    fn passes(_tx: &Transaction) -> Result<()> {
+        Ok(())
+    }
+    
+    fn fails(_tx: &Transaction) -> Result<()> {
+        anyhow::bail!("verification failed")
+    }
+    
+    const FAILING_MIGRATIONS: &[Migration] = &[
+        Migration { version: 1, description: "probe", apply: create_probe, verify: passes },
+        Migration { version: 2, description: "second probe", apply: create_second_probe, verify: fails },
+    ];
+    
+    #[test]
+    fn failed_verify_rolls_back_the_step() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("samples.db");
+        let mut conn = Connection::open(&path).unwrap();
+        
+        let error = apply_migrations(&mut conn, &path, FAILING_MIGRATIONS).err().expect("the second step should fail");
+        
+        assert!(error.to_string().contains("Migration 2 (second probe) failed"), "{}", error);
+        // The first step stays applied, the second leaves no trace
+        assert_eq!(current_version(&conn).unwrap(), 1);
+        assert!(table_exists(&conn, "probe").unwrap());
+        assert!(!table_exists(&conn, "second_probe").unwrap());
+    }
+    
+    #[test]
+    fn backs_up_existing_database_before_migrating() {
+        let (dir, path, mut conn) = baseline_database();
+        insert_baseline_sample(&conn, 1, ("Pack", "pack-1"), "Provider", "[]");
+        
+        migrate(&mut conn, &path).unwrap();
+        
+        let backups: Vec<PathBuf> = std::fs::read_dir(dir.path()).unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.to_string_lossy().ends_with(".bak"))
+            .collect();
+        assert_eq!(backups.len(), 1);
+        assert!(backups[0].file_name().unwrap().to_string_lossy().starts_with("samples.db.v0-"));
+        
+        // The copy is the database as it was before the migrations
+        let backup = Connection::open(&backups[0]).unwrap();
+        assert_eq!(current_version(&backup).unwrap(), 0);
+        assert_eq!(count(&backup, "SELECT COUNT(*) FROM samples"), 1);
+        assert!(!table_exists(&backup, "packs").unwrap());
+    }
+    
+    #[test]
+    fn fresh_database_is_not_backed_up() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("samples.db");
+        let mut conn = Connection::open(&path).unwrap();
+        
+        migrate(&mut conn, &path).unwrap();
+        
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
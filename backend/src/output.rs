@@ -0,0 +1,163 @@
+use anyhow::Result;
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// How command results are written to stdout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Decorated console output for people (default)
+    #[default]
+    Table,
+    /// Undecorated tab-separated lines, one record per line
+    Plain,
+    /// A single pretty-printed JSON document
+    Json,
+    /// One compact JSON object per line
+    Ndjson,
+}
+
+static FORMAT: OnceLock<OutputFormat> = OnceLock::new();
+
+/// Select the output format for the rest of the process; only the first call has an effect
+pub fn set_format(format: OutputFormat) {
+    let _ = FORMAT.set(format);
+}
+
+pub fn format() -> OutputFormat {
+    FORMAT.get().copied().unwrap_or_default()
+}
+
+/// Whether the decorated console output is selected
+pub fn is_table() -> bool {
+    format() == OutputFormat::Table
+}
+
+/// Print a progress or status message. With a machine-readable format selected
+/// these go to stderr so stdout only carries the result.
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if $crate::output::is_table() {
+            println!($($arg)*);
+        } else {
+            eprintln!($($arg)*);
+        }
+    };
+}
+
+/// Tab-separated rendering used by `--output plain`. By default every field
+/// becomes a `name<TAB>value` line; record types override this with a single row.
+pub trait PlainText: Serialize {
+    fn plain_lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        flatten_fields("", &serde_json::to_value(self).unwrap_or(Value::Null), &mut lines);
+        lines
+    }
+}
+
+fn scalar_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Nested objects use dotted names (`matched.imported`); list items repeat the name,
+/// with the fields of object items following on the same line
+fn flatten_fields(name: &str, value: &Value, lines: &mut Vec<String>) {
+    match value {
+        Value::Object(fields) => {
+            for (key, value) in fields {
+                let name = if name.is_empty() { key.clone() } else { format!("{}.{}", name, key) };
+                flatten_fields(&name, value, lines);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                let mut row = vec![name.to_string()];
+                match item {
+                    Value::Object(fields) => row.extend(fields.values().map(scalar_text)),
+                    other => row.push(scalar_text(other)),
+                }
+                lines.push(plain_row(row));
+            }
+        }
+        other => lines.push(plain_row([name.to_string(), scalar_text(other)])),
+    }
+}
+
+/// Join fields with tabs, replacing tabs and newlines inside values so each record stays on one line
+pub fn plain_row<I, T>(fields: I) -> String
+where
+    I: IntoIterator<Item = T>,
+    T: Display,
+{
+    fields
+        .into_iter()
+        .map(|field| field.to_string().replace(['\t', '\n', '\r'], " "))
+        .collect::<Vec<_>>()
+        .join("\t")
+}
+
+/// Results passed through as-is, such as answers from the daemon's control socket
+impl PlainText for Value {}
+
+/// Print a single result in the selected machine-readable format
+pub fn emit<T: Serialize + PlainText>(value: &T) -> Result<()> {
+    match format() {
+        OutputFormat::Json | OutputFormat::Table => println!("{}", serde_json::to_string_pretty(value)?),
+        OutputFormat::Ndjson => println!("{}", serde_json::to_string(value)?),
+        OutputFormat::Plain => {
+            for line in value.plain_lines() {
+                println!("{}", line);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Print a collection: a JSON array, or one line per item for ndjson and plain
+pub fn emit_list<T: Serialize + PlainText>(items: &[T]) -> Result<()> {
+    match format() {
+        OutputFormat::Json | OutputFormat::Table => println!("{}", serde_json::to_string_pretty(items)?),
+        OutputFormat::Ndjson => {
+            for item in items {
+                println!("{}", serde_json::to_string(item)?);
+            }
+        }
+        OutputFormat::Plain => {
+            for item in items {
+                for line in item.plain_lines() {
+                    println!("{}", line);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Serialize a value through its `Display` impl (e.g. `BitwigCategory` as "Drum Loop")
+pub fn display<T: Display, S: Serializer>(value: &T, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+pub fn display_option<T: Display, S: Serializer>(value: &Option<T>, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    match value {
+        Some(value) => serializer.collect_str(value),
+        None => serializer.serialize_none(),
+    }
+}
+
+#[derive(Serialize)]
+struct Failure<'a> {
+    path: &'a PathBuf,
+    error: &'a str,
+}
+
+/// Serialize `(path, error)` pairs as `{"path": ..., "error": ...}` objects
+pub fn failures<S: Serializer>(failed: &[(PathBuf, String)], serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    serializer.collect_seq(failed.iter().map(|(path, error)| Failure { path, error }))
+}
@@ -0,0 +1,186 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use strum::{Display, EnumString};
+
+use crate::metadata::AudioFormat;
+use crate::watcher::move_file_safely;
+
+/// Written next to the quarantined files, describing why they were set aside
+pub const REPORT_FILE: &str = "quarantine-report.json";
+
+/// Why a pair ended up in quarantine
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumString, Display)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum QuarantineReason {
+    /// The audio file didn't match the size or hash in its metadata
+    Verification,
+    /// The audio file's header couldn't be parsed
+    Malformed,
+    /// Importing still failed after every retry
+    ImportFailed,
+}
+
+/// Contents of `quarantine-report.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarantineReport {
+    pub reason: QuarantineReason,
+    /// The last error seen for the pair
+    pub error: String,
+    /// Where the files were when they were quarantined
+    #[serde(alias = "wav_file")]
+    pub audio_file: PathBuf,
+    pub json_file: PathBuf,
+    /// Unix timestamp of the quarantine
+    pub quarantined_at: u64,
+    /// Failed `quarantine retry` attempts
+    #[serde(default)]
+    pub retries: u32,
+}
+
+/// One quarantined pair: a directory holding the audio file, its JSON and the report
+#[derive(Debug, Clone, Serialize)]
+pub struct QuarantineEntry {
+    /// Directory name, used to address the entry from the command line
+    pub id: String,
+    pub dir: PathBuf,
+    pub audio_path: PathBuf,
+    pub json_path: PathBuf,
+    /// `None` when the report is missing or unreadable
+    pub report: Option<QuarantineReport>,
+}
+
+impl QuarantineEntry {
+    fn load(dir: &Path) -> Result<Self> {
+        let id = dir.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .ok_or_else(|| anyhow::anyhow!("Invalid quarantine entry {:?}", dir))?;
+        let report = fs::read_to_string(dir.join(REPORT_FILE)).ok()
+            .and_then(|text| serde_json::from_str::<QuarantineReport>(&text).ok());
+        
+        // Prefer the names recorded in the report; fall back to whatever pair is in the directory
+        let (audio_path, json_path) = match &report {
+            Some(report) => (
+                dir.join(report.audio_file.file_name().unwrap_or_default()),
+                dir.join(report.json_file.file_name().unwrap_or_default()),
+            ),
+            None => {
+                let audio_path = fs::read_dir(dir)?
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .find(|path| AudioFormat::from_path(path).is_some())
+                    .unwrap_or_else(|| dir.join(format!("{}.wav", id)));
+                let json_path = audio_path.with_extension("json");
+                (audio_path, json_path)
+            }
+        };
+        
+        Ok(Self { id, dir: dir.to_path_buf(), audio_path, json_path, report })
+    }
+    
+    /// Record another failed retry in the report
+    pub fn record_retry_failure(&mut self, error: &str) -> Result<()> {
+        let Some(report) = self.report.as_mut() else {
+            return Ok(());
+        };
+        report.error = error.to_string();
+        report.retries += 1;
+        write_report(&self.dir, report)
+    }
+}
+
+fn write_report(dir: &Path, report: &QuarantineReport) -> Result<()> {
+    let path = dir.join(REPORT_FILE);
+    fs::write(&path, serde_json::to_string_pretty(report)?)
+        .map_err(|e| anyhow::anyhow!("Failed to write quarantine report {:?}: {}", path, e))
+}
+
+/// A directory for the pair under `quarantine_dir`, named after the audio file and numbered when taken
+fn entry_dir(quarantine_dir: &Path, audio_path: &Path) -> PathBuf {
+    let stem = audio_path.file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "sample".to_string());
+    std::iter::once(stem.clone())
+        .chain((2..).map(|n| format!("{}-{}", stem, n)))
+        .map(|name| quarantine_dir.join(name))
+        .find(|candidate| !candidate.exists())
+        .expect("some numbered name is free")
+}
+
+/// Move an audio/JSON pair into its own quarantine directory along with a report of the
+/// error, returning that directory
+pub fn quarantine_pair(quarantine_dir: &Path, audio_path: &Path, json_path: &Path, reason: QuarantineReason, error: &str) -> Result<PathBuf> {
+    fs::create_dir_all(quarantine_dir)
+        .map_err(|e| anyhow::anyhow!("Failed to create quarantine directory {:?}: {}", quarantine_dir, e))?;
+    let dir = entry_dir(quarantine_dir, audio_path);
+    fs::create_dir(&dir)
+        .map_err(|e| anyhow::anyhow!("Failed to create quarantine entry {:?}: {}", dir, e))?;
+    
+    // The report goes first so the entry explains itself even if a move fails halfway
+    let report = QuarantineReport {
+        reason,
+        error: error.to_string(),
+        audio_file: audio_path.to_path_buf(),
+        json_file: json_path.to_path_buf(),
+        quarantined_at: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+        retries: 0,
+    };
+    write_report(&dir, &report)?;
+    
+    for path in [audio_path, json_path] {
+        let file_name = path.file_name()
+            .ok_or_else(|| anyhow::anyhow!("Invalid file path {:?}", path))?;
+        move_file_safely(path, &dir.join(file_name))?;
+    }
+    
+    Ok(dir)
+}
+
+/// Every quarantined pair, oldest first
+pub fn list(quarantine_dir: &Path) -> Result<Vec<QuarantineEntry>> {
+    if !quarantine_dir.exists() {
+        return Ok(Vec::new());
+    }
+    
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(quarantine_dir)
+        .map_err(|e| anyhow::anyhow!("Failed to read quarantine directory {:?}: {}", quarantine_dir, e))?
+    {
+        let path = entry?.path();
+        if path.is_dir() {
+            entries.push(QuarantineEntry::load(&path)?);
+        }
+    }
+    entries.sort_by_key(|entry| (entry.report.as_ref().map_or(0, |report| report.quarantined_at), entry.id.clone()));
+    Ok(entries)
+}
+
+/// Look up a quarantined pair by its ID
+pub fn find(quarantine_dir: &Path, id: &str) -> Result<Option<QuarantineEntry>> {
+    let dir = quarantine_dir.join(id);
+    if id.contains('/') || !dir.is_dir() {
+        return Ok(None);
+    }
+    QuarantineEntry::load(&dir).map(Some)
+}
+
+/// Delete a quarantined pair and its report
+pub fn discard(entry: &QuarantineEntry) -> Result<()> {
+    fs::remove_dir_all(&entry.dir)
+        .map_err(|e| anyhow::anyhow!("Failed to remove quarantine entry {:?}: {}", entry.dir, e))
+}
+
+/// Remove an entry's directory once a retry has taken its files, leaving it alone if
+/// anything other than the report is still inside
+pub fn remove_if_settled(entry: &QuarantineEntry) -> Result<bool> {
+    let leftovers = fs::read_dir(&entry.dir)?
+        .filter_map(|e| e.ok())
+        .any(|e| e.file_name() != REPORT_FILE);
+    if leftovers {
+        return Ok(false);
+    }
+    discard(entry)?;
+    Ok(true)
+}
@@ -0,0 +1,304 @@
+use anyhow::Result;
+use serde::{Serialize, Serializer};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::db;
+use crate::layout::{self, CollisionPolicy, PathTemplate};
+use crate::output;
+use crate::rules::CategoryRules;
+use crate::watcher::{is_collision_variant, move_file_safely, resolve_collision_among};
+
+/// A sample whose library location or category no longer matches the current rules
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedMove {
+    pub file_hash: String,
+    pub from: PathBuf,
+    pub to: PathBuf,
+    pub old_category: String,
+    pub new_category: String,
+    pub confidence: f64,
+    pub runner_up: Option<String>,
+}
+
+impl PlannedMove {
+    /// Only the category changed, the file stays where it is
+    pub fn is_category_only(&self) -> bool {
+        self.from == self.to
+    }
+}
+
+/// What `reorganize` would do to the library
+#[derive(Debug, Default, Serialize)]
+pub struct ReorganizePlan {
+    pub moves: Vec<PlannedMove>,
+    pub unchanged: usize,
+    /// Records whose library file no longer exists
+    pub missing: Vec<PathBuf>,
+    /// Moves skipped because something already occupies the target path
+    #[serde(serialize_with = "conflicts")]
+    pub conflicts: Vec<(PathBuf, PathBuf)>,
+}
+
+#[derive(Serialize)]
+struct Conflict<'a> {
+    from: &'a PathBuf,
+    to: &'a PathBuf,
+}
+
+fn conflicts<S: Serializer>(conflicts: &[(PathBuf, PathBuf)], serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    serializer.collect_seq(conflicts.iter().map(|(from, to)| Conflict { from, to }))
+}
+
+/// Result of applying a plan
+#[derive(Debug, Default, Serialize)]
+pub struct ReorganizeSummary {
+    pub moved: usize,
+    pub recategorized: usize,
+    #[serde(serialize_with = "output::failures")]
+    pub failed: Vec<(PathBuf, String)>,
+    pub removed_dirs: usize,
+}
+
+/// Recompute the category and target path of every sample in the database. BPM and
+/// key estimates at least `min_confidence` sure stand in for missing values. A sample
+/// whose target is taken goes where `policy` puts it, and one already renamed by a
+/// collision policy counts as being at its target.
+pub fn plan(
+    database_path: &Path,
+    library_dir: &Path,
+    rules: &CategoryRules,
+    layout: &PathTemplate,
+    policy: CollisionPolicy,
+    min_confidence: f64,
+) -> Result<ReorganizePlan> {
+    let mut plan = ReorganizePlan::default();
+    let mut claimed_targets = HashSet::new();
+    let records = db::get_all_samples(database_path)?;
+    let recorded: HashSet<PathBuf> = records.iter().map(|record| PathBuf::from(&record.file_path)).collect();
+    
+    for record in records {
+        let current = PathBuf::from(&record.file_path);
+        let sample = record.sample_meta_data_with_detections(min_confidence);
+        let categorization = rules.categorize(&sample);
+        let mut target = library_dir.join(layout.render(&sample, &categorization.category));
+        if let Some(extension) = current.extension() {
+            target = layout::with_audio_extension(target, extension);
+        }
+        if is_collision_variant(&current, &target, &record.file_hash) {
+            target = current.clone();
+        }
+        let new_category = categorization.category.as_str();
+        
+        if target == current && new_category == record.mapped_category {
+            plan.unchanged += 1;
+            claimed_targets.insert(target);
+            continue;
+        }
+        
+        if !current.is_file() {
+            plan.missing.push(current);
+            continue;
+        }
+        
+        if target != current {
+            let taken = |path: &Path| path.exists() || claimed_targets.contains(path);
+            match resolve_collision_among(&target, &current, &record.file_hash, policy, taken)? {
+                // Never replace a file that belongs to another sample
+                Some(resolved) if !recorded.contains(&resolved) && !claimed_targets.contains(&resolved) => target = resolved,
+                _ => {
+                    plan.conflicts.push((current, target));
+                    continue;
+                }
+            }
+        }
+        
+        claimed_targets.insert(target.clone());
+        plan.moves.push(PlannedMove {
+            file_hash: record.file_hash,
+            from: current,
+            to: target,
+            old_category: record.mapped_category,
+            new_category,
+            confidence: categorization.confidence as f64,
+            runner_up: categorization.runner_up.map(|c| c.as_str()),
+        });
+    }
+    
+    Ok(plan)
+}
+
+/// Perform the planned moves, keeping the database and the filesystem in step
+pub fn apply(plan: &ReorganizePlan, database_path: &Path, library_dir: &Path) -> ReorganizeSummary {
+    let mut summary = ReorganizeSummary::default();
+    let mut vacated_dirs = HashSet::new();
+    
+    for planned in &plan.moves {
+        match apply_move(planned, database_path) {
+            Ok(()) => {
+                if planned.is_category_only() {
+                    summary.recategorized += 1;
+                } else {
+                    summary.moved += 1;
+                    if let Some(parent) = planned.from.parent() {
+                        vacated_dirs.insert(parent.to_path_buf());
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("🚨 Failed to reorganize {:?}: {}", planned.from, e);
+                summary.failed.push((planned.from.clone(), e.to_string()));
+            }
+        }
+    }
+    
+    for dir in vacated_dirs {
+        summary.removed_dirs += remove_empty_dirs(&dir, library_dir);
+    }
+    
+    summary
+}
+
+fn apply_move(planned: &PlannedMove, database_path: &Path) -> Result<()> {
+    if !planned.is_category_only() {
+        if let Some(parent) = planned.to.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| anyhow::anyhow!("Failed to create directory {:?}: {}", parent, e))?;
+        }
+        move_file_safely(&planned.from, &planned.to)?;
+    }
+    
+    let updated = db::relocate_sample(
+        database_path,
+        &planned.file_hash,
+        &planned.to.to_string_lossy(),
+        &planned.new_category,
+        Some(planned.confidence),
+        planned.runner_up.as_deref(),
+    );
+    
+    if let Err(e) = updated {
+        // Put the file back so the record keeps pointing at a real file
+        if !planned.is_category_only() {
+            if let Err(rollback_error) = move_file_safely(&planned.to, &planned.from) {
+                return Err(anyhow::anyhow!("{} (and failed to move file back: {})", e, rollback_error));
+            }
+        }
+        return Err(e);
+    }
+    
+    Ok(())
+}
+
+/// Remove `dir` and its parents while they are empty, stopping at the library root
+fn remove_empty_dirs(dir: &Path, library_dir: &Path) -> usize {
+    let mut removed = 0;
+    let mut current = Some(dir);
+    
+    while let Some(dir) = current {
+        if dir == library_dir || !dir.starts_with(library_dir) {
+            break;
+        }
+        // remove_dir only succeeds on empty directories
+        if fs::remove_dir(dir).is_err() {
+            break;
+        }
+        removed += 1;
+        current = dir.parent();
+    }
+    
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata;
+    
+    struct Library {
+        dir: tempfile::TempDir,
+        database: PathBuf,
+        rules: CategoryRules,
+        layout: PathTemplate,
+    }
+    
+    impl Library {
+        fn new() -> Self {
+            let dir = tempfile::tempdir().unwrap();
+            let database = dir.path().join("samples.db");
+            db::init_database(&database).unwrap();
+            Self {
+                dir,
+                database,
+                rules: CategoryRules::builtin(),
+                layout: PathTemplate::parse("{category}/{pack}/{filename}").unwrap(),
+            }
+        }
+        
+        fn root(&self) -> PathBuf {
+            self.dir.path().join("lib")
+        }
+        
+        /// Store a sample at `relative_path` in the library, filed under the category the rules give it
+        fn add(&self, relative_path: &str, file_hash: &str) -> PathBuf {
+            let path = self.root().join(relative_path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, file_hash).unwrap();
+            
+            let metadata = metadata::test_metadata();
+            let mut record = db::SampleRecord::from(&metadata);
+            record.file_hash = file_hash.to_string();
+            record.asset_uuid = file_hash.to_string();
+            record.file_path = path.to_string_lossy().to_string();
+            record.mapped_category = self.rules.categorize(&record.sample_meta_data()).category.as_str();
+            db::insert_sample(&self.database, record).unwrap();
+            path
+        }
+        
+        fn plan(&self, policy: CollisionPolicy) -> ReorganizePlan {
+            plan(&self.database, &self.root(), &self.rules, &self.layout, policy, 0.6).unwrap()
+        }
+    }
+    
+    #[test]
+    fn suffixed_samples_count_as_in_place() {
+        let library = Library::new();
+        let original = library.add("Kick/Pack/kick_one.wav", "aaaaaaaaaaaa");
+        library.add("Kick/Pack/kick_one-bbbbbbbb.wav", "bbbbbbbbbbbb");
+        library.add("Kick/Pack/kick_one-2.wav", "cccccccccccc");
+        
+        let plan = library.plan(CollisionPolicy::HashSuffix);
+        assert_eq!((plan.unchanged, plan.moves.len(), plan.conflicts.len()), (3, 0, 0));
+        
+        // Without the original, the renamed copies still stay where they are
+        fs::remove_file(&original).unwrap();
+        let plan = library.plan(CollisionPolicy::HashSuffix);
+        assert!(plan.moves.is_empty(), "{:?}", plan.moves);
+        assert!(plan.conflicts.is_empty());
+    }
+    
+    #[test]
+    fn taken_targets_follow_the_collision_policy() {
+        let library = Library::new();
+        library.add("Kick/Pack/kick_one.wav", "aaaaaaaaaaaa");
+        let misplaced = library.add("Misc/kick_one.wav", "dddddddddddd");
+        let target_dir = library.root().join("Kick/Pack");
+        
+        let plan = library.plan(CollisionPolicy::HashSuffix);
+        assert_eq!(plan.moves.len(), 1);
+        assert_eq!(plan.moves[0].from, misplaced);
+        assert_eq!(plan.moves[0].to, target_dir.join("kick_one-dddddddd.wav"));
+        
+        let plan = library.plan(CollisionPolicy::Counter);
+        assert_eq!(plan.moves[0].to, target_dir.join("kick_one-2.wav"));
+        
+        let plan = library.plan(CollisionPolicy::Skip);
+        assert!(plan.moves.is_empty());
+        assert_eq!(plan.conflicts, [(misplaced.clone(), target_dir.join("kick_one.wav"))]);
+        
+        // Identical contents would still replace another sample's file
+        let plan = library.plan(CollisionPolicy::OverwriteSameHash);
+        assert_eq!(plan.conflicts.len(), 1);
+    }
+}
@@ -0,0 +1,479 @@
+use anyhow::Result;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::metadata::{BitwigCategory, SampleMetaData};
+
+/// One entry of the built-in mapping table
+struct BuiltinRule {
+    category: BitwigCategory,
+    tags: &'static [&'static str],
+    filename_tokens: &'static [&'static str],
+    priority: i32,
+    sample_type: Option<&'static str>,
+    genres: &'static [&'static str],
+    tonal: Option<bool>,
+}
+
+const fn drum_hit(category: BitwigCategory, tags: &'static [&'static str], filename_tokens: &'static [&'static str]) -> BuiltinRule {
+    BuiltinRule { category, tags, filename_tokens, priority: 30, sample_type: Some("oneshot"), genres: &[], tonal: Some(false) }
+}
+
+const fn instrument(category: BitwigCategory, tags: &'static [&'static str], filename_tokens: &'static [&'static str], priority: i32) -> BuiltinRule {
+    BuiltinRule { category, tags, filename_tokens, priority, sample_type: None, genres: &[], tonal: Some(true) }
+}
+
+/// Built-in mapping table used when no rules file is present.
+/// Priorities only break ties between equally scored categories, so drum
+/// elements still win over melodic ones when the evidence is balanced.
+const BUILTIN_RULES: &[BuiltinRule] = &[
+    // Drum elements
+    drum_hit(BitwigCategory::Kick, &["kick", "kicks"], &["kick", "kck", "kik", "bd"]),
+    drum_hit(BitwigCategory::Snare, &["snare", "snares"], &["snare", "snr", "sd"]),
+    drum_hit(BitwigCategory::HiHat, &["hihat", "hi-hat", "hihats", "hi-hats"], &["hihat", "hat", "hats", "hh"]),
+    drum_hit(BitwigCategory::Cymbal, &["cymbal", "cymbals"], &["cymbal", "cym", "crash", "ride", "splash"]),
+    drum_hit(BitwigCategory::Tom, &["tom", "toms"], &["tom", "toms"]),
+    BuiltinRule {
+        category: BitwigCategory::DrumLoop,
+        tags: &["drum loop", "drum loops", "drums"],
+        filename_tokens: &["drum loop", "drums", "beat", "break", "groove"],
+        priority: 25,
+        sample_type: Some("loop"),
+        genres: &[],
+        tonal: Some(false),
+    },
+    BuiltinRule {
+        category: BitwigCategory::Percussion,
+        tags: &["percussion", "perc"],
+        filename_tokens: &["perc", "shaker", "clap", "conga", "bongo", "tamb", "rim"],
+        priority: 20,
+        sample_type: None,
+        genres: &[],
+        tonal: Some(false),
+    },
+    
+    // Instruments
+    instrument(BitwigCategory::Piano, &["piano"], &["piano"], 15),
+    instrument(BitwigCategory::Guitar, &["guitar"], &["guitar", "gtr"], 15),
+    instrument(BitwigCategory::Organ, &["organ"], &["organ"], 15),
+    instrument(BitwigCategory::Bell, &["bell", "bells"], &["bell", "bells"], 15),
+    instrument(BitwigCategory::Brass, &["brass"], &["brass", "trumpet", "horn", "trombone"], 15),
+    instrument(BitwigCategory::Strings, &["strings", "string"], &["strings", "violin", "cello"], 15),
+    instrument(BitwigCategory::Vocal, &["vocal", "vocals", "voice"], &["vocal", "vocals", "vox", "voice", "chant"], 15),
+    
+    // Melodic elements
+    BuiltinRule {
+        category: BitwigCategory::Bass,
+        tags: &["bass", "bassline", "sub bass"],
+        filename_tokens: &["bass", "bassline", "sub", "808"],
+        priority: 10,
+        sample_type: None,
+        genres: &["dubstep", "trap", "drum-and-bass"],
+        tonal: Some(true),
+    },
+    instrument(BitwigCategory::Lead, &["lead", "leads", "lead synth"], &["lead", "ld"], 10),
+    BuiltinRule {
+        category: BitwigCategory::Pad,
+        tags: &["pad", "pads", "ambient"],
+        filename_tokens: &["pad", "pads"],
+        priority: 5,
+        sample_type: None,
+        genres: &["ambient", "cinematic", "chillout"],
+        tonal: Some(true),
+    },
+    instrument(BitwigCategory::Synth, &["synth", "synthesizer"], &["synth", "arp"], 5),
+    
+    // Effects
+    BuiltinRule {
+        category: BitwigCategory::SoundFX,
+        tags: &["fx", "sfx", "sound fx", "effects"],
+        filename_tokens: &["fx", "sfx", "riser", "impact", "sweep", "uplifter", "downlifter"],
+        priority: 5,
+        sample_type: None,
+        genres: &["cinematic"],
+        tonal: None,
+    },
+    BuiltinRule {
+        category: BitwigCategory::Drone,
+        tags: &["drone", "texture"],
+        filename_tokens: &["drone", "texture", "atmos"],
+        priority: 5,
+        sample_type: None,
+        genres: &["ambient", "cinematic", "experimental"],
+        tonal: None,
+    },
+];
+
+/// Smoothing applied to the confidence so a single weak hit is never "certain"
+const CONFIDENCE_SMOOTHING: f32 = 0.5;
+
+/// How much each kind of evidence contributes to a category's score
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SignalWeights {
+    /// Per matching Splice tag
+    pub tag: f32,
+    /// Per matching filename token
+    pub filename: f32,
+    /// When the rule's preferred sample type (loop/oneshot) matches
+    pub sample_type: f32,
+    /// When the pack's main genre is one of the rule's genres
+    pub genre: f32,
+    /// Added when a tonal sample (key or chord) meets a tonal rule, subtracted on mismatch
+    pub tonal: f32,
+}
+
+impl Default for SignalWeights {
+    fn default() -> Self {
+        Self {
+            tag: 1.0,
+            filename: 0.75,
+            sample_type: 0.5,
+            genre: 0.25,
+            tonal: 0.25,
+        }
+    }
+}
+
+/// Evidence that maps a sample to a single category
+#[derive(Debug, Clone, Deserialize)]
+pub struct CategoryRule {
+    #[serde(deserialize_with = "deserialize_category")]
+    pub category: BitwigCategory,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Words looked for in the sample filename (e.g. "kick", "snr", "hat")
+    #[serde(default)]
+    pub filename_tokens: Vec<String>,
+    /// Breaks ties between equally scored categories
+    #[serde(default)]
+    pub priority: i32,
+    /// Multiplier applied to tag and filename evidence
+    #[serde(default = "default_weight")]
+    pub weight: f32,
+    /// Preferred sample type ("loop" or "oneshot")
+    #[serde(default)]
+    pub sample_type: Option<String>,
+    /// Pack genres that make this category more likely
+    #[serde(default)]
+    pub genres: Vec<String>,
+    /// Whether this category is melodic (`true`) or unpitched (`false`)
+    #[serde(default)]
+    pub tonal: Option<bool>,
+}
+
+fn default_weight() -> f32 {
+    1.0
+}
+
+fn deserialize_category<'de, D>(deserializer: D) -> std::result::Result<BitwigCategory, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let name = String::deserialize(deserializer)?;
+    name.parse()
+        .map_err(|_| serde::de::Error::custom(format!("unknown Bitwig category '{}'", name)))
+}
+
+/// Category mapping rules, either built in or loaded from `rules.toml`
+#[derive(Debug, Clone, Deserialize)]
+pub struct CategoryRules {
+    #[serde(default)]
+    pub weights: SignalWeights,
+    #[serde(rename = "rule", default)]
+    pub rules: Vec<CategoryRule>,
+    /// File the rules were loaded from, `None` for the built-in table
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
+}
+
+/// A rule that found evidence for its category
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleMatch {
+    /// Zero-based position of the rule in the rules table
+    pub rule_index: usize,
+    #[serde(serialize_with = "crate::output::display")]
+    pub category: BitwigCategory,
+    pub priority: i32,
+    pub score: f32,
+    /// Human readable breakdown of how the score was reached
+    pub signals: Vec<String>,
+    /// Position of the earliest matching tag, used as the final tie-breaker
+    #[serde(skip)]
+    pub first_tag_position: usize,
+}
+
+/// Outcome of scoring a sample against the rules
+#[derive(Debug, Clone, Serialize)]
+pub struct Categorization {
+    #[serde(serialize_with = "crate::output::display")]
+    pub category: BitwigCategory,
+    /// Share of the evidence backing the winner, from 0.0 to 1.0
+    pub confidence: f32,
+    /// Best scoring category other than the winner
+    #[serde(serialize_with = "crate::output::display_option")]
+    pub runner_up: Option<BitwigCategory>,
+    /// The best matching rule of every category that scored, best first
+    pub matches: Vec<RuleMatch>,
+}
+
+impl Categorization {
+    /// The rule that decided the category, if any matched
+    pub fn fired(&self) -> Option<&RuleMatch> {
+        self.matches.first()
+    }
+}
+
+impl Default for CategoryRules {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+/// Lowercase a loop/oneshot label so "One-Shot", "one_shot" and "oneshot" compare equal
+pub fn normalize_sample_type(sample_type: &str) -> String {
+    sample_type
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Split a filename into lowercase words at separators and letter/digit boundaries
+fn filename_words(filename: &str) -> Vec<String> {
+    let stem = Path::new(filename)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| filename.to_string());
+    
+    let mut words = Vec::new();
+    let mut current = String::new();
+    
+    for c in stem.chars() {
+        if !c.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+        
+        let boundary = current
+            .chars()
+            .last()
+            .is_some_and(|prev| prev.is_ascii_digit() != c.is_ascii_digit());
+        if boundary {
+            words.push(std::mem::take(&mut current));
+        }
+        current.extend(c.to_lowercase());
+    }
+    
+    if !current.is_empty() {
+        words.push(current);
+    }
+    
+    words
+}
+
+/// Whether `token` (possibly several words, e.g. "drum loop") occurs in the filename words
+fn contains_token(words: &[String], token: &str) -> bool {
+    let token_words: Vec<String> = token
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect();
+    !token_words.is_empty() && words.windows(token_words.len()).any(|window| window == token_words.as_slice())
+}
+
+impl CategoryRules {
+    pub fn builtin() -> Self {
+        let rules = BUILTIN_RULES
+            .iter()
+            .map(|rule| CategoryRule {
+                category: rule.category.clone(),
+                tags: rule.tags.iter().map(|t| t.to_string()).collect(),
+                filename_tokens: rule.filename_tokens.iter().map(|t| t.to_string()).collect(),
+                priority: rule.priority,
+                weight: default_weight(),
+                sample_type: rule.sample_type.map(|t| t.to_string()),
+                genres: rule.genres.iter().map(|g| g.to_string()).collect(),
+                tonal: rule.tonal,
+            })
+            .collect();
+        
+        Self { weights: SignalWeights::default(), rules, source: None }
+    }
+    
+    /// Default location of the rules file (`~/.config/splice-alt/rules.toml`)
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("splice-alt").join("rules.toml"))
+    }
+    
+    /// Load rules from the XDG config dir, falling back to the built-in table
+    pub fn load() -> Result<Self> {
+        match Self::default_path() {
+            Some(path) if path.exists() => Self::from_file(&path),
+            _ => Ok(Self::builtin()),
+        }
+    }
+    
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read rules file {:?}: {}", path, e))?;
+        let mut rules: CategoryRules = toml::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("Failed to parse rules file {:?}: {}", path, e))?;
+        rules.validate()
+            .map_err(|e| anyhow::anyhow!("Invalid rules file {:?}: {}", path, e))?;
+        rules.source = Some(path.to_path_buf());
+        Ok(rules)
+    }
+    
+    fn validate(&self) -> Result<()> {
+        if self.rules.is_empty() {
+            anyhow::bail!("no [[rule]] entries defined");
+        }
+        
+        for (index, rule) in self.rules.iter().enumerate() {
+            let has_evidence = rule.tags.iter().chain(&rule.filename_tokens).any(|t| !t.trim().is_empty());
+            if !has_evidence {
+                anyhow::bail!("rule #{} ({}) has no tags or filename tokens", index + 1, rule.category);
+            }
+            if rule.weight.is_nan() || rule.weight <= 0.0 {
+                anyhow::bail!("rule #{} ({}) must have a positive weight", index + 1, rule.category);
+            }
+            if let Some(sample_type) = &rule.sample_type {
+                let normalized = normalize_sample_type(sample_type);
+                if normalized != "loop" && normalized != "oneshot" {
+                    anyhow::bail!("rule #{} ({}) has sample_type '{}', expected 'loop' or 'oneshot'", index + 1, rule.category, sample_type);
+                }
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Human readable description of where the rules came from
+    pub fn source_description(&self) -> String {
+        match &self.source {
+            Some(path) => format!("{:?}", path),
+            None => "built-in defaults".to_string(),
+        }
+    }
+    
+    /// Score a single rule against the sample, returning `None` without direct evidence
+    fn score_rule(&self, rule_index: usize, rule: &CategoryRule, sample: &SampleMetaData, tags_lower: &[String], words: &[String]) -> Option<RuleMatch> {
+        let weights = &self.weights;
+        let mut score = 0.0;
+        let mut signals = Vec::new();
+        let mut first_tag_position = usize::MAX;
+        
+        for (position, tag) in tags_lower.iter().enumerate() {
+            if rule.tags.iter().any(|synonym| synonym.trim().to_lowercase() == *tag) {
+                let points = weights.tag * rule.weight;
+                score += points;
+                signals.push(format!("tag '{}' (+{:.2})", sample.tags[position], points));
+                first_tag_position = first_tag_position.min(position);
+            }
+        }
+        
+        for token in &rule.filename_tokens {
+            if contains_token(words, token) {
+                let points = weights.filename * rule.weight;
+                score += points;
+                signals.push(format!("filename token '{}' (+{:.2})", token.trim().to_lowercase(), points));
+            }
+        }
+        
+        // Context signals only reinforce or weaken direct evidence
+        if signals.is_empty() {
+            return None;
+        }
+        
+        if let Some(preferred) = &rule.sample_type {
+            if normalize_sample_type(preferred) == normalize_sample_type(&sample.sample_type) {
+                score += weights.sample_type;
+                signals.push(format!("sample type '{}' (+{:.2})", sample.sample_type, weights.sample_type));
+            }
+        }
+        
+        let genre = sample.pack.main_genre.trim().to_lowercase();
+        if !genre.is_empty() && rule.genres.iter().any(|g| g.trim().to_lowercase() == genre) {
+            score += weights.genre;
+            signals.push(format!("genre '{}' (+{:.2})", sample.pack.main_genre, weights.genre));
+        }
+        
+        if let Some(tonal) = rule.tonal {
+            let sample_is_tonal = sample.audio_key.is_some() || sample.chord_type.is_some();
+            let label = if sample_is_tonal { "has key/chord" } else { "no key/chord" };
+            if tonal == sample_is_tonal {
+                score += weights.tonal;
+                signals.push(format!("{} (+{:.2})", label, weights.tonal));
+            } else {
+                score -= weights.tonal;
+                signals.push(format!("{} (-{:.2})", label, weights.tonal));
+            }
+        }
+        
+        if score <= 0.0 {
+            return None;
+        }
+        
+        Some(RuleMatch {
+            rule_index,
+            category: rule.category.clone(),
+            priority: rule.priority,
+            score,
+            signals,
+            first_tag_position,
+        })
+    }
+    
+    /// Score every rule against the sample and pick the best category
+    pub fn categorize(&self, sample: &SampleMetaData) -> Categorization {
+        let tags_lower: Vec<String> = sample.tags.iter().map(|t| t.trim().to_lowercase()).collect();
+        let words = filename_words(&sample.filename);
+        
+        let mut matches: Vec<RuleMatch> = self.rules
+            .iter()
+            .enumerate()
+            .filter_map(|(index, rule)| self.score_rule(index, rule, sample, &tags_lower, &words))
+            .collect();
+        
+        // Score first, then priority, then Splice's tag order
+        matches.sort_by(|a, b| {
+            b.score.total_cmp(&a.score)
+                .then(b.priority.cmp(&a.priority))
+                .then(a.first_tag_position.cmp(&b.first_tag_position))
+                .then(a.rule_index.cmp(&b.rule_index))
+        });
+        
+        // Keep only the best rule per category so the runner-up is a real alternative
+        let mut seen = Vec::new();
+        matches.retain(|m| {
+            if seen.contains(&m.category) {
+                false
+            } else {
+                seen.push(m.category.clone());
+                true
+            }
+        });
+        
+        let Some(best) = matches.first() else {
+            return Categorization {
+                category: BitwigCategory::Unknown,
+                confidence: 0.0,
+                runner_up: None,
+                matches,
+            };
+        };
+        
+        let runner_up_score = matches.get(1).map_or(0.0, |m| m.score);
+        let confidence = best.score / (best.score + runner_up_score + CONFIDENCE_SMOOTHING);
+        
+        Categorization {
+            category: best.category.clone(),
+            confidence,
+            runner_up: matches.get(1).map(|m| m.category.clone()),
+            matches,
+        }
+    }
+}
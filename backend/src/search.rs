@@ -0,0 +1,581 @@
+use anyhow::Result;
+use rusqlite::{params_from_iter, types::Value, Connection};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::db::{self, SampleRecord};
+use crate::metadata::BitwigCategory;
+
+/// Inclusive BPM range, written as `120`, `120..128`, `120..` or `..128`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BpmRange {
+    pub min: Option<i32>,
+    pub max: Option<i32>,
+}
+
+impl FromStr for BpmRange {
+    type Err = String;
+    
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let parse_bound = |bound: &str| -> std::result::Result<Option<i32>, String> {
+            let bound = bound.trim();
+            if bound.is_empty() {
+                return Ok(None);
+            }
+            bound.parse::<i32>()
+                .map(Some)
+                .map_err(|_| format!("'{}' is not a valid BPM", bound))
+        };
+        
+        let range = match s.split_once("..") {
+            Some((min, max)) => BpmRange { min: parse_bound(min)?, max: parse_bound(max)? },
+            None => {
+                let exact = parse_bound(s)?;
+                BpmRange { min: exact, max: exact }
+            }
+        };
+        
+        match range {
+            BpmRange { min: None, max: None } => Err("BPM range needs at least one bound, e.g. 120..128".to_string()),
+            BpmRange { min: Some(min), max: Some(max) } if min > max => Err(format!("BPM range {}..{} is empty", min, max)),
+            range => Ok(range),
+        }
+    }
+}
+
+/// Inclusive range of a measured level, written as `-14..-8`, `-1..` or `..-1`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LevelRange {
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl FromStr for LevelRange {
+    type Err = String;
+    
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let parse_bound = |bound: &str| -> std::result::Result<Option<f64>, String> {
+            let bound = bound.trim();
+            if bound.is_empty() {
+                return Ok(None);
+            }
+            bound.parse::<f64>()
+                .ok()
+                .filter(|value| value.is_finite())
+                .map(Some)
+                .ok_or_else(|| format!("'{}' is not a number", bound))
+        };
+        
+        // Measurements are never exact, so a single value isn't a useful filter
+        let (min, max) = s.split_once("..")
+            .ok_or_else(|| format!("'{}' is not a range, expected e.g. -14..-8, -1.. or ..-1", s))?;
+        
+        match (parse_bound(min)?, parse_bound(max)?) {
+            (None, None) => Err("Range needs at least one bound, e.g. -14..-8".to_string()),
+            (Some(min), Some(max)) if min > max => Err(format!("Range {}..{} is empty", min, max)),
+            (min, max) => Ok(LevelRange { min, max }),
+        }
+    }
+}
+
+/// Musical key such as `A`, `Am`, `F#min` or `Bbmaj`
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyFilter {
+    /// Root note, lowercase with sharps (`a#` rather than `bb`)
+    pub root: String,
+    /// `minor` or `major`, `None` to match either
+    pub chord: Option<&'static str>,
+}
+
+/// Flat spellings and their sharp equivalents
+const ENHARMONICS: &[(&str, &str)] = &[("db", "c#"), ("eb", "d#"), ("gb", "f#"), ("ab", "g#"), ("bb", "a#")];
+
+impl KeyFilter {
+    /// Both spellings of the root so `Bb` also finds samples stored as `A#`
+    fn spellings(&self) -> Vec<String> {
+        let mut spellings = vec![self.root.clone()];
+        spellings.extend(
+            ENHARMONICS
+                .iter()
+                .filter(|(_, sharp)| *sharp == self.root)
+                .map(|(flat, _)| flat.to_string()),
+        );
+        spellings
+    }
+}
+
+impl FromStr for KeyFilter {
+    type Err = String;
+    
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let lower = s.trim().to_lowercase();
+        let mut chars = lower.chars();
+        let root = match chars.next() {
+            Some(c @ 'a'..='g') => c,
+            _ => return Err(format!("'{}' is not a key, expected e.g. A, Am, F#min or Bbmaj", s)),
+        };
+        
+        let rest = chars.as_str();
+        let (accidental, suffix) = match rest.chars().next() {
+            Some('#') => ("#", &rest[1..]),
+            // A lone "b" after the root is a flat; "bm"/"bmaj" etc. too
+            Some('b') => ("b", &rest[1..]),
+            _ => ("", rest),
+        };
+        
+        let chord = match suffix.trim() {
+            "" => None,
+            "m" | "min" | "minor" => Some("minor"),
+            "maj" | "major" => Some("major"),
+            other => return Err(format!("unknown key quality '{}' in '{}', expected m, min, maj or major", other, s)),
+        };
+        
+        let mut note = format!("{}{}", root, accidental);
+        if let Some((_, sharp)) = ENHARMONICS.iter().find(|(flat, _)| *flat == note) {
+            note = sharp.to_string();
+        }
+        
+        Ok(KeyFilter { root: note, chord })
+    }
+}
+
+/// Result ordering for `search`
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum SearchSort {
+    /// Best full-text match first (pack order without a query)
+    Relevance,
+    Bpm,
+    Filename,
+    Pack,
+    Category,
+    /// Most recently downloaded first
+    Downloaded,
+    /// Loudest integrated loudness first
+    Loudness,
+}
+
+/// Free text plus structured filters, all of which must match
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+    pub text: Option<String>,
+    pub bpm: Option<BpmRange>,
+    pub key: Option<KeyFilter>,
+    pub sample_type: Option<String>,
+    pub category: Option<BitwigCategory>,
+    /// Tags the sample must all carry
+    pub tags: Vec<String>,
+    pub pack: Option<String>,
+    pub provider: Option<String>,
+    /// `YYYY-MM-DD`, inclusive
+    pub downloaded_after: Option<String>,
+    /// Audio properties from the WAV header; samples without them never match
+    pub sample_rate: Option<u32>,
+    pub bit_depth: Option<u16>,
+    pub channels: Option<u16>,
+    /// Loudness measurements; samples not measured yet never match
+    pub lufs: Option<LevelRange>,
+    pub true_peak: Option<LevelRange>,
+    pub peak: Option<LevelRange>,
+    pub rms: Option<LevelRange>,
+    /// Matched against the size of the offset, whichever its sign
+    pub dc_offset: Option<LevelRange>,
+    /// BPM and key estimates at least this sure stand in for missing values
+    pub min_detection_confidence: f64,
+    pub sort: SearchSort,
+    pub limit: Option<usize>,
+}
+
+/// Turn user input into an FTS5 query: every word must match, as a prefix.
+/// Words are quoted so characters like `-` or `:` aren't read as FTS5 syntax.
+fn fts_query(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+    
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+/// Same normalization as the rules use, so "One-Shot" matches "oneshot"
+fn normalize_sample_type(sample_type: &str) -> String {
+    sample_type
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Check a `--downloaded-after` value is a plain `YYYY-MM-DD` date
+pub fn parse_date(s: &str) -> std::result::Result<String, String> {
+    let parts: Vec<&str> = s.trim().split('-').collect();
+    let valid = matches!(parts.as_slice(), [y, m, d]
+        if y.len() == 4 && m.len() == 2 && d.len() == 2
+            && [y, m, d].iter().all(|p| p.chars().all(|c| c.is_ascii_digit()))
+            && (1..=12).contains(&m.parse::<u32>().unwrap_or(0))
+            && (1..=31).contains(&d.parse::<u32>().unwrap_or(0)));
+    
+    if valid {
+        Ok(s.trim().to_string())
+    } else {
+        Err(format!("'{}' is not a date, expected YYYY-MM-DD", s))
+    }
+}
+
+impl SearchQuery {
+    /// Build the SQL statement and its parameters
+    fn to_sql(&self) -> (String, Vec<Value>) {
+        let mut conditions = Vec::new();
+        let mut values: Vec<Value> = Vec::new();
+        let mut sql = String::new();
+        
+        let fts = self.text.as_deref().and_then(fts_query);
+        if let Some(fts) = &fts {
+            values.push(Value::Text(fts.clone()));
+            sql.push_str(&format!(
+                "WITH matches AS (
+                    SELECT rowid AS sample_id, bm25(samples_fts) AS rank
+                    FROM samples_fts WHERE samples_fts MATCH ?{}
+                )
+                SELECT {} FROM samples JOIN matches ON matches.sample_id = samples.id",
+                values.len(),
+                db::SAMPLE_COLUMNS
+            ));
+        } else {
+            sql.push_str(&format!("SELECT {} FROM samples", db::SAMPLE_COLUMNS));
+        }
+        
+        // Splice's BPM and key, or the estimates when those are missing and sure enough.
+        // The threshold is only bound when used: unused parameters are an error.
+        let mut confidence = 0;
+        if self.bpm.is_some() || self.key.is_some() || self.sort == SearchSort::Bpm {
+            values.push(Value::Real(self.min_detection_confidence));
+            confidence = values.len();
+        }
+        let bpm_column = format!(
+            "COALESCE(NULLIF(bpm, 0), CASE WHEN detected_bpm_confidence >= ?{} THEN CAST(ROUND(detected_bpm) AS INTEGER) END)",
+            confidence
+        );
+        let use_detected_key = format!(
+            "(NULLIF(TRIM(audio_key), '') IS NULL AND detected_key_confidence >= ?{})",
+            confidence
+        );
+        let key_column = format!("CASE WHEN {} THEN detected_key ELSE audio_key END", use_detected_key);
+        let chord_column = format!("CASE WHEN {} THEN detected_chord_type ELSE chord_type END", use_detected_key);
+        
+        if let Some(bpm) = self.bpm {
+            if let Some(min) = bpm.min {
+                values.push(Value::Integer(min as i64));
+                conditions.push(format!("{} >= ?{}", bpm_column, values.len()));
+            }
+            if let Some(max) = bpm.max {
+                values.push(Value::Integer(max as i64));
+                conditions.push(format!("{} <= ?{}", bpm_column, values.len()));
+            }
+        }
+        
+        if let Some(key) = &self.key {
+            let placeholders: Vec<String> = key
+                .spellings()
+                .into_iter()
+                .map(|spelling| {
+                    values.push(Value::Text(spelling));
+                    format!("?{}", values.len())
+                })
+                .collect();
+            conditions.push(format!("LOWER({}) IN ({})", key_column, placeholders.join(", ")));
+            
+            if let Some(chord) = key.chord {
+                values.push(Value::Text(chord.to_string()));
+                conditions.push(format!("LOWER({}) = ?{}", chord_column, values.len()));
+            }
+        }
+        
+        if let Some(sample_type) = &self.sample_type {
+            values.push(Value::Text(normalize_sample_type(sample_type)));
+            conditions.push(format!(
+                "LOWER(REPLACE(REPLACE(REPLACE(sample_type, '-', ''), '_', ''), ' ', '')) = ?{}",
+                values.len()
+            ));
+        }
+        
+        if let Some(category) = &self.category {
+            values.push(Value::Text(category.as_str()));
+            conditions.push(format!("mapped_category = ?{}", values.len()));
+        }
+        
+        for tag in &self.tags {
+            values.push(Value::Text(db::normalize_tag(tag)));
+            conditions.push(format!(
+                "id IN (SELECT sample_tags.sample_id FROM sample_tags
+                        JOIN tags ON tags.id = sample_tags.tag_id WHERE tags.name = ?{})",
+                values.len()
+            ));
+        }
+        
+        // LIKE is case-insensitive for ASCII, which is what pack and provider names mostly are
+        if let Some(pack) = &self.pack {
+            values.push(Value::Text(format!("%{}%", pack)));
+            conditions.push(format!("pack_name LIKE ?{}", values.len()));
+        }
+        
+        if let Some(provider) = &self.provider {
+            values.push(Value::Text(format!("%{}%", provider)));
+            conditions.push(format!("provider_name LIKE ?{}", values.len()));
+        }
+        
+        if let Some(date) = &self.downloaded_after {
+            values.push(Value::Text(date.clone()));
+            conditions.push(format!("date(date_downloaded) >= date(?{})", values.len()));
+        }
+        
+        let properties = [
+            ("sample_rate", self.sample_rate.map(i64::from)),
+            ("bit_depth", self.bit_depth.map(i64::from)),
+            ("channels", self.channels.map(i64::from)),
+        ];
+        for (column, value) in properties {
+            if let Some(value) = value {
+                values.push(Value::Integer(value));
+                conditions.push(format!("{} = ?{}", column, values.len()));
+            }
+        }
+        
+        let levels = [
+            ("loudness_lufs", self.lufs),
+            ("true_peak_dbtp", self.true_peak),
+            ("sample_peak_dbfs", self.peak),
+            ("rms_dbfs", self.rms),
+            ("ABS(dc_offset)", self.dc_offset),
+        ];
+        for (column, range) in levels {
+            let Some(range) = range else { continue };
+            if let Some(min) = range.min {
+                values.push(Value::Real(min));
+                conditions.push(format!("{} >= ?{}", column, values.len()));
+            }
+            if let Some(max) = range.max {
+                values.push(Value::Real(max));
+                conditions.push(format!("{} <= ?{}", column, values.len()));
+            }
+        }
+        
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+        
+        let order = match self.sort {
+            SearchSort::Relevance if fts.is_some() => "matches.rank, pack_name, filename".to_string(),
+            SearchSort::Relevance | SearchSort::Pack => "pack_name, filename".to_string(),
+            SearchSort::Bpm => format!("{0} IS NULL, {0}, pack_name, filename", bpm_column),
+            SearchSort::Filename => "filename, pack_name".to_string(),
+            SearchSort::Category => "mapped_category, pack_name, filename".to_string(),
+            SearchSort::Downloaded => "date_downloaded DESC, pack_name, filename".to_string(),
+            SearchSort::Loudness => "loudness_lufs IS NULL, loudness_lufs DESC, pack_name, filename".to_string(),
+        };
+        sql.push_str(" ORDER BY ");
+        sql.push_str(&order);
+        
+        if let Some(limit) = self.limit {
+            values.push(Value::Integer(limit as i64));
+            sql.push_str(&format!(" LIMIT ?{}", values.len()));
+        }
+        
+        (sql, values)
+    }
+}
+
+/// Run a search against the sample database
+pub fn search_samples(db_path: &Path, query: &SearchQuery) -> Result<Vec<SampleRecord>> {
+    let conn = Connection::open(db_path)?;
+    let (sql, values) = query.to_sql();
+    
+    let mut stmt = conn.prepare(&sql)?;
+    let samples = stmt.query_map(params_from_iter(values), db::sample_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    
+    Ok(samples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::Analysis;
+    use crate::metadata;
+    
+    fn key(s: &str) -> (String, Option<&'static str>) {
+        let key: KeyFilter = s.parse().unwrap();
+        (key.root, key.chord)
+    }
+    
+    #[test]
+    fn parses_keys_with_flats_sharps_and_qualities() {
+        assert_eq!(key("Bb"), ("a#".to_string(), None));
+        assert_eq!(key("A#"), ("a#".to_string(), None));
+        assert_eq!(key("Bm"), ("b".to_string(), Some("minor")));
+        assert_eq!(key("bmaj"), ("b".to_string(), Some("major")));
+        assert_eq!(key("Bbm"), ("a#".to_string(), Some("minor")));
+        assert_eq!(key(" F#min "), ("f#".to_string(), Some("minor")));
+        assert_eq!(key("Ebmajor"), ("d#".to_string(), Some("major")));
+        
+        assert!("H".parse::<KeyFilter>().is_err());
+        assert!("Cdim".parse::<KeyFilter>().unwrap_err().contains("unknown key quality 'dim'"));
+        
+        let flat: KeyFilter = "Bb".parse().unwrap();
+        assert_eq!(flat.spellings(), vec!["a#", "bb"]);
+    }
+    
+    #[test]
+    fn parses_open_and_exact_bpm_ranges() {
+        assert_eq!("120".parse(), Ok(BpmRange { min: Some(120), max: Some(120) }));
+        assert_eq!("120..128".parse(), Ok(BpmRange { min: Some(120), max: Some(128) }));
+        assert_eq!("120..".parse(), Ok(BpmRange { min: Some(120), max: None }));
+        assert_eq!(" ..128".parse(), Ok(BpmRange { min: None, max: Some(128) }));
+        
+        assert_eq!("128..120".parse::<BpmRange>(), Err("BPM range 128..120 is empty".to_string()));
+        assert!("..".parse::<BpmRange>().unwrap_err().contains("at least one bound"));
+        assert!("fast".parse::<BpmRange>().unwrap_err().contains("not a valid BPM"));
+    }
+    
+    #[test]
+    fn quotes_fts_terms() {
+        assert_eq!(fts_query("kick-808 snare"), Some("\"kick-808\"* \"snare\"*".to_string()));
+        assert_eq!(fts_query("type:loop"), Some("\"type:loop\"*".to_string()));
+        assert_eq!(fts_query("say \"hi\""), Some("\"say\"* \"\"\"hi\"\"\"*".to_string()));
+        assert_eq!(fts_query("   "), None);
+    }
+    
+    #[test]
+    fn parses_dates() {
+        assert_eq!(parse_date(" 2024-03-09 "), Ok("2024-03-09".to_string()));
+        for bad in ["2024-3-9", "2024-13-01", "2024-01-32", "yesterday", "2024-01-01T00:00"] {
+            assert!(parse_date(bad).is_err(), "{}", bad);
+        }
+    }
+    
+    /// A database of four samples: two with Splice's BPM and key, and two with only
+    /// estimates, one of them too unsure to count
+    struct Library {
+        _dir: tempfile::TempDir,
+        database: std::path::PathBuf,
+    }
+    
+    impl Library {
+        fn new() -> Self {
+            let dir = tempfile::tempdir().unwrap();
+            let database = dir.path().join("samples.db");
+            db::init_database(&database).unwrap();
+            let library = Self { _dir: dir, database };
+            
+            library.add("kick_one.wav", "Pack", Some((120, "a", "minor")), &["kick", "drums"], None);
+            library.add("bass_loop.wav", "Deep House", Some((128, "a#", "minor")), &["bass", "loop"], None);
+            library.add("pad_loop.wav", "Pads", None, &["pad", "loop"], Some((90.2, "c", "major", 0.8)));
+            library.add("fx_riser.wav", "Pads", None, &["fx"], Some((140.0, "d", "minor", 0.3)));
+            library
+        }
+        
+        fn add(&self, filename: &str, pack: &str, declared: Option<(u32, &str, &str)>, tags: &[&str], detected: Option<(f64, &str, &str, f64)>) {
+            let mut metadata = metadata::test_metadata();
+            let sample = &mut metadata.sample_meta_data;
+            sample.filename = filename.to_string();
+            sample.pack.name = pack.to_string();
+            sample.tags = tags.iter().map(|tag| tag.to_string()).collect();
+            sample.bpm = declared.map(|(bpm, _, _)| bpm);
+            sample.audio_key = declared.map(|(_, key, _)| key.to_string());
+            sample.chord_type = declared.map(|(_, _, chord)| chord.to_string());
+            
+            let mut record = db::SampleRecord::from(&metadata);
+            record.file_hash = filename.to_string();
+            record.asset_uuid = filename.to_string();
+            record.file_path = format!("/library/{}", filename);
+            db::insert_sample(&self.database, record).unwrap();
+            
+            if let Some((bpm, key, chord, confidence)) = detected {
+                let analysis = Analysis {
+                    bpm: Some(bpm),
+                    bpm_confidence: Some(confidence),
+                    key: Some(key.to_string()),
+                    chord_type: Some(chord.to_string()),
+                    key_confidence: Some(confidence),
+                };
+                db::store_analysis(&self.database, filename, &analysis).unwrap();
+            }
+        }
+        
+        fn search(&self, query: &SearchQuery) -> Vec<String> {
+            search_samples(&self.database, query).unwrap().into_iter().map(|sample| sample.filename).collect()
+        }
+    }
+    
+    fn query() -> SearchQuery {
+        SearchQuery {
+            text: None,
+            bpm: None,
+            key: None,
+            sample_type: None,
+            category: None,
+            tags: Vec::new(),
+            pack: None,
+            provider: None,
+            downloaded_after: None,
+            sample_rate: None,
+            bit_depth: None,
+            channels: None,
+            lufs: None,
+            true_peak: None,
+            peak: None,
+            rms: None,
+            dc_offset: None,
+            min_detection_confidence: 0.6,
+            sort: SearchSort::Filename,
+            limit: None,
+        }
+    }
+    
+    #[test]
+    fn filters_narrow_the_results() {
+        let library = Library::new();
+        assert_eq!(library.search(&query()).len(), 4);
+        
+        let text = SearchQuery { text: Some("loop".to_string()), ..query() };
+        assert_eq!(library.search(&text), ["bass_loop.wav", "pad_loop.wav"]);
+        
+        let tags = SearchQuery { tags: vec!["Loop".to_string(), "bass".to_string()], ..query() };
+        assert_eq!(library.search(&tags), ["bass_loop.wav"]);
+        
+        let pack = SearchQuery { pack: Some("deep".to_string()), ..query() };
+        assert_eq!(library.search(&pack), ["bass_loop.wav"]);
+        
+        let key = SearchQuery { key: Some("Bbm".parse().unwrap()), ..query() };
+        assert_eq!(library.search(&key), ["bass_loop.wav"]);
+        
+        let limited = SearchQuery { limit: Some(1), ..query() };
+        assert_eq!(library.search(&limited), ["bass_loop.wav"]);
+    }
+    
+    #[test]
+    fn detected_values_stand_in_when_sure_enough() {
+        let library = Library::new();
+        
+        let bpm = |range: &str| SearchQuery { bpm: Some(range.parse().unwrap()), ..query() };
+        assert_eq!(library.search(&bpm("120..128")), ["bass_loop.wav", "kick_one.wav"]);
+        // The pad's 90.2 BPM estimate counts as 90; the riser's estimate is too unsure
+        assert_eq!(library.search(&bpm("90")), ["pad_loop.wav"]);
+        assert!(library.search(&bpm("130..")).is_empty());
+        
+        let unsure = SearchQuery { min_detection_confidence: 0.2, ..bpm("130..") };
+        assert_eq!(library.search(&unsure), ["fx_riser.wav"]);
+        let strict = SearchQuery { min_detection_confidence: 0.9, ..bpm("..100") };
+        assert!(library.search(&strict).is_empty());
+        
+        let key = SearchQuery { key: Some("Cmaj".parse().unwrap()), ..query() };
+        assert_eq!(library.search(&key), ["pad_loop.wav"]);
+        
+        let by_bpm = SearchQuery { sort: SearchSort::Bpm, ..query() };
+        assert_eq!(library.search(&by_bpm), ["pad_loop.wav", "kick_one.wav", "bass_loop.wav", "fx_riser.wav"]);
+    }
+}
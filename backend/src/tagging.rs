@@ -0,0 +1,322 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::db::{self, SampleRecord};
+use crate::metadata::AudioFormat;
+use crate::output;
+use crate::rules::normalize_sample_type;
+use crate::wav::{self, Chunk, WavInfo};
+
+/// Written as the software that tagged the file
+const SOFTWARE: &str = concat!("splice-alt-daemon ", env!("CARGO_PKG_VERSION"));
+
+/// Fixed part of a `bext` chunk (EBU Tech 3285, version 1), up to the coding history
+const BEXT_LEN: usize = 602;
+
+/// `acid` flags (the chunk is undocumented; these are the bits ACID and DAWs agree on)
+const ACID_ONE_SHOT: u32 = 0x01;
+const ACID_ROOT_NOTE_SET: u32 = 0x02;
+const ACID_STRETCH: u32 = 0x04;
+
+/// MIDI note of the octave root notes are placed in: 60 is middle C
+const ROOT_OCTAVE: u8 = 60;
+
+/// Note names and their offset from C, flats included
+const NOTE_OFFSETS: &[(&str, u8)] = &[
+    ("c", 0), ("c#", 1), ("db", 1), ("d", 2), ("d#", 3), ("eb", 3), ("e", 4), ("f", 5),
+    ("f#", 6), ("gb", 6), ("g", 7), ("g#", 8), ("ab", 8), ("a", 9), ("a#", 10), ("bb", 10), ("b", 11),
+];
+
+/// Which chunks tagging writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmbedOptions {
+    /// `LIST`/`INFO`, `bext` and `iXML` with the Splice metadata
+    pub metadata: bool,
+    /// `acid` and `smpl` with tempo, beats, root note and loop points
+    pub loop_chunks: bool,
+}
+
+impl EmbedOptions {
+    pub fn any(&self) -> bool {
+        self.metadata || self.loop_chunks
+    }
+}
+
+/// Key as musicians write it: `a#` + `minor` becomes `A#m`
+fn key_name(record: &SampleRecord) -> Option<String> {
+    let key = record.audio_key.as_deref()?.trim();
+    let mut chars = key.chars();
+    let root = chars.next()?.to_ascii_uppercase();
+    let quality = match record.chord_type.as_deref() {
+        Some(chord) if chord.eq_ignore_ascii_case("minor") => "m",
+        _ => "",
+    };
+    Some(format!("{}{}{}", root, chars.as_str(), quality))
+}
+
+fn tag_list(record: &SampleRecord) -> Vec<String> {
+    serde_json::from_str(&record.tags).unwrap_or_default()
+}
+
+/// One-line summary used for the INFO comment and the `bext` description
+fn description(record: &SampleRecord) -> String {
+    let mut parts = vec![format!("{} from {}", record.filename, record.pack_name)];
+    if let Some(bpm) = record.bpm {
+        parts.push(format!("{} BPM", bpm));
+    }
+    if let Some(key) = key_name(record) {
+        parts.push(key);
+    }
+    if !record.sample_type.is_empty() {
+        parts.push(record.sample_type.clone());
+    }
+    parts.join(", ")
+}
+
+/// Fixed-width, NUL-padded ASCII field; other characters become `?`
+fn fixed_field(text: &str, width: usize) -> Vec<u8> {
+    let ascii: String = text.chars().map(|c| if c.is_ascii() { c } else { '?' }).collect();
+    let mut field = ascii.into_bytes();
+    field.truncate(width);
+    field.resize(width, 0);
+    field
+}
+
+/// `LIST`/`INFO` text chunk; values are NUL-terminated
+fn info_entry(id: &[u8; 4], value: &str) -> Chunk {
+    let mut body = value.as_bytes().to_vec();
+    body.push(0);
+    Chunk::new(id, body)
+}
+
+fn info_chunk(record: &SampleRecord) -> Chunk {
+    let title = Path::new(&record.filename).file_stem()
+        .map_or(record.filename.clone(), |stem| stem.to_string_lossy().to_string());
+    let mut entries = vec![
+        info_entry(b"INAM", &title),
+        info_entry(b"IART", &record.provider_name),
+        info_entry(b"IPRD", &record.pack_name),
+        info_entry(b"IGNR", &record.mapped_category),
+        info_entry(b"ICMT", &description(record)),
+        info_entry(b"ISFT", SOFTWARE),
+    ];
+    let tags = tag_list(record);
+    if !tags.is_empty() {
+        entries.push(info_entry(b"IKEY", &tags.join("; ")));
+    }
+    if let Some(date) = record.date_downloaded.get(..10) {
+        entries.push(info_entry(b"ICRD", date));
+    }
+    if let Some(url) = &record.splice_url {
+        entries.push(info_entry(b"ISRC", url));
+    }
+    entries.retain(|entry| entry.body.len() > 1);
+    Chunk::list(b"INFO", &entries)
+}
+
+/// Broadcast Wave `bext` chunk: description, originator and origination date
+fn bext_chunk(record: &SampleRecord) -> Chunk {
+    let mut body = Vec::with_capacity(BEXT_LEN);
+    body.extend(fixed_field(&description(record), 256));
+    body.extend(fixed_field(&record.provider_name, 32));
+    body.extend(fixed_field(&record.asset_uuid, 32));
+    // `purchased_at` is an ISO 8601 timestamp; the date and time are stored separately
+    let date = record.date_downloaded.get(..10).unwrap_or_default();
+    let time = record.date_downloaded.get(11..19).unwrap_or_default();
+    body.extend(fixed_field(date, 10));
+    body.extend(fixed_field(time, 8));
+    body.extend(0u64.to_le_bytes()); // time reference
+    body.extend(1u16.to_le_bytes()); // version
+    body.resize(BEXT_LEN, 0); // UMID and reserved space
+    Chunk::new(b"bext", body)
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// iXML document with the pack as the project, Splice's fields as USER lines and the
+/// ASWG block that sample managers read tempo and key from
+fn ixml_chunk(record: &SampleRecord) -> Chunk {
+    let mut user = vec![
+        format!("PACK={}", record.pack_name),
+        format!("PROVIDER={}", record.provider_name),
+        format!("CATEGORY={}", record.mapped_category),
+        format!("TYPE={}", record.sample_type),
+        format!("TAGS={}", tag_list(record).join(", ")),
+    ];
+    if let Some(bpm) = record.bpm {
+        user.push(format!("BPM={}", bpm));
+    }
+    if let Some(key) = key_name(record) {
+        user.push(format!("KEY={}", key));
+    }
+    if let Some(url) = &record.splice_url {
+        user.push(format!("URL={}", url));
+    }
+    
+    let mut aswg = vec![
+        format!("<library>{}</library>", xml_escape(&record.pack_name)),
+        format!("<libraryManufacturer>{}</libraryManufacturer>", xml_escape(&record.provider_name)),
+        format!("<category>{}</category>", xml_escape(&record.mapped_category)),
+    ];
+    if let Some(bpm) = record.bpm {
+        aswg.push(format!("<tempo>{}</tempo>", bpm));
+    }
+    if let Some(key) = key_name(record) {
+        aswg.push(format!("<inKey>{}</inKey>", xml_escape(&key)));
+    }
+    
+    let xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<BWFXML>\n\
+         <IXML_VERSION>2.10</IXML_VERSION>\n\
+         <PROJECT>{}</PROJECT>\n\
+         <NOTE>{}</NOTE>\n\
+         <USER>{}</USER>\n\
+         <ASWG>\n{}\n</ASWG>\n\
+         </BWFXML>\n",
+        xml_escape(&record.pack_name),
+        xml_escape(&description(record)),
+        xml_escape(&user.join("\n")),
+        aswg.join("\n"),
+    );
+    Chunk::new(b"iXML", xml.into_bytes())
+}
+
+fn is_loop(record: &SampleRecord) -> bool {
+    normalize_sample_type(&record.sample_type) == "loop"
+}
+
+/// MIDI note for the sample's key, in the octave starting at middle C
+fn midi_root_note(audio_key: &str) -> Option<u8> {
+    let key = audio_key.trim().to_lowercase();
+    NOTE_OFFSETS.iter()
+        .find(|(name, _)| *name == key)
+        .map(|(_, offset)| ROOT_OCTAVE + offset)
+}
+
+/// Whole beats in the sample at its tempo
+fn beat_count(info: &WavInfo, bpm: u32) -> u32 {
+    (info.duration_secs() * bpm as f64 / 60.0).round() as u32
+}
+
+/// ACID chunk: loops get their tempo and beat count so DAWs stretch them to the
+/// project tempo; one-shots are flagged so they aren't. Loops without a BPM get none.
+fn acid_chunk(record: &SampleRecord, info: &WavInfo) -> Option<Chunk> {
+    let root_note = record.audio_key.as_deref().and_then(midi_root_note);
+    let (mut flags, tempo, beats) = if is_loop(record) {
+        let bpm = record.bpm.filter(|&bpm| bpm > 0)?;
+        (ACID_STRETCH, bpm as f32, beat_count(info, bpm))
+    } else {
+        (ACID_ONE_SHOT, record.bpm.unwrap_or(0) as f32, 0)
+    };
+    if root_note.is_some() {
+        flags |= ACID_ROOT_NOTE_SET;
+    }
+    
+    let mut body = Vec::with_capacity(24);
+    body.extend(flags.to_le_bytes());
+    body.extend((root_note.unwrap_or(ROOT_OCTAVE) as u16).to_le_bytes());
+    body.extend(0x8000u16.to_le_bytes()); // unknown, always written as 0x8000
+    body.extend(0f32.to_le_bytes()); // unknown
+    body.extend(beats.to_le_bytes());
+    body.extend(4u16.to_le_bytes()); // meter denominator
+    body.extend(4u16.to_le_bytes()); // meter numerator
+    body.extend(tempo.to_le_bytes());
+    Some(Chunk::new(b"acid", body))
+}
+
+/// Sampler chunk: the MIDI root note from the key and, for loops, a forward loop over
+/// the whole file. Samples with neither get none.
+fn smpl_chunk(record: &SampleRecord, info: &WavInfo) -> Option<Chunk> {
+    let root_note = record.audio_key.as_deref().and_then(midi_root_note);
+    let looped = is_loop(record);
+    if root_note.is_none() && !looped {
+        return None;
+    }
+    
+    let mut body = Vec::with_capacity(60);
+    body.extend(0u32.to_le_bytes()); // manufacturer
+    body.extend(0u32.to_le_bytes()); // product
+    body.extend((1_000_000_000 / info.sample_rate).to_le_bytes()); // sample period in ns
+    body.extend((root_note.unwrap_or(ROOT_OCTAVE) as u32).to_le_bytes());
+    body.extend(0u32.to_le_bytes()); // pitch fraction
+    body.extend(0u32.to_le_bytes()); // SMPTE format
+    body.extend(0u32.to_le_bytes()); // SMPTE offset
+    body.extend((looped as u32).to_le_bytes());
+    body.extend(0u32.to_le_bytes()); // sampler data
+    if looped {
+        let last_frame = u32::try_from(info.frames - 1).unwrap_or(u32::MAX);
+        body.extend(0u32.to_le_bytes()); // cue point ID
+        body.extend(0u32.to_le_bytes()); // forward loop
+        body.extend(0u32.to_le_bytes()); // start
+        body.extend(last_frame.to_le_bytes()); // end, inclusive
+        body.extend(0u32.to_le_bytes()); // fraction
+        body.extend(0u32.to_le_bytes()); // play forever
+    }
+    Some(Chunk::new(b"smpl", body))
+}
+
+/// Write the sample's Splice metadata into its WAV file, replacing chunks written
+/// earlier: `LIST`/`INFO`, `bext` and `iXML` with `options.metadata`, `acid` and `smpl`
+/// with `options.loop_chunks`. The audio data is left untouched.
+pub fn embed_metadata(path: &Path, record: &SampleRecord, options: EmbedOptions) -> Result<()> {
+    let mut chunks = Vec::new();
+    if options.metadata {
+        chunks.extend([info_chunk(record), bext_chunk(record), ixml_chunk(record)]);
+    }
+    if options.loop_chunks {
+        let info = wav::read_info(path)?;
+        chunks.extend(acid_chunk(record, &info));
+        chunks.extend(smpl_chunk(record, &info));
+    }
+    if chunks.is_empty() {
+        return Ok(());
+    }
+    wav::write_chunks(path, &chunks)
+}
+
+/// Result of `retag`
+#[derive(Debug, Default, Serialize)]
+pub struct RetagSummary {
+    pub tagged: usize,
+    /// Samples in formats other than WAV
+    pub skipped: usize,
+    /// Records whose library file no longer exists
+    pub missing: Vec<PathBuf>,
+    #[serde(serialize_with = "output::failures")]
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+impl output::PlainText for RetagSummary {}
+
+/// Embed metadata into every WAV in the library
+pub fn retag_library(database_path: &Path, options: EmbedOptions) -> Result<RetagSummary> {
+    let mut summary = RetagSummary::default();
+    
+    for record in db::get_all_samples(database_path)? {
+        let path = PathBuf::from(&record.file_path);
+        if AudioFormat::from_path(&path) != Some(AudioFormat::Wav) {
+            summary.skipped += 1;
+            continue;
+        }
+        if !path.is_file() {
+            summary.missing.push(path);
+            continue;
+        }
+        
+        match embed_metadata(&path, &record, options) {
+            Ok(()) => summary.tagged += 1,
+            Err(e) => {
+                eprintln!("🚨 Failed to tag {:?}: {}", path, e);
+                summary.failed.push((path, e.to_string()));
+            }
+        }
+    }
+    
+    Ok(summary)
+}
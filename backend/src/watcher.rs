@@ -1,25 +1,296 @@
 use anyhow::Result;
-use notify::{Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use sha2::{Sha256, Digest};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::mpsc;
-use tokio::time::{sleep, timeout};
+use tokio::sync::{mpsc, oneshot, watch};
+use tokio::time::{sleep, sleep_until, timeout};
 
-use crate::metadata::SampleMetadata;
-use crate::db::{SampleRecord, insert_sample, get_sample_by_hash};
+use crate::config::{Config, WatcherConfig};
+use crate::debounce::{self, Debouncer};
+use crate::integrity::{digest_file, sha256_file, FileDigest, MismatchAction};
+use crate::metadata::{AudioFormat, SampleMetadata};
+use crate::layout::{self, CollisionPolicy, PathTemplate};
+use crate::rules::CategoryRules;
+use crate::output::{self, PlainText};
+use crate::quarantine::{self, QuarantineEntry, QuarantineReason};
+use crate::analysis;
+use crate::tagging::{self, EmbedOptions};
+use crate::wav;
+use crate::db::{self, SampleRecord, PendingKind, PendingRecord, JournalEntry, JournalOperation, JournalState, insert_sample, get_sample_by_hash};
+
+/// How often a new audio file checks whether its JSON metadata has arrived
+const JSON_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Result of pushing a single audio/JSON pair through the import pipeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PairOutcome {
+    /// Sample was moved into the library and recorded in the database
+    Imported,
+    /// Sample was already known but its library file was missing, so it was restored
+    Restored,
+    /// Sample already exists in the library; the downloaded copy was removed
+    Duplicate,
+    /// Another file occupies the library path and the collision policy left the download in place
+    Skipped,
+    /// The audio file didn't match its metadata and was moved to the quarantine directory
+    Quarantined,
+}
+
+/// An audio file that failed verification or can't be read, left to be retried. Kept
+/// as the error so a pair that runs out of retries is quarantined for that reason.
+#[derive(Debug, thiserror::Error)]
+#[error("Audio file failed its checks ({problem}); it may still be downloading")]
+struct BadAudio {
+    reason: QuarantineReason,
+    problem: String,
+}
+
+/// Summary of a reconciliation scan over the watch directory
+#[derive(Debug, Default, Serialize)]
+pub struct ScanSummary {
+    pub imported: usize,
+    pub restored: usize,
+    pub duplicates: usize,
+    pub skipped: usize,
+    pub quarantined: usize,
+    pub orphaned_audio: Vec<PathBuf>,
+    pub orphaned_jsons: Vec<PathBuf>,
+    #[serde(serialize_with = "output::failures")]
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+impl ScanSummary {
+    fn record(&mut self, outcome: PairOutcome) {
+        match outcome {
+            PairOutcome::Imported => self.imported += 1,
+            PairOutcome::Restored => self.restored += 1,
+            PairOutcome::Duplicate => self.duplicates += 1,
+            PairOutcome::Skipped => self.skipped += 1,
+            PairOutcome::Quarantined => self.quarantined += 1,
+        }
+    }
+    
+    pub fn print(&self) {
+        println!("📊 Scan summary:");
+        println!("   ✅ Imported: {}", self.imported);
+        if self.restored > 0 {
+            println!("   🔄 Restored: {}", self.restored);
+        }
+        println!("   ⏭️  Skipped as duplicate: {}", self.duplicates);
+        if self.skipped > 0 {
+            println!("   🚧 Skipped, library path taken: {}", self.skipped);
+        }
+        if self.quarantined > 0 {
+            println!("   🚫 Quarantined: {}", self.quarantined);
+        }
+        println!("   🤔 Orphaned audio files: {}", self.orphaned_audio.len());
+        for path in &self.orphaned_audio {
+            println!("      {:?}", path);
+        }
+        println!("   📄 Orphaned JSON files: {}", self.orphaned_jsons.len());
+        for path in &self.orphaned_jsons {
+            println!("      {:?}", path);
+        }
+        if !self.failed.is_empty() {
+            println!("   🚨 Failed: {}", self.failed.len());
+            for (path, error) in &self.failed {
+                println!("      {:?}: {}", path, error);
+            }
+        }
+    }
+}
+
+impl PlainText for ScanSummary {}
+
+/// Summary of a pass over the persisted pending-pairs queue
+#[derive(Debug, Default, Serialize)]
+pub struct PendingSummary {
+    pub matched: ScanSummary,
+    pub waiting: usize,
+    pub stale: Vec<PathBuf>,
+}
+
+impl PlainText for PendingSummary {}
+
+/// Interrupted imports resolved from the journal on startup
+#[derive(Debug, Default, Serialize)]
+pub struct RecoverySummary {
+    /// Library files whose import was finished
+    pub completed: Vec<PathBuf>,
+    /// Downloads put back in the watch directory to be imported again
+    pub rolled_back: Vec<PathBuf>,
+    #[serde(serialize_with = "output::failures")]
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+impl RecoverySummary {
+    pub fn is_empty(&self) -> bool {
+        self.completed.is_empty() && self.rolled_back.is_empty() && self.failed.is_empty()
+    }
+    
+    pub fn print(&self) {
+        println!("🧾 Import journal recovery:");
+        println!("   ✅ Completed: {}", self.completed.len());
+        for path in &self.completed {
+            println!("      {:?}", path);
+        }
+        println!("   ↩️  Rolled back: {}", self.rolled_back.len());
+        for path in &self.rolled_back {
+            println!("      {:?}", path);
+        }
+        if !self.failed.is_empty() {
+            println!("   🚨 Failed: {}", self.failed.len());
+            for (path, error) in &self.failed {
+                println!("      {:?}: {}", path, error);
+            }
+        }
+    }
+}
+
+/// How a single journal entry was resolved
+enum Recovery {
+    Completed,
+    RolledBack,
+}
+
+/// Result of a rescan requested over the control socket
+#[derive(Debug, Default, Serialize)]
+pub struct RescanSummary {
+    pub scan: ScanSummary,
+    pub pending: PendingSummary,
+}
+
+/// Requests the control socket passes on to the running watcher
+#[derive(Debug)]
+pub enum WatcherCommand {
+    /// Scan the watch directory and retry the pending queue, replying with the result if asked
+    Rescan(Option<oneshot::Sender<std::result::Result<RescanSummary, String>>>),
+    /// Stop watching once the pair in progress is finished; queued events are left to the next startup scan
+    Shutdown,
+}
+
+/// Running totals kept by the watcher and reported over the control socket
+#[derive(Debug, Default)]
+pub struct WatcherStats {
+    /// While set, file events are ignored; resuming triggers a rescan to catch up
+    pub paused: AtomicBool,
+    /// Set when the daemon is shutting down, cutting waits and retries short
+    pub stopping: AtomicBool,
+    events: AtomicU64,
+    imported: AtomicU64,
+    restored: AtomicU64,
+    duplicates: AtomicU64,
+    skipped: AtomicU64,
+    quarantined: AtomicU64,
+    queued: AtomicU64,
+    errors: AtomicU64,
+}
+
+/// Point-in-time copy of `WatcherStats`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Counters {
+    /// File system events received
+    pub events: u64,
+    pub imported: u64,
+    pub restored: u64,
+    pub duplicates: u64,
+    /// Pairs left in the watch directory because their library path was taken
+    pub skipped: u64,
+    /// Pairs moved to quarantine after failing verification
+    pub quarantined: u64,
+    /// Files added to the pending queue to wait for their partner
+    pub queued: u64,
+    /// Events that still failed after every retry
+    pub errors: u64,
+}
+
+impl WatcherStats {
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+    
+    pub fn is_stopping(&self) -> bool {
+        self.stopping.load(Ordering::Relaxed)
+    }
+    
+    fn record(&self, outcome: PairOutcome) {
+        let counter = match outcome {
+            PairOutcome::Imported => &self.imported,
+            PairOutcome::Restored => &self.restored,
+            PairOutcome::Duplicate => &self.duplicates,
+            PairOutcome::Skipped => &self.skipped,
+            PairOutcome::Quarantined => &self.quarantined,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+    
+    pub fn snapshot(&self) -> Counters {
+        Counters {
+            events: self.events.load(Ordering::Relaxed),
+            imported: self.imported.load(Ordering::Relaxed),
+            restored: self.restored.load(Ordering::Relaxed),
+            duplicates: self.duplicates.load(Ordering::Relaxed),
+            skipped: self.skipped.load(Ordering::Relaxed),
+            quarantined: self.quarantined.load(Ordering::Relaxed),
+            queued: self.queued.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl PendingSummary {
+    pub fn print(&self) {
+        println!("📊 Pending queue summary:");
+        println!("   ✅ Matched and imported: {}", self.matched.imported + self.matched.restored);
+        println!("   ⏭️  Matched as duplicate: {}", self.matched.duplicates);
+        if self.matched.skipped > 0 {
+            println!("   🚧 Skipped, library path taken: {}", self.matched.skipped);
+        }
+        if self.matched.quarantined > 0 {
+            println!("   🚫 Quarantined: {}", self.matched.quarantined);
+        }
+        println!("   ⏳ Still waiting for partner: {}", self.waiting);
+        println!("   🧹 Dropped (file no longer exists): {}", self.stale.len());
+        for path in &self.stale {
+            println!("      {:?}", path);
+        }
+        if !self.matched.failed.is_empty() {
+            println!("   🚨 Failed: {}", self.matched.failed.len());
+            for (path, error) in &self.matched.failed {
+                println!("      {:?}: {}", path, error);
+            }
+        }
+    }
+}
 
 pub struct FileWatcher {
     watch_dir: PathBuf,
     library_dir: PathBuf,
     database_path: PathBuf,
-    retry_attempts: u32,
+    quarantine_dir: PathBuf,
+    rules: CategoryRules,
+    layout: PathTemplate,
+    collision_policy: CollisionPolicy,
+    min_detection_confidence: f64,
+    settings: WatcherConfig,
+    debouncer: Debouncer,
+    stats: Arc<WatcherStats>,
     error_count: u32,
 }
 
 impl FileWatcher {
-    pub fn new(watch_dir: PathBuf, library_dir: PathBuf, database_path: PathBuf) -> Result<Self> {
+    pub fn new(config: &Config) -> Result<Self> {
+        let watch_dir = config.paths.watch_dir.clone();
+        let library_dir = config.paths.library_dir.clone();
+        let database_path = config.paths.database.clone();
+        
         // Create directories if they don't exist
         Self::ensure_directory(&watch_dir)?;
         Self::ensure_directory(&library_dir)?;
@@ -28,27 +299,96 @@ impl FileWatcher {
             Self::ensure_directory(parent)?;
         }
         
+        let rules = config.category_rules()?;
+        let layout = config.layout()?;
+        say!("📐 Category rules: {}", rules.source_description());
+        say!("🗂️  Library layout: {}", layout);
+        
         Ok(Self {
             watch_dir,
             library_dir,
             database_path,
-            retry_attempts: 3,
+            quarantine_dir: config.quarantine_dir(),
+            rules,
+            layout,
+            collision_policy: config.library.on_collision,
+            min_detection_confidence: config.library.min_detection_confidence,
+            settings: config.watcher.clone(),
+            debouncer: Debouncer::new(
+                Duration::from_millis(config.watcher.debounce_ms),
+                Duration::from_secs(config.watcher.settle_timeout_secs),
+            ),
+            stats: Arc::default(),
             error_count: 0,
         })
     }
     
+    /// Switch to a reloaded configuration without dropping the file watcher. Everything
+    /// that can fail is prepared first, so an error leaves the previous settings in place.
+    async fn reload(&mut self, config: &Config, watcher: &mut RecommendedWatcher) -> Result<()> {
+        let rules = config.category_rules()?;
+        let layout = config.layout()?;
+        
+        Self::ensure_directory(&config.paths.library_dir)?;
+        if config.paths.database != self.database_path {
+            db::init_database(&config.paths.database)?;
+        }
+        
+        let watch_dir_changed = config.paths.watch_dir != self.watch_dir;
+        if watch_dir_changed {
+            Self::ensure_directory(&config.paths.watch_dir)?;
+            watcher.watch(&config.paths.watch_dir, RecursiveMode::Recursive)
+                .map_err(|e| anyhow::anyhow!("Failed to watch directory {:?}: {}", config.paths.watch_dir, e))?;
+            if let Err(e) = watcher.unwatch(&self.watch_dir) {
+                eprintln!("⚠️  Failed to stop watching {:?}: {}", self.watch_dir, e);
+            }
+            say!("👀 Now watching directory: {:?}", config.paths.watch_dir);
+        }
+        
+        self.watch_dir = config.paths.watch_dir.clone();
+        self.library_dir = config.paths.library_dir.clone();
+        self.database_path = config.paths.database.clone();
+        self.quarantine_dir = config.quarantine_dir();
+        self.rules = rules;
+        self.layout = layout;
+        self.collision_policy = config.library.on_collision;
+        self.min_detection_confidence = config.library.min_detection_confidence;
+        self.settings = config.watcher.clone();
+        self.debouncer.set_timing(
+            Duration::from_millis(self.settings.debounce_ms),
+            Duration::from_secs(self.settings.settle_timeout_secs),
+        );
+        
+        say!("🔄 Configuration reloaded from {}", config.source_description());
+        say!("📐 Category rules: {}", self.rules.source_description());
+        say!("🗂️  Library layout: {}", self.layout);
+        
+        if watch_dir_changed {
+            match self.scan_watch_directory().await {
+                Ok(summary) => summary.print(),
+                Err(e) => eprintln!("⚠️  Scan of new watch directory failed: {}", e),
+            }
+        }
+        
+        Ok(())
+    }
+    
     fn ensure_directory(path: &Path) -> Result<()> {
         if !path.exists() {
             fs::create_dir_all(path)
                 .map_err(|e| anyhow::anyhow!("Failed to create directory {:?}: {}", path, e))?;
-            println!("📁 Created directory: {:?}", path);
+            say!("📁 Created directory: {:?}", path);
         } else if !path.is_dir() {
             return Err(anyhow::anyhow!("{:?} exists but is not a directory", path));
         }
         Ok(())
     }
     
-    pub async fn start_watching(&mut self) -> Result<()> {
+    pub async fn start_watching(
+        &mut self,
+        mut updates: watch::Receiver<Config>,
+        mut commands: mpsc::Receiver<WatcherCommand>,
+    ) -> Result<()> {
         let (tx, mut rx) = mpsc::channel(100);
         
         // Create the file system watcher with error handling
@@ -66,35 +406,141 @@ impl FileWatcher {
                     }
                 }
             },
-            Config::default(),
+            notify::Config::default(),
         ).map_err(|e| anyhow::anyhow!("Failed to create file watcher: {}", e))?;
         
         // Start watching the directory
         watcher.watch(&self.watch_dir, RecursiveMode::Recursive)
             .map_err(|e| anyhow::anyhow!("Failed to watch directory {:?}: {}", self.watch_dir, e))?;
         
-        println!("👀 Started watching directory: {:?}", self.watch_dir);
+        say!("👀 Started watching directory: {:?}", self.watch_dir);
         
-        // Process events with error handling and recovery
-        while let Some(event) = rx.recv().await {
-            if let Err(e) = self.handle_event_with_retry(event).await {
-                self.error_count += 1;
-                eprintln!("🚨 Error handling event (total errors: {}): {}", self.error_count, e);
-                
-                // If too many errors, pause briefly to avoid rapid failures
-                if self.error_count % 10 == 0 {
-                    println!("⏸️  Too many errors, pausing for 30 seconds...");
-                    sleep(Duration::from_secs(30)).await;
+        // Settle imports a crash interrupted before scanning, so their files are where they belong
+        match self.recover_interrupted_imports() {
+            Ok(summary) if !summary.is_empty() => summary.print(),
+            Ok(_) => {}
+            Err(e) => eprintln!("⚠️  Failed to read the import journal: {}", e),
+        }
+        
+        // Pick up anything that arrived while the daemon was not running. The
+        // watcher is already registered, so files landing during the scan are
+        // still delivered as events afterwards.
+        match self.scan_watch_directory().await {
+            Ok(summary) => summary.print(),
+            Err(e) => eprintln!("⚠️  Startup scan failed: {}", e),
+        }
+        
+        // Re-match anything queued by a previous run and drop entries whose files are gone
+        match self.resolve_pending().await {
+            Ok(summary) => summary.print(),
+            Err(e) => eprintln!("⚠️  Failed to resolve pending pairs: {}", e),
+        }
+        
+        // Collect events until their files settle, then process them with error handling
+        // and recovery, picking up reloaded settings and control requests in between
+        let mut updates_open = true;
+        let mut commands_open = true;
+        loop {
+            let next_due = self.debouncer.next_due();
+            tokio::select! {
+                event = rx.recv() => {
+                    let Some(event) = event else { break };
+                    if self.stats.is_stopping() {
+                        break;
+                    }
+                    self.stats.events.fetch_add(1, Ordering::Relaxed);
+                    if self.stats.is_paused() {
+                        continue;
+                    }
+                    self.queue_event(event);
+                }
+                _ = sleep_until(next_due.unwrap_or_else(std::time::Instant::now).into()), if next_due.is_some() => {
+                    for paths in self.debouncer.take_ready() {
+                        // Whatever is left is picked up by the next startup scan
+                        if self.stats.is_stopping() {
+                            break;
+                        }
+                        self.handle_settled_pair(&paths).await;
+                    }
+                }
+                changed = updates.changed(), if updates_open => {
+                    if changed.is_err() {
+                        // Nobody can send new settings any more
+                        updates_open = false;
+                        continue;
+                    }
+                    let config = updates.borrow_and_update().clone();
+                    if let Err(e) = self.reload(&config, &mut watcher).await {
+                        eprintln!("🚨 Failed to apply reloaded configuration, keeping the previous one: {}", e);
+                    }
+                }
+                command = commands.recv(), if commands_open => {
+                    match command {
+                        Some(WatcherCommand::Rescan(reply)) => {
+                            let result = self.rescan().await.map_err(|e| e.to_string());
+                            if let Err(e) = &result {
+                                eprintln!("⚠️  Rescan failed: {}", e);
+                            }
+                            if let Some(reply) = reply {
+                                let _ = reply.send(result);
+                            }
+                        }
+                        Some(WatcherCommand::Shutdown) => break,
+                        None => commands_open = false,
+                    }
                 }
             }
         }
         
+        // Dropping the watcher stops new events from being delivered
+        drop(watcher);
+        say!("🛑 Stopped watching directory: {:?}", self.watch_dir);
+        
         Ok(())
     }
     
-    async fn handle_event_with_retry(&mut self, event: Event) -> Result<()> {
-        for attempt in 1..=self.retry_attempts {
-            match self.handle_event(event.clone()).await {
+    /// Sweep the watch directory and the pending queue, as on startup
+    async fn rescan(&self) -> Result<RescanSummary> {
+        let scan = self.scan_watch_directory().await?;
+        scan.print();
+        let pending = self.resolve_pending().await?;
+        pending.print();
+        Ok(RescanSummary { scan, pending })
+    }
+    
+    /// Persist a file in the pending queue until its partner arrives
+    fn queue_pending(&self, path: &Path, kind: PendingKind) -> Result<()> {
+        db::add_pending_file(&self.database_path, path, kind)?;
+        self.stats.queued.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+    
+    /// Process a pair the debouncer released, counting and quarantining it if it keeps failing
+    async fn handle_settled_pair(&mut self, paths: &[PathBuf]) {
+        let Err(e) = self.handle_pair_with_retry(paths).await else {
+            return;
+        };
+        
+        self.stats.errors.fetch_add(1, Ordering::Relaxed);
+        self.error_count += 1;
+        eprintln!("🚨 Error handling event (total errors: {}): {}", self.error_count, e);
+        
+        // A shutdown cut the retries short, so the next startup gets another go
+        if self.settings.quarantine_failures && !self.stats.is_stopping() {
+            self.quarantine_failed_event(paths, &e);
+        }
+        
+        // If too many errors, pause briefly to avoid rapid failures
+        if self.error_count.is_multiple_of(10) && !self.stats.is_stopping() {
+            say!("⏸️  Too many errors, pausing for {} seconds...", self.settings.error_pause_secs);
+            sleep(Duration::from_secs(self.settings.error_pause_secs)).await;
+        }
+    }
+    
+    async fn handle_pair_with_retry(&mut self, paths: &[PathBuf]) -> Result<()> {
+        let attempts = self.settings.retry_attempts;
+        for attempt in 1..=attempts {
+            match self.handle_pair(paths).await {
                 Ok(()) => {
                     // Reset error count on success
                     if self.error_count > 0 {
@@ -103,10 +549,10 @@ impl FileWatcher {
                     return Ok(());
                 }
                 Err(e) => {
-                    eprintln!("🔄 Attempt {}/{} failed: {}", attempt, self.retry_attempts, e);
-                    if attempt < self.retry_attempts {
+                    eprintln!("🔄 Attempt {}/{} failed: {}", attempt, attempts, e);
+                    if attempt < attempts && !self.stats.is_stopping() {
                         // Exponential backoff
-                        let delay = Duration::from_millis(1000 * (2_u64.pow(attempt - 1)));
+                        let delay = Duration::from_millis(self.settings.retry_delay_ms.saturating_mul(2_u64.saturating_pow(attempt - 1)));
                         sleep(delay).await;
                     } else {
                         return Err(e);
@@ -117,57 +563,88 @@ impl FileWatcher {
         Ok(())
     }
     
-    async fn handle_event(&self, event: Event) -> Result<()> {
+    /// Hand an event's paths to the debouncer. Renames are followed, so a browser's
+    /// temp file becoming the finished download counts as that download arriving.
+    fn queue_event(&mut self, event: Event) {
         match event.kind {
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+                if let [from, to] = event.paths.as_slice() {
+                    self.debouncer.forget(from);
+                    self.queue_path(to.clone());
+                }
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::From)) | EventKind::Remove(_) => {
+                for path in &event.paths {
+                    self.debouncer.forget(path);
+                }
+            }
             EventKind::Create(_) | EventKind::Modify(_) => {
                 for path in event.paths {
-                    // Early validation - skip if file doesn't exist
-                    if !path.exists() {
-                        continue;
-                    }
-                    
-                    if let Some(extension) = path.extension() {
-                        match extension.to_str() {
-                            Some("wav") => {
-                                println!("🎵 New WAV file detected: {:?}", path);
-                                self.process_wav_file(&path).await?;
-                            }
-                            Some("json") => {
-                                println!("📄 New JSON metadata file detected: {:?}", path);
-                                self.process_json_file(&path).await?;
-                            }
-                            _ => {}
-                        }
-                    }
+                    self.queue_path(path);
                 }
             }
             _ => {}
         }
+    }
+    
+    fn queue_path(&mut self, path: PathBuf) {
+        // Files just moved into the library or a quarantine under the watch directory stay there
+        if path.starts_with(&self.library_dir) || path.starts_with(&self.quarantine_dir) {
+            return;
+        }
+        
+        // Partial downloads only matter once they are renamed to their final name
+        if debounce::is_browser_temp_file(&path) {
+            return;
+        }
+        
+        if self.settings.is_audio_file(&path) || is_json_file(&path) {
+            self.debouncer.touch(path);
+        }
+    }
+    
+    /// Process the settled halves of a pair. Whichever half comes first imports the
+    /// pair, so the other one is gone by the time its turn comes.
+    async fn handle_pair(&self, paths: &[PathBuf]) -> Result<()> {
+        for path in paths {
+            if !path.exists() {
+                continue;
+            }
+            
+            if self.settings.is_audio_file(path) {
+                say!("🎵 New audio file detected: {:?}", path);
+                self.process_audio_file(path).await?;
+            } else if is_json_file(path) {
+                say!("📄 New JSON metadata file detected: {:?}", path);
+                self.process_json_file(path).await?;
+            }
+        }
         
         Ok(())
     }
     
-    async fn process_wav_file(&self, wav_path: &Path) -> Result<()> {
-        self.validate_file(wav_path, "WAV")?;
+    async fn process_audio_file(&self, audio_path: &Path) -> Result<()> {
+        self.validate_file(audio_path, "Audio")?;
         
-        println!("🔍 Processing WAV file: {:?}", wav_path);
+        say!("🔍 Processing audio file: {:?}", audio_path);
         
         // Wait for corresponding JSON metadata file with timeout
-        let json_path = wav_path.with_extension("json");
+        let json_path = audio_path.with_extension("json");
         
-        // Try to find JSON file with timeout and retries
-        let mut attempts = 0;
-        while attempts < 10 && !json_path.exists() {
-            sleep(Duration::from_millis(500)).await;
-            attempts += 1;
+        // Try to find JSON file, polling until the wait window closes
+        let mut waited = Duration::ZERO;
+        let window = Duration::from_millis(self.settings.json_wait_ms);
+        while waited < window && !json_path.exists() && !self.stats.is_stopping() {
+            sleep(JSON_POLL_INTERVAL).await;
+            waited += JSON_POLL_INTERVAL;
         }
         
         if json_path.exists() {
-            println!("✅ Found corresponding metadata file: {:?}", json_path);
-            self.process_sample_pair(wav_path, &json_path).await?;
+            say!("✅ Found corresponding metadata file: {:?}", json_path);
+            self.process_sample_pair(audio_path, &json_path).await?;
         } else {
-            println!("⚠️  No metadata file found for: {:?}", wav_path);
-            self.handle_orphaned_wav(wav_path).await?;
+            say!("⚠️  No metadata file found for: {:?}", audio_path);
+            self.handle_orphaned_audio(audio_path).await?;
         }
         
         Ok(())
@@ -181,16 +658,153 @@ impl FileWatcher {
         
         self.validate_file(json_path, "JSON")?;
         
-        println!("🔍 Processing JSON file: {:?}", json_path);
+        say!("🔍 Processing JSON file: {:?}", json_path);
         
-        // Check if there's a corresponding WAV file
-        let wav_path = json_path.with_extension("wav");
-        if wav_path.exists() {
-            println!("✅ Found corresponding WAV file: {:?}", wav_path);
-            self.process_sample_pair(&wav_path, json_path).await?;
+        // Check if there's a corresponding audio file, whatever its format
+        if let Some(audio_path) = self.settings.audio_partner(json_path) {
+            say!("✅ Found corresponding audio file: {:?}", audio_path);
+            self.process_sample_pair(&audio_path, json_path).await?;
         } else {
-            println!("⏳ JSON metadata file arrived before WAV: {:?}", json_path);
-            // The WAV processing will handle this when it arrives
+            say!("⏳ JSON metadata file arrived before its audio: {:?}", json_path);
+            // The audio processing will pick this up when it arrives, even after a restart
+            self.queue_pending(json_path, PendingKind::Json)?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Sweep the watch directory for existing audio/JSON pairs and import them
+    pub async fn scan_watch_directory(&self) -> Result<ScanSummary> {
+        say!("🔎 Scanning for unprocessed samples in: {:?}", self.watch_dir);
+        
+        let mut audio_files = Vec::new();
+        let mut json_files = Vec::new();
+        self.collect_scan_candidates(&self.watch_dir, &mut audio_files, &mut json_files)?;
+        audio_files.sort();
+        json_files.sort();
+        
+        let mut summary = ScanSummary::default();
+        
+        for audio_path in &audio_files {
+            // Whatever is left is picked up by the next startup scan
+            if self.stats.is_stopping() {
+                break;
+            }
+            
+            let json_path = audio_path.with_extension("json");
+            if !json_path.exists() {
+                self.queue_pending(audio_path, PendingKind::Audio)?;
+                summary.orphaned_audio.push(audio_path.clone());
+                continue;
+            }
+            
+            match self.process_sample_pair(audio_path, &json_path).await {
+                Ok(outcome) => summary.record(outcome),
+                Err(e) => {
+                    eprintln!("🚨 Failed to process {:?}: {}", audio_path, e);
+                    summary.failed.push((audio_path.clone(), e.to_string()));
+                }
+            }
+        }
+        
+        for json_path in &json_files {
+            // Processed pairs have had their JSON removed; failed pairs still have their audio
+            if !json_path.exists() || self.settings.audio_partner(json_path).is_some() {
+                continue;
+            }
+            
+            // Only report files that actually look like Splice metadata
+            if SampleMetadata::from_file(json_path).is_ok() {
+                self.queue_pending(json_path, PendingKind::Json)?;
+                summary.orphaned_jsons.push(json_path.clone());
+            }
+        }
+        
+        Ok(summary)
+    }
+    
+    /// Walk the persisted pending queue and import every entry whose partner has appeared
+    pub async fn resolve_pending(&self) -> Result<PendingSummary> {
+        let mut summary = PendingSummary::default();
+        
+        for record in db::get_pending_files(&self.database_path)? {
+            if self.stats.is_stopping() {
+                break;
+            }
+            
+            let path = PathBuf::from(&record.file_path);
+            
+            if !path.exists() {
+                db::remove_pending_file(&self.database_path, &path)?;
+                summary.stale.push(path);
+                continue;
+            }
+            
+            let partner = record.file_kind.partner_path(&path, &self.settings);
+            if !partner.exists() {
+                summary.waiting += 1;
+                continue;
+            }
+            
+            self.process_pending_record(&record, &partner, &mut summary.matched).await;
+        }
+        
+        Ok(summary)
+    }
+    
+    /// Pair a single pending entry with an explicitly chosen partner file
+    pub async fn resolve_pending_with(&self, record: &PendingRecord, partner: &Path) -> ScanSummary {
+        let mut summary = ScanSummary::default();
+        self.process_pending_record(record, partner, &mut summary).await;
+        summary
+    }
+    
+    async fn process_pending_record(&self, record: &PendingRecord, partner: &Path, summary: &mut ScanSummary) {
+        let path = PathBuf::from(&record.file_path);
+        let (audio_path, json_path) = match record.file_kind {
+            PendingKind::Audio => (path.as_path(), partner),
+            PendingKind::Json => (partner, path.as_path()),
+        };
+        
+        match self.process_sample_pair(audio_path, json_path).await {
+            Ok(outcome) => summary.record(outcome),
+            Err(e) => {
+                eprintln!("🚨 Failed to process pending pair {:?}: {}", path, e);
+                if let Err(db_error) = db::set_pending_error(&self.database_path, &path, &e.to_string()) {
+                    eprintln!("⚠️  Failed to record pending error: {}", db_error);
+                }
+                summary.failed.push((path, e.to_string()));
+            }
+        }
+    }
+    
+    fn collect_scan_candidates(&self, dir: &Path, audio_files: &mut Vec<PathBuf>, json_files: &mut Vec<PathBuf>) -> Result<()> {
+        let entries = fs::read_dir(dir)
+            .map_err(|e| anyhow::anyhow!("Failed to read directory {:?}: {}", dir, e))?;
+        
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let file_type = match entry.file_type() {
+                Ok(file_type) => file_type,
+                Err(_) => continue,
+            };
+            
+            if file_type.is_dir() {
+                // Never re-import from the library or quarantine if they live under the watch directory
+                if path.starts_with(&self.library_dir) || path.starts_with(&self.quarantine_dir) {
+                    continue;
+                }
+                if let Err(e) = self.collect_scan_candidates(&path, audio_files, json_files) {
+                    eprintln!("⚠️  Skipping directory during scan: {}", e);
+                }
+            } else if file_type.is_file() {
+                // Downloads still in progress are imported once the browser renames them
+                if self.settings.is_audio_file(&path) && !debounce::is_still_downloading(&path) {
+                    audio_files.push(path);
+                } else if is_json_file(&path) {
+                    json_files.push(path);
+                }
+            }
         }
         
         Ok(())
@@ -215,164 +829,488 @@ impl FileWatcher {
         Ok(())
     }
     
-    pub async fn process_sample_pair_public(&self, wav_path: &Path, json_path: &Path) -> Result<()> {
-        self.process_sample_pair(wav_path, json_path).await
+    pub async fn process_sample_pair_public(&self, audio_path: &Path, json_path: &Path) -> Result<PairOutcome> {
+        self.process_sample_pair(audio_path, json_path).await
+    }
+    
+    async fn process_sample_pair(&self, audio_path: &Path, json_path: &Path) -> Result<PairOutcome> {
+        let outcome = self.import_sample_pair(audio_path, json_path).await?;
+        self.stats.record(outcome);
+        
+        // Both halves are accounted for, so neither is waiting on a partner any more
+        for path in [audio_path, json_path] {
+            if let Err(e) = db::remove_pending_file(&self.database_path, path) {
+                eprintln!("⚠️  Failed to clear pending entry for {:?}: {}", path, e);
+            }
+        }
+        
+        Ok(outcome)
     }
     
-    async fn process_sample_pair(&self, wav_path: &Path, json_path: &Path) -> Result<()> {
-        println!("🎵 Processing sample pair: {:?} + {:?}", wav_path.file_name(), json_path.file_name());
+    async fn import_sample_pair(&self, audio_path: &Path, json_path: &Path) -> Result<PairOutcome> {
+        say!("🎵 Processing sample pair: {:?} + {:?}", audio_path.file_name(), json_path.file_name());
         
         // Validate both files
-        self.validate_file(wav_path, "WAV")?;
+        self.validate_file(audio_path, "Audio")?;
+        let format = AudioFormat::from_path(audio_path)
+            .ok_or_else(|| anyhow::anyhow!("Unrecognized audio format: {:?}", audio_path))?;
         self.validate_file(json_path, "JSON")?;
         
         // Parse metadata with timeout
-        let metadata = timeout(Duration::from_secs(10), async {
+        let metadata = timeout(Duration::from_secs(self.settings.metadata_timeout_secs), async {
             SampleMetadata::from_file(json_path)
         }).await
         .map_err(|_| anyhow::anyhow!("Timeout parsing metadata from {:?}", json_path))?
         .map_err(|e| anyhow::anyhow!("Failed to parse metadata from {:?}: {}", json_path, e))?;
         
+        // Splice says which format it decoded to; a different file is still imported as what it is
+        if let Ok(expected) = metadata.sample.encoding.decoded_format.parse::<AudioFormat>() {
+            if expected != format {
+                eprintln!("⚠️  {:?} is {}, but its metadata describes {}", audio_path, format, expected);
+            }
+        }
+        
+        // A WAV whose header can't be read is as broken as a truncated download
+        let mut wav_info = None;
+        if format == AudioFormat::Wav {
+            match wav::read_info(audio_path) {
+                Ok(info) => wav_info = Some(info),
+                Err(e) => {
+                    let problem = format!("malformed WAV: {}", e);
+                    if let Some(outcome) = self.handle_bad_audio(audio_path, json_path, QuarantineReason::Malformed, &problem)? {
+                        return Ok(outcome);
+                    }
+                }
+            }
+        }
+        
         // Calculate file hash for deduplication
-        let file_hash = self.calculate_file_hash_with_retry(wav_path).await?;
-        println!("🔐 Calculated file hash: {}", file_hash);
+        let digest = self.calculate_file_hash_with_retry(audio_path).await?;
+        let file_hash = digest.sha256.clone();
+        say!("🔐 Calculated file hash: {}", file_hash);
+        
+        // Catch truncated or corrupted downloads before they reach the library
+        if let Err(problem) = digest.verify(&metadata) {
+            if let Some(outcome) = self.handle_bad_audio(audio_path, json_path, QuarantineReason::Verification, &problem)? {
+                return Ok(outcome);
+            }
+        } else {
+            say!("✅ Verified against the size and hash in the metadata");
+        }
+        
+        // Estimates stand in for a missing BPM or key when placing and categorizing the
+        // sample; the record keeps Splice's own values
+        let analysis = match (self.settings.analyze_on_import, wav_info) {
+            (true, Some(_)) => match analysis::analyze_file(audio_path) {
+                Ok(analysis) => {
+                    say!("🎼 Detected {}", analysis.describe());
+                    Some(analysis)
+                }
+                Err(e) => {
+                    eprintln!("⚠️  Failed to analyze {:?}: {}", audio_path, e);
+                    None
+                }
+            },
+            _ => None,
+        };
+        let mut placement = metadata.clone();
+        if let Some(analysis) = &analysis {
+            analysis.fill_missing(&mut placement.sample_meta_data, self.min_detection_confidence);
+        }
         
         // Check if this sample already exists in the database
         if let Ok(Some(existing_record)) = get_sample_by_hash(&self.database_path, &file_hash) {
-            println!("🔍 Found existing database entry for this sample");
+            say!("🔍 Found existing database entry for this sample");
             
             // Verify if the physical file still exists
             let existing_path = std::path::Path::new(&existing_record.file_path);
             if existing_path.exists() && existing_path.is_file() {
-                println!("⚠️  Sample already exists in library (true duplicate detected)");
-                println!("   Existing file: {:?}", existing_path);
+                say!("⚠️  Sample already exists in library (true duplicate detected)");
+                say!("   Existing file: {:?}", existing_path);
                 
                 // Clean up duplicate files
-                self.cleanup_duplicate_files(wav_path, json_path).await?;
-                return Ok(());
+                self.cleanup_duplicate_files(audio_path, json_path).await?;
+                return Ok(PairOutcome::Duplicate);
             } else {
-                println!("🔄 Database entry exists but physical file is missing");
-                println!("   Missing file: {:?}", existing_path);
-                println!("   Updating database with new file location...");
+                say!("🔄 Database entry exists but physical file is missing");
+                say!("   Missing file: {:?}", existing_path);
+                say!("   Updating database with new file location...");
                 
                 // The file was deleted but database entry remains
                 // Process normally but update the existing record instead of creating new one
-                let target_path = metadata.get_library_path(&self.library_dir);
-                println!("📍 New target path: {:?}", target_path);
+                let Some(claim) = self.claim_target(self.library_path(&placement, audio_path), audio_path, &file_hash)? else {
+                    return Ok(PairOutcome::Skipped);
+                };
+                let target_path = claim.path.clone();
+                say!("📍 New target path: {:?}", target_path);
                 
                 // Create target directory
                 if let Some(parent) = target_path.parent() {
                     Self::ensure_directory(parent)?;
-                    println!("📁 Ensured directory: {:?}", parent);
+                    say!("📁 Ensured directory: {:?}", parent);
                 }
                 
-                // Move file to new location
-                self.move_file_safely(wav_path, &target_path).await?;
-                println!("✅ Moved WAV file to: {:?}", target_path);
+                // Move file to new location, journaling each step so a crash can be recovered
+                let journal_id = db::begin_import(&self.database_path, JournalOperation::Restore, audio_path, json_path, &target_path, &file_hash)?;
+                self.move_into_library(journal_id, audio_path, &claim, &file_hash)?;
+                db::set_import_state(&self.database_path, journal_id, JournalState::Moved)?;
+                say!("✅ Moved audio file to: {:?}", target_path);
                 
                 // Update the existing database record with new path
-                crate::db::update_file_path(&self.database_path, &file_hash, &target_path.to_string_lossy())?;
-                println!("✅ Updated database record with new file path");
+                if let Err(e) = crate::db::update_file_path(&self.database_path, &file_hash, &target_path.to_string_lossy()) {
+                    self.roll_back_move(journal_id, &target_path, audio_path);
+                    return Err(e);
+                }
+                db::set_import_state(&self.database_path, journal_id, JournalState::Recorded)?;
+                discard_replaced(&target_path);
+                say!("✅ Updated database record with new file path");
+                self.embed_metadata(&target_path, &existing_record);
                 
                 // Clean up the JSON file
                 self.cleanup_metadata_file(json_path).await?;
+                self.finish_journal_entry(journal_id);
                 
-                println!("🎉 Sample restored to library!\n");
-                return Ok(());
+                say!("🎉 Sample restored to library!\n");
+                return Ok(PairOutcome::Restored);
             }
         }
         
         // Determine target library path
-        let target_path = metadata.get_library_path(&self.library_dir);
-        println!("📍 Target path: {:?}", target_path);
+        let Some(claim) = self.claim_target(self.library_path(&placement, audio_path), audio_path, &file_hash)? else {
+            return Ok(PairOutcome::Skipped);
+        };
+        let target_path = claim.path.clone();
+        say!("📍 Target path: {:?}", target_path);
         
         // Create target directory with proper error handling
         if let Some(parent) = target_path.parent() {
             Self::ensure_directory(parent)?;
-            println!("📁 Ensured directory: {:?}", parent);
+            say!("📁 Ensured directory: {:?}", parent);
         }
         
-        // Atomic file move with backup
-        self.move_file_safely(wav_path, &target_path).await?;
-        println!("✅ Moved WAV file to: {:?}", target_path);
+        // Atomic file move with backup, journaling each step so a crash can be recovered
+        let journal_id = db::begin_import(&self.database_path, JournalOperation::Import, audio_path, json_path, &target_path, &file_hash)?;
+        self.move_into_library(journal_id, audio_path, &claim, &file_hash)?;
+        db::set_import_state(&self.database_path, journal_id, JournalState::Moved)?;
+        say!("✅ Moved audio file to: {:?}", target_path);
         
         // Create database record
         let mut record = SampleRecord::from(&metadata);
         record.file_path = target_path.to_string_lossy().to_string();
         record.file_hash = file_hash;
+        record.format = Some(format.to_string());
+        if let Some(info) = wav_info {
+            record.sample_rate = Some(info.sample_rate);
+            record.bit_depth = Some(info.bit_depth);
+            record.channels = Some(info.channels);
+            record.frame_count = Some(info.frames);
+            record.duration_secs = Some(info.duration_secs());
+            say!("🎚️  {} Hz, {}-bit, {} channel(s), {:.3}s", info.sample_rate, info.bit_depth, info.channels, info.duration_secs());
+        }
         
-        // Insert into database with retry
-        self.insert_sample_with_retry(record).await?;
+        let categorization = placement.categorize(&self.rules);
+        record.mapped_category = categorization.category.as_str();
+        record.category_confidence = Some(categorization.confidence as f64);
+        record.runner_up_category = categorization.runner_up.map(|c| c.as_str());
+        say!("📂 Category: {} (confidence {:.0}%)", record.mapped_category, categorization.confidence * 100.0);
+        
+        // Insert into database with retry; without a record the library copy would be
+        // untracked, so put the download back where a later scan will find it
+        if let Err(e) = self.insert_sample_with_retry(record.clone()).await {
+            self.roll_back_move(journal_id, &target_path, audio_path);
+            return Err(e);
+        }
+        db::set_import_state(&self.database_path, journal_id, JournalState::Recorded)?;
+        discard_replaced(&target_path);
+        if let Some(analysis) = &analysis {
+            if let Err(e) = db::store_analysis(&self.database_path, &record.file_hash, analysis) {
+                eprintln!("⚠️  Failed to store the analysis of {:?}: {}", target_path, e);
+            }
+        }
+        self.embed_metadata(&target_path, &record);
         
         // Clean up the JSON file
         self.cleanup_metadata_file(json_path).await?;
+        self.finish_journal_entry(journal_id);
         
-        println!("🎉 Sample processing complete!\n");
+        say!("🎉 Sample processing complete!\n");
+        Ok(PairOutcome::Imported)
+    }
+    
+    /// Tag the library copy when `embed_metadata` or `embed_loop_chunks` is on. The sample
+    /// is already in the library by now, so a file that can't be tagged is only a warning.
+    fn embed_metadata(&self, target_path: &Path, record: &SampleRecord) {
+        let options = EmbedOptions {
+            metadata: self.settings.embed_metadata,
+            loop_chunks: self.settings.embed_loop_chunks,
+        };
+        if !options.any() || AudioFormat::from_path(target_path) != Some(AudioFormat::Wav) {
+            return;
+        }
+        match tagging::embed_metadata(target_path, record, options) {
+            Ok(()) => say!("🏷️  Embedded metadata into the WAV"),
+            Err(e) => eprintln!("⚠️  {}", e),
+        }
+    }
+    
+    /// Apply `on_mismatch` to an audio file that failed verification or can't be read.
+    /// `None` means import it anyway; otherwise the pair stops here. `accept` only covers
+    /// verification: a file whose header can't be read is never imported, so under
+    /// `accept` it fails the attempt like `retry-wait` and is quarantined once retries run out.
+    fn handle_bad_audio(&self, audio_path: &Path, json_path: &Path, reason: QuarantineReason, problem: &str) -> Result<Option<PairOutcome>> {
+        match (self.settings.on_mismatch, reason) {
+            (MismatchAction::Quarantine, _) => {
+                self.quarantine_pair(audio_path, json_path, reason, problem)?;
+                Ok(Some(PairOutcome::Quarantined))
+            }
+            (MismatchAction::Accept, QuarantineReason::Verification) => {
+                eprintln!("⚠️  {:?} failed its checks ({}), importing anyway", audio_path, problem);
+                Ok(None)
+            }
+            _ => Err(BadAudio { reason, problem: problem.to_string() }.into()),
+        }
+    }
+    
+    /// Move a pair out of the watch directory into its own quarantine entry
+    fn quarantine_pair(&self, audio_path: &Path, json_path: &Path, reason: QuarantineReason, problem: &str) -> Result<()> {
+        let entry = quarantine::quarantine_pair(&self.quarantine_dir, audio_path, json_path, reason, problem)?;
+        eprintln!("🚫 Quarantined {:?} ({}): {:?}", audio_path, problem, entry);
         Ok(())
     }
     
-    async fn calculate_file_hash_with_retry(&self, file_path: &Path) -> Result<String> {
-        for attempt in 1..=3 {
-            match self.calculate_file_hash(file_path) {
-                Ok(hash) => return Ok(hash),
-                Err(e) => {
-                    eprintln!("🔄 Hash calculation attempt {}/3 failed: {}", attempt, e);
-                    if attempt < 3 {
-                        sleep(Duration::from_millis(1000)).await;
-                    } else {
-                        return Err(e);
-                    }
+    /// Set aside the pairs behind an event that kept failing, so the same error doesn't
+    /// come back on every scan. Files without their partner are left for the pending queue.
+    fn quarantine_failed_event(&self, paths: &[PathBuf], error: &anyhow::Error) {
+        let mut pairs: Vec<(PathBuf, PathBuf)> = Vec::new();
+        for path in paths {
+            let pair = if self.settings.is_audio_file(path) {
+                (path.clone(), path.with_extension("json"))
+            } else if is_json_file(path) {
+                match self.settings.audio_partner(path) {
+                    Some(audio_path) => (audio_path, path.clone()),
+                    None => continue,
                 }
+            } else {
+                continue;
+            };
+            if pair.0.exists() && pair.1.exists() && !pairs.contains(&pair) {
+                pairs.push(pair);
             }
         }
-        unreachable!()
+        
+        // A pair that kept failing its checks is reported for what was wrong with it
+        let (reason, problem) = match error.downcast_ref::<BadAudio>() {
+            Some(bad) => (bad.reason, bad.problem.clone()),
+            None => (QuarantineReason::ImportFailed, error.to_string()),
+        };
+        for (audio_path, json_path) in pairs {
+            if let Err(e) = self.quarantine_pair(&audio_path, &json_path, reason, &problem) {
+                eprintln!("⚠️  Failed to quarantine {:?}: {}", audio_path, e);
+                continue;
+            }
+            self.stats.record(PairOutcome::Quarantined);
+            for path in [&audio_path, &json_path] {
+                if let Err(e) = db::remove_pending_file(&self.database_path, path) {
+                    eprintln!("⚠️  Failed to clear pending entry for {:?}: {}", path, e);
+                }
+            }
+        }
+    }
+    
+    /// Where the layout puts a sample, keeping the extension of the audio file actually imported
+    fn library_path(&self, metadata: &SampleMetadata, audio_path: &Path) -> PathBuf {
+        let target_path = metadata.get_library_path(&self.library_dir, &self.rules, &self.layout);
+        match audio_path.extension() {
+            Some(extension) => layout::with_audio_extension(target_path, extension),
+            None => target_path,
+        }
+    }
+    
+    /// Apply the collision policy to a freshly rendered library path, returning where the
+    /// sample should go, or `None` when it should stay in the watch directory
+    fn claim_target(&self, target_path: PathBuf, audio_path: &Path, file_hash: &str) -> Result<Option<Claim>> {
+        let resolved = resolve_collision(&target_path, audio_path, file_hash, self.collision_policy)?;
+        let claim = match resolved {
+            Some(path) if path != target_path => {
+                say!("🚧 {:?} is taken by another sample, using {:?} ({})", target_path, path, self.collision_policy);
+                Claim { path, replaces: false }
+            }
+            Some(path) if path.exists() => {
+                say!("♻️  {:?} already holds the same audio, replacing it", target_path);
+                Claim { path, replaces: true }
+            }
+            Some(path) => Claim { path, replaces: false },
+            None => {
+                say!("🚧 {:?} is taken by another file, leaving the download in place ({})", target_path, self.collision_policy);
+                return Ok(None);
+            }
+        };
+        Ok(Some(claim))
+    }
+    
+    /// Move a download to its claimed library path, which `move_file_safely` never
+    /// overwrites. A same-hash overwrite first sets the old file aside, and it stays there
+    /// until the new copy is recorded, so a failed import can put it back.
+    fn move_into_library(&self, journal_id: i64, audio_path: &Path, claim: &Claim, file_hash: &str) -> Result<()> {
+        let moved = (|| {
+            if claim.replaces {
+                set_aside_replaced(&claim.path, audio_path, file_hash)?;
+            }
+            move_file_safely(audio_path, &claim.path)
+        })();
+        if let Err(e) = moved {
+            restore_replaced(&claim.path);
+            self.finish_journal_entry(journal_id);
+            return Err(e);
+        }
+        Ok(())
     }
     
-    fn calculate_file_hash(&self, file_path: &Path) -> Result<String> {
-        let data = fs::read(file_path)
-            .map_err(|e| anyhow::anyhow!("Failed to read file for hashing: {}", e))?;
-        let mut hasher = Sha256::new();
-        hasher.update(&data);
-        let result = hasher.finalize();
-        Ok(format!("{:x}", result))
+    /// Undo `move_file_safely` after the database could not be updated, putting back any
+    /// file the import replaced. If the file cannot be moved back, the journal entry
+    /// stays so the next startup tries again.
+    fn roll_back_move(&self, journal_id: i64, target_path: &Path, audio_path: &Path) {
+        match move_file_safely(target_path, audio_path) {
+            Ok(()) => {
+                say!("↩️  Moved {:?} back to {:?}", target_path, audio_path);
+                restore_replaced(target_path);
+                self.finish_journal_entry(journal_id);
+            }
+            Err(e) => eprintln!("🚨 Failed to move {:?} back to {:?}: {}", target_path, audio_path, e),
+        }
     }
     
-    async fn move_file_safely(&self, source: &Path, target: &Path) -> Result<()> {
-        // Create backup name in case of failure (reserved for future rollback functionality)
-        let _backup_path = source.with_extension("wav.backup");
+    /// The import is complete or fully undone; a leftover entry would only be re-checked on startup
+    fn finish_journal_entry(&self, journal_id: i64) {
+        if let Err(e) = db::finish_import(&self.database_path, journal_id) {
+            eprintln!("⚠️  Failed to clear import journal entry {}: {}", journal_id, e);
+        }
+    }
+    
+    /// Resolve imports a previous run left unfinished, completing those that got far
+    /// enough and undoing the rest so the startup scan can import them again
+    fn recover_interrupted_imports(&self) -> Result<RecoverySummary> {
+        let mut summary = RecoverySummary::default();
         
-        // First, try to copy the file
-        fs::copy(source, target)
-            .map_err(|e| anyhow::anyhow!("Failed to copy file to target: {}", e))?;
+        for entry in db::get_incomplete_imports(&self.database_path)? {
+            say!("🧾 Recovering interrupted {} of {:?} ({})", entry.operation, entry.audio_path, entry.state);
+            // A file a same-hash overwrite set aside goes back unless the import completed
+            match self.recover_import(&entry) {
+                Ok(Recovery::Completed) => {
+                    discard_replaced(&entry.target_path);
+                    summary.completed.push(entry.target_path);
+                }
+                Ok(Recovery::RolledBack) => {
+                    restore_replaced(&entry.target_path);
+                    summary.rolled_back.push(entry.audio_path);
+                }
+                Err(e) => {
+                    eprintln!("🚨 Failed to recover import of {:?}: {}", entry.audio_path, e);
+                    summary.failed.push((entry.audio_path, e.to_string()));
+                }
+            }
+        }
         
-        // Verify the copy is complete and valid
-        let source_size = fs::metadata(source)?.len();
-        let target_size = fs::metadata(target)?.len();
+        Ok(summary)
+    }
+    
+    fn recover_import(&self, entry: &JournalEntry) -> Result<Recovery> {
+        let mut state = entry.state;
         
-        if source_size != target_size {
-            // Remove invalid copy
-            let _ = fs::remove_file(target);
-            return Err(anyhow::anyhow!("File copy verification failed: size mismatch"));
+        if state == JournalState::Moving {
+            if entry.audio_path.exists() {
+                // The source is only removed after a verified copy, so the move never
+                // finished; drop whatever partial copy made it into the library
+                let partial_path = partial_copy_path(&entry.target_path);
+                if partial_path.exists() {
+                    fs::remove_file(&partial_path)
+                        .map_err(|e| anyhow::anyhow!("Failed to remove partial copy {:?}: {}", partial_path, e))?;
+                }
+                let is_copy = |path: &Path| sha256_file(path).is_ok_and(|hash| hash == entry.file_hash);
+                if entry.target_path.exists()
+                    && !db::sample_exists_at_path(&self.database_path, &entry.target_path)?
+                    && is_copy(&entry.target_path)
+                {
+                    fs::remove_file(&entry.target_path)
+                        .map_err(|e| anyhow::anyhow!("Failed to remove partial copy {:?}: {}", entry.target_path, e))?;
+                }
+                db::finish_import(&self.database_path, entry.id)?;
+                return Ok(Recovery::RolledBack);
+            }
+            if !entry.target_path.exists() {
+                db::finish_import(&self.database_path, entry.id)?;
+                anyhow::bail!("Audio file is neither at {:?} nor at {:?}", entry.audio_path, entry.target_path);
+            }
+            // Copied and verified, and the source removed: the move completed
+            state = JournalState::Moved;
         }
         
-        // Only remove source after successful copy and verification
-        fs::remove_file(source)
-            .map_err(|e| anyhow::anyhow!("Failed to remove source file after copy: {}", e))?;
+        if state == JournalState::Moved {
+            let recorded = match get_sample_by_hash(&self.database_path, &entry.file_hash)? {
+                Some(record) => match entry.operation {
+                    JournalOperation::Import => Path::new(&record.file_path) == entry.target_path,
+                    JournalOperation::Restore => {
+                        db::update_file_path(&self.database_path, &entry.file_hash, &entry.target_path.to_string_lossy())?;
+                        true
+                    }
+                },
+                None => false,
+            };
+            
+            if !recorded {
+                // Without a record the library copy would be untracked
+                if entry.audio_path.exists() {
+                    anyhow::bail!("Cannot move {:?} back, {:?} exists again", entry.target_path, entry.audio_path);
+                }
+                move_file_safely(&entry.target_path, &entry.audio_path)?;
+                db::finish_import(&self.database_path, entry.id)?;
+                return Ok(Recovery::RolledBack);
+            }
+        }
         
-        Ok(())
+        // Only the JSON cleanup is left, unless the same file has been downloaded again
+        if entry.json_path.exists() && !entry.audio_path.exists() {
+            fs::remove_file(&entry.json_path)
+                .map_err(|e| anyhow::anyhow!("Failed to remove metadata file {:?}: {}", entry.json_path, e))?;
+        }
+        db::finish_import(&self.database_path, entry.id)?;
+        Ok(Recovery::Completed)
+    }
+    
+    async fn calculate_file_hash_with_retry(&self, file_path: &Path) -> Result<FileDigest> {
+        let attempts = self.settings.retry_attempts;
+        for attempt in 1..=attempts {
+            match self.calculate_file_hash(file_path) {
+                Ok(digest) => return Ok(digest),
+                Err(e) => {
+                    eprintln!("🔄 Hash calculation attempt {}/{} failed: {}", attempt, attempts, e);
+                    if attempt < attempts && !self.stats.is_stopping() {
+                        sleep(Duration::from_millis(self.settings.retry_delay_ms)).await;
+                    } else {
+                        return Err(e);
+                    }
+                }
+            }
+        }
+        unreachable!()
+    }
+    
+    fn calculate_file_hash(&self, file_path: &Path) -> Result<FileDigest> {
+        digest_file(file_path)
     }
     
     async fn insert_sample_with_retry(&self, record: SampleRecord) -> Result<()> {
-        for attempt in 1..=3 {
+        let attempts = self.settings.retry_attempts;
+        for attempt in 1..=attempts {
             match insert_sample(&self.database_path, record.clone()) {
                 Ok(id) => {
-                    println!("✅ Added sample to database with ID: {}", id);
+                    say!("✅ Added sample to database with ID: {}", id);
                     return Ok(());
                 }
                 Err(e) => {
-                    eprintln!("🔄 Database insert attempt {}/3 failed: {}", attempt, e);
-                    if attempt < 3 {
-                        sleep(Duration::from_millis(1000)).await;
+                    eprintln!("🔄 Database insert attempt {}/{} failed: {}", attempt, attempts, e);
+                    if attempt < attempts && !self.stats.is_stopping() {
+                        sleep(Duration::from_millis(self.settings.retry_delay_ms)).await;
                     } else {
                         return Err(anyhow::anyhow!("Failed to add sample to database after {} attempts: {}", attempt, e));
                     }
@@ -382,19 +1320,19 @@ impl FileWatcher {
         unreachable!()
     }
     
-    async fn cleanup_duplicate_files(&self, wav_path: &Path, json_path: &Path) -> Result<()> {
-        // Remove duplicate WAV file
-        if let Err(e) = fs::remove_file(wav_path) {
-            eprintln!("⚠️  Failed to remove duplicate WAV file {:?}: {}", wav_path, e);
+    async fn cleanup_duplicate_files(&self, audio_path: &Path, json_path: &Path) -> Result<()> {
+        // Remove duplicate audio file
+        if let Err(e) = fs::remove_file(audio_path) {
+            eprintln!("⚠️  Failed to remove duplicate audio file {:?}: {}", audio_path, e);
         } else {
-            println!("🗑️  Removed duplicate WAV file");
+            say!("🗑️  Removed duplicate audio file");
         }
         
         // Remove duplicate JSON file
         if let Err(e) = fs::remove_file(json_path) {
             eprintln!("⚠️  Failed to remove duplicate JSON file {:?}: {}", json_path, e);
         } else {
-            println!("🗑️  Removed duplicate JSON file");
+            say!("🗑️  Removed duplicate JSON file");
         }
         
         Ok(())
@@ -404,26 +1342,273 @@ impl FileWatcher {
         if let Err(e) = fs::remove_file(json_path) {
             eprintln!("⚠️  Warning: Failed to remove JSON file {:?}: {}", json_path, e);
         } else {
-            println!("🗑️  Cleaned up metadata file");
+            say!("🗑️  Cleaned up metadata file");
         }
         Ok(())
     }
     
-    async fn handle_orphaned_wav(&self, wav_path: &Path) -> Result<()> {
-        println!("🤔 Handling WAV file without metadata: {:?}", wav_path);
+    async fn handle_orphaned_audio(&self, audio_path: &Path) -> Result<()> {
+        say!("🤔 Handling audio file without metadata: {:?}", audio_path);
         
-        // For now, just log it. In the future, we could:
-        // - Move to a special "unprocessed" folder
-        // - Try to extract metadata from filename
-        // - Queue for manual processing
+        // Persist it so a late JSON (or a restart) can still complete the pair
+        self.queue_pending(audio_path, PendingKind::Audio)?;
         
-        println!("⏳ WAV file queued for manual processing or metadata arrival");
+        say!("⏳ Audio file queued until its metadata arrives (see `pending list`)");
         Ok(())
     }
     
 
 }
 
+/// Move a file into place, failing rather than replacing anything already at `target`.
+/// On the same filesystem this is an atomic rename; across devices the file is copied
+/// next to the target, synced, checked against the source's hash and renamed into
+/// place, and the source is only removed after that.
+pub fn move_file_safely(source: &Path, target: &Path) -> Result<()> {
+    match rename_no_replace(source, target) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => copy_across_devices(source, target),
+        Err(e) => Err(anyhow::anyhow!("Failed to move {:?} to {:?}: {}", source, target, e)),
+    }
+}
+
+/// Rename `source` to `target` unless `target` exists. A hard link claims the name
+/// atomically and fails if it is taken, where a plain rename would replace the file;
+/// filesystems without hard links fall back to checking first.
+fn rename_no_replace(source: &Path, target: &Path) -> std::io::Result<()> {
+    use std::io::ErrorKind;
+    
+    match fs::hard_link(source, target) {
+        Ok(()) => {
+            if let Err(e) = fs::remove_file(source) {
+                let _ = fs::remove_file(target);
+                return Err(e);
+            }
+            Ok(())
+        }
+        Err(e) if matches!(e.kind(), ErrorKind::AlreadyExists | ErrorKind::CrossesDevices | ErrorKind::NotFound) => Err(e),
+        Err(_) if target.symlink_metadata().is_ok() => Err(ErrorKind::AlreadyExists.into()),
+        Err(_) => fs::rename(source, target),
+    }
+}
+
+/// A library path handed out by the collision policy
+#[derive(Debug)]
+struct Claim {
+    path: PathBuf,
+    /// The path already holds the same audio, which the import replaces
+    replaces: bool,
+}
+
+/// Where a same-hash overwrite keeps the file it replaces until the import is recorded.
+/// Hidden, and without an audio extension, so neither the watcher nor a scan picks it up.
+pub fn replaced_file_path(target: &Path) -> PathBuf {
+    let file_name = target.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    target.with_file_name(format!(".{}.splice-alt-replaced", file_name))
+}
+
+/// Move the file at `target` aside for a same-hash overwrite, making sure what was set
+/// aside really is the same audio as `source` and not a file that appeared since the check
+fn set_aside_replaced(target: &Path, source: &Path, file_hash: &str) -> Result<()> {
+    let replaced = replaced_file_path(target);
+    rename_no_replace(target, &replaced)
+        .map_err(|e| anyhow::anyhow!("Failed to set aside {:?} before replacing it: {}", target, e))?;
+    if sha256_file(&replaced)? != file_hash && !same_wav_audio(source, &replaced) {
+        restore_replaced(target);
+        anyhow::bail!("{:?} changed before it could be replaced", target);
+    }
+    Ok(())
+}
+
+/// Put a file set aside by `set_aside_replaced` back, after the import was undone
+fn restore_replaced(target: &Path) {
+    let replaced = replaced_file_path(target);
+    if replaced.exists() {
+        if let Err(e) = rename_no_replace(&replaced, target) {
+            eprintln!("🚨 Failed to restore {:?} from {:?}: {}", target, replaced, e);
+        }
+    }
+}
+
+/// Delete the file a same-hash overwrite replaced, once the new copy is recorded
+fn discard_replaced(target: &Path) {
+    let replaced = replaced_file_path(target);
+    if replaced.exists() {
+        if let Err(e) = fs::remove_file(&replaced) {
+            eprintln!("⚠️  Failed to remove replaced file {:?}: {}", replaced, e);
+        }
+    }
+}
+
+/// Decide where the sample at `source` (hashing to `file_hash`) goes when `target` may
+/// already exist. `None` means the policy is to leave the sample where it is.
+pub fn resolve_collision(target: &Path, source: &Path, file_hash: &str, policy: CollisionPolicy) -> Result<Option<PathBuf>> {
+    resolve_collision_among(target, source, file_hash, policy, |path| path.exists())
+}
+
+/// `resolve_collision` where `taken` decides which paths are occupied, so a plan can
+/// also count the targets it has already handed out
+pub fn resolve_collision_among(
+    target: &Path,
+    source: &Path,
+    file_hash: &str,
+    policy: CollisionPolicy,
+    taken: impl Fn(&Path) -> bool,
+) -> Result<Option<PathBuf>> {
+    if !taken(target) {
+        return Ok(Some(target.to_path_buf()));
+    }
+    
+    match policy {
+        CollisionPolicy::HashSuffix => {
+            let suffixed = with_name_suffix(target, hash_suffix(file_hash));
+            // Only an identical short hash lands here, so count on from the suffixed name
+            Ok(Some(if taken(&suffixed) { next_free_path(&suffixed, &taken) } else { suffixed }))
+        }
+        CollisionPolicy::Counter => Ok(Some(next_free_path(target, &taken))),
+        CollisionPolicy::Skip => Ok(None),
+        CollisionPolicy::OverwriteSameHash => {
+            if target.exists() && (sha256_file(target)? == file_hash || same_wav_audio(source, target)) {
+                Ok(Some(target.to_path_buf()))
+            } else {
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Whether `path` is `target` renamed by a collision policy: `kick-1a2b3c4d.wav`,
+/// `kick-2.wav` or `kick-1a2b3c4d-2.wav` for `kick.wav`
+pub fn is_collision_variant(path: &Path, target: &Path, file_hash: &str) -> bool {
+    if path.parent() != target.parent() || path.extension() != target.extension() {
+        return false;
+    }
+    let (Some(stem), Some(target_stem)) = (path.file_stem(), target.file_stem()) else {
+        return false;
+    };
+    let stem = stem.to_string_lossy();
+    let Some(suffix) = stem.strip_prefix(&format!("{}-", target_stem.to_string_lossy())) else {
+        return false;
+    };
+    
+    let is_counter = |n: &str| n.parse::<u32>().is_ok_and(|n| n >= 2) && !n.starts_with('0');
+    let hash = hash_suffix(file_hash);
+    suffix == hash
+        || is_counter(suffix)
+        || suffix.strip_prefix(hash).and_then(|rest| rest.strip_prefix('-')).is_some_and(is_counter)
+}
+
+fn hash_suffix(file_hash: &str) -> &str {
+    &file_hash[..file_hash.len().min(8)]
+}
+
+/// Whether two WAVs hold the same audio, even if one of them has had metadata embedded
+/// since (which changes the file's hash but never its format or sample data)
+fn same_wav_audio(a: &Path, b: &Path) -> bool {
+    let (Ok(info_a), Ok(info_b)) = (wav::read_info(a), wav::read_info(b)) else {
+        return false;
+    };
+    let format = |info: &wav::WavInfo| (info.format_tag, info.channels, info.sample_rate, info.bit_depth, info.data_size);
+    format(&info_a) == format(&info_b)
+        && matches!((wav::data_sha256(a), wav::data_sha256(b)), (Ok(hash_a), Ok(hash_b)) if hash_a == hash_b)
+}
+
+/// `dir/kick.wav` with suffix `2` becomes `dir/kick-2.wav`
+fn with_name_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let file_name = match path.extension() {
+        Some(extension) => format!("{}-{}.{}", stem, suffix, extension.to_string_lossy()),
+        None => format!("{}-{}", stem, suffix),
+    };
+    path.with_file_name(file_name)
+}
+
+fn next_free_path(path: &Path, taken: impl Fn(&Path) -> bool) -> PathBuf {
+    (2..)
+        .map(|n| with_name_suffix(path, &n.to_string()))
+        .find(|candidate| !taken(candidate))
+        .expect("some numbered name is free")
+}
+
+fn is_json_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+}
+
+/// Where a cross-device copy is written before it is renamed into place. Hidden, and
+/// without an audio extension, so neither the watcher nor a scan picks it up.
+pub fn partial_copy_path(target: &Path) -> PathBuf {
+    let file_name = target.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    target.with_file_name(format!(".{}.splice-alt-partial", file_name))
+}
+
+fn copy_across_devices(source: &Path, target: &Path) -> Result<()> {
+    copy_into_place(source, target, sha256_file)
+}
+
+/// The cross-device move: write a verified copy to the partial path, rename it to
+/// `target`, sync the directory and only then remove `source`. `hash_copy` reads the
+/// hash of the synced copy back from disk.
+fn copy_into_place(source: &Path, target: &Path, hash_copy: impl Fn(&Path) -> Result<String>) -> Result<()> {
+    let partial_path = partial_copy_path(target);
+    
+    if let Err(e) = write_verified_copy(source, &partial_path, hash_copy) {
+        let _ = fs::remove_file(&partial_path);
+        return Err(e);
+    }
+    
+    rename_no_replace(&partial_path, target).map_err(|e| {
+        let _ = fs::remove_file(&partial_path);
+        anyhow::anyhow!("Failed to move verified copy into place at {:?}: {}", target, e)
+    })?;
+    
+    // Make the rename itself durable before the only other copy goes away
+    if let Some(parent) = target.parent() {
+        if let Err(e) = fs::File::open(parent).and_then(|dir| dir.sync_all()) {
+            eprintln!("⚠️  Failed to sync directory {:?}: {}", parent, e);
+        }
+    }
+    
+    // Only remove source after successful copy and verification
+    fs::remove_file(source)
+        .map_err(|e| anyhow::anyhow!("Failed to remove source file after copy: {}", e))?;
+    
+    Ok(())
+}
+
+/// Copy `source` to `destination`, hashing it on the way, then fsync the copy and
+/// read it back with `hash_copy` to make sure the same bytes reached the disk
+fn write_verified_copy(source: &Path, destination: &Path, hash_copy: impl Fn(&Path) -> Result<String>) -> Result<()> {
+    use std::io::{Read, Write};
+    
+    let mut reader = fs::File::open(source)
+        .map_err(|e| anyhow::anyhow!("Failed to open {:?} for copying: {}", source, e))?;
+    let mut writer = fs::File::create(destination)
+        .map_err(|e| anyhow::anyhow!("Failed to create temporary copy {:?}: {}", destination, e))?;
+    
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = reader.read(&mut buffer)
+            .map_err(|e| anyhow::anyhow!("Failed to read {:?}: {}", source, e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        writer.write_all(&buffer[..read])
+            .map_err(|e| anyhow::anyhow!("Failed to write temporary copy {:?}: {}", destination, e))?;
+    }
+    writer.sync_all()
+        .map_err(|e| anyhow::anyhow!("Failed to sync temporary copy {:?}: {}", destination, e))?;
+    drop(writer);
+    
+    let source_hash = format!("{:x}", hasher.finalize());
+    if hash_copy(destination)? != source_hash {
+        return Err(anyhow::anyhow!("File copy verification failed: hash mismatch for {:?}", destination));
+    }
+    
+    Ok(())
+}
+
 // Make SampleRecord cloneable for retry operations
 impl Clone for crate::db::SampleRecord {
     fn clone(&self) -> Self {
@@ -447,37 +1632,308 @@ impl Clone for crate::db::SampleRecord {
             splice_url: self.splice_url.clone(),
             preview_url: self.preview_url.clone(),
             asset_uuid: self.asset_uuid.clone(),
+            category_confidence: self.category_confidence,
+            runner_up_category: self.runner_up_category.clone(),
+            format: self.format.clone(),
+            sample_rate: self.sample_rate,
+            bit_depth: self.bit_depth,
+            channels: self.channels,
+            frame_count: self.frame_count,
+            duration_secs: self.duration_secs,
+            detected_bpm: self.detected_bpm,
+            detected_bpm_confidence: self.detected_bpm_confidence,
+            detected_key: self.detected_key.clone(),
+            detected_chord_type: self.detected_chord_type.clone(),
+            detected_key_confidence: self.detected_key_confidence,
+            analyzed_at: self.analyzed_at.clone(),
+            loudness_lufs: self.loudness_lufs,
+            sample_peak_dbfs: self.sample_peak_dbfs,
+            true_peak_dbtp: self.true_peak_dbtp,
+            rms_dbfs: self.rms_dbfs,
+            dc_offset: self.dc_offset,
+            loudness_analyzed_at: self.loudness_analyzed_at.clone(),
+            metadata_json: self.metadata_json.clone(),
         }
     }
 }
 
 // Public API functions for main.rs
 
+/// Watch until the event channel closes, applying every configuration sent on `updates`
+/// and carrying out `commands` from the control socket
 pub async fn watch_directory(
-    watch_dir: &Path, 
-    library_dir: &Path, 
-    database_path: &Path
+    config: &Config,
+    stats: Arc<WatcherStats>,
+    updates: watch::Receiver<Config>,
+    commands: mpsc::Receiver<WatcherCommand>,
 ) -> Result<()> {
-    let mut watcher = FileWatcher::new(
-        watch_dir.to_path_buf(),
-        library_dir.to_path_buf(),
-        database_path.to_path_buf(),
-    )?;
+    let mut watcher = FileWatcher::new(config)?;
+    watcher.stats = stats;
+    watcher.start_watching(updates, commands).await
+}
+
+/// Settings for one-off operations that never look at the watch directory
+fn without_watch_dir(config: &Config) -> Config {
+    let mut config = config.clone();
+    config.paths.watch_dir = PathBuf::from("/tmp"); // Dummy watch dir since we're not watching
+    config
+}
+
+pub async fn process_sample_pair(audio_path: &Path, json_path: &Path, config: &Config) -> Result<PairOutcome> {
+    let watcher = FileWatcher::new(&without_watch_dir(config))?;
+    watcher.process_sample_pair_public(audio_path, json_path).await
+}
+
+/// Run a quarantined pair through the import again. A mismatch against the metadata
+/// fails the retry rather than quarantining the pair a second time; `force` imports it anyway.
+pub async fn retry_quarantined(entry: &QuarantineEntry, force: bool, config: &Config) -> Result<PairOutcome> {
+    let mut config = without_watch_dir(config);
+    if force {
+        config.watcher.on_mismatch = MismatchAction::Accept;
+    } else if config.watcher.on_mismatch == MismatchAction::Quarantine {
+        config.watcher.on_mismatch = MismatchAction::RetryWait;
+    }
     
-    watcher.start_watching().await
+    let watcher = FileWatcher::new(&config)?;
+    let outcome = watcher.process_sample_pair(&entry.audio_path, &entry.json_path).await?;
+    quarantine::remove_if_settled(entry)?;
+    Ok(outcome)
 }
 
-pub async fn process_sample_pair(
-    wav_path: &Path,
-    json_path: &Path,
-    library_dir: &Path,
-    database_path: &Path,
-) -> Result<()> {
-    let watcher = FileWatcher::new(
-        PathBuf::from("/tmp"), // Dummy watch dir since we're not watching
-        library_dir.to_path_buf(),
-        database_path.to_path_buf(),
-    )?;
-    
-    watcher.process_sample_pair_public(wav_path, json_path).await
-} 
\ No newline at end of file
+pub async fn scan_directory(config: &Config) -> Result<ScanSummary> {
+    let watcher = FileWatcher::new(config)?;
+    watcher.scan_watch_directory().await
+}
+
+pub async fn resolve_pending(config: &Config) -> Result<PendingSummary> {
+    let watcher = FileWatcher::new(&without_watch_dir(config))?;
+    watcher.resolve_pending().await
+}
+
+pub async fn resolve_pending_with(record: &PendingRecord, partner: &Path, config: &Config) -> Result<ScanSummary> {
+    let watcher = FileWatcher::new(&without_watch_dir(config))?;
+    Ok(watcher.resolve_pending_with(record, partner).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wav::Chunk;
+    
+    fn tone(frequency: f32) -> Vec<f32> {
+        (0..4410).map(|i| (i as f32 * frequency * 2.0 * std::f32::consts::PI / 44100.0).sin() * 0.5).collect()
+    }
+    
+    #[test]
+    fn overwrite_same_hash_recognizes_a_tagged_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("download.wav");
+        let target = dir.path().join("kick.wav");
+        wav::write_float(&source, 1, 44100, &tone(440.0)).unwrap();
+        fs::copy(&source, &target).unwrap();
+        // Embedding metadata in the library copy changes its hash but not its audio
+        wav::write_chunks(&target, &[Chunk::list(b"INFO", &[Chunk::new(b"INAM", b"Kick\0".to_vec())])]).unwrap();
+        let file_hash = sha256_file(&source).unwrap();
+        assert_ne!(sha256_file(&target).unwrap(), file_hash);
+        
+        let resolved = resolve_collision(&target, &source, &file_hash, CollisionPolicy::OverwriteSameHash).unwrap();
+        
+        assert_eq!(resolved, Some(target));
+    }
+    
+    #[test]
+    fn overwrite_same_hash_leaves_different_audio_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("download.wav");
+        let target = dir.path().join("kick.wav");
+        wav::write_float(&source, 1, 44100, &tone(440.0)).unwrap();
+        wav::write_float(&target, 1, 44100, &tone(220.0)).unwrap();
+        let file_hash = sha256_file(&source).unwrap();
+        
+        let resolved = resolve_collision(&target, &source, &file_hash, CollisionPolicy::OverwriteSameHash).unwrap();
+        
+        assert_eq!(resolved, None);
+    }
+    
+    #[test]
+    fn moves_never_replace_an_existing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("download.wav");
+        let target = dir.path().join("kick.wav");
+        fs::write(&source, b"new").unwrap();
+        fs::write(&target, b"old").unwrap();
+        
+        assert!(move_file_safely(&source, &target).is_err());
+        assert_eq!(fs::read(&source).unwrap(), b"new");
+        assert_eq!(fs::read(&target).unwrap(), b"old");
+        
+        fs::remove_file(&target).unwrap();
+        move_file_safely(&source, &target).unwrap();
+        assert!(!source.exists());
+        assert_eq!(fs::read(&target).unwrap(), b"new");
+    }
+    
+    #[test]
+    fn cross_device_copy_renames_the_verified_copy_into_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("download.wav");
+        let target = dir.path().join("library").join("kick.wav");
+        fs::create_dir(target.parent().unwrap()).unwrap();
+        wav::write_float(&source, 1, 44100, &tone(440.0)).unwrap();
+        let contents = fs::read(&source).unwrap();
+        
+        // The source must still be there while its copy is checked
+        copy_into_place(&source, &target, |copy| {
+            assert!(source.exists());
+            assert_eq!(copy, partial_copy_path(&target));
+            sha256_file(copy)
+        }).unwrap();
+        
+        assert_eq!(fs::read(&target).unwrap(), contents);
+        assert!(!source.exists());
+        assert!(!partial_copy_path(&target).exists());
+    }
+    
+    #[test]
+    fn cross_device_copy_keeps_the_source_on_a_hash_mismatch() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("download.wav");
+        let target = dir.path().join("kick.wav");
+        fs::write(&source, b"audio").unwrap();
+        
+        let error = copy_into_place(&source, &target, |_| Ok("0".repeat(64))).unwrap_err();
+        
+        assert!(error.to_string().contains("hash mismatch"));
+        assert_eq!(fs::read(&source).unwrap(), b"audio");
+        assert!(!target.exists());
+        assert!(!partial_copy_path(&target).exists());
+    }
+    
+    #[test]
+    fn cross_device_copy_never_replaces_the_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("download.wav");
+        let target = dir.path().join("kick.wav");
+        fs::write(&source, b"new").unwrap();
+        fs::write(&target, b"old").unwrap();
+        
+        assert!(copy_into_place(&source, &target, sha256_file).is_err());
+        
+        assert_eq!(fs::read(&source).unwrap(), b"new");
+        assert_eq!(fs::read(&target).unwrap(), b"old");
+        assert!(!partial_copy_path(&target).exists());
+    }
+    
+    #[test]
+    fn set_aside_refuses_a_file_that_changed() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("download.wav");
+        let target = dir.path().join("kick.wav");
+        wav::write_float(&source, 1, 44100, &tone(440.0)).unwrap();
+        wav::write_float(&target, 1, 44100, &tone(220.0)).unwrap();
+        let before = fs::read(&target).unwrap();
+        
+        assert!(set_aside_replaced(&target, &source, &sha256_file(&source).unwrap()).is_err());
+        assert_eq!(fs::read(&target).unwrap(), before);
+        assert!(!replaced_file_path(&target).exists());
+    }
+    
+    /// Settings for a watcher working inside `dir`, giving up after the first attempt
+    fn test_config(dir: &Path) -> Config {
+        let mut config = Config::default();
+        config.paths.watch_dir = dir.join("watch");
+        config.paths.library_dir = dir.join("library");
+        config.paths.database = dir.join("samples.db");
+        config.watcher.retry_attempts = 1;
+        fs::create_dir_all(&config.paths.watch_dir).unwrap();
+        db::init_database(&config.paths.database).unwrap();
+        config
+    }
+    
+    #[tokio::test]
+    async fn retry_wait_quarantines_a_mismatch_as_verification() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = test_config(dir.path());
+        let audio = config.paths.watch_dir.join("kick_one.wav");
+        let json = config.paths.watch_dir.join("kick_one.json");
+        wav::write_float(&audio, 1, 44100, &tone(440.0)).unwrap();
+        // The fixture declares a 4-byte download
+        fs::write(&json, serde_json::to_string(&crate::metadata::test_metadata()).unwrap()).unwrap();
+        
+        let mut watcher = FileWatcher::new(&config).unwrap();
+        watcher.handle_settled_pair(std::slice::from_ref(&audio)).await;
+        
+        let entries = quarantine::list(&config.quarantine_dir()).unwrap();
+        assert_eq!(entries.len(), 1);
+        let report = entries[0].report.as_ref().unwrap();
+        assert_eq!(report.reason, QuarantineReason::Verification);
+        assert!(report.error.starts_with("size is"), "{}", report.error);
+        assert!(!audio.exists() && !json.exists());
+    }
+    
+    /// A watcher replacing same-hash files, with a download of `tone(440.0)` waiting and a
+    /// tagged copy of the same audio already at its library path
+    struct Overwrite {
+        _dir: tempfile::TempDir,
+        config: Config,
+        audio: PathBuf,
+        json: PathBuf,
+        target: PathBuf,
+    }
+    
+    impl Overwrite {
+        fn new() -> Self {
+            let dir = tempfile::tempdir().unwrap();
+            let mut config = test_config(dir.path());
+            config.library.on_collision = CollisionPolicy::OverwriteSameHash;
+            
+            let audio = config.paths.watch_dir.join("kick_one.wav");
+            let json = config.paths.watch_dir.join("kick_one.json");
+            wav::write_float(&audio, 1, 44100, &tone(440.0)).unwrap();
+            let mut metadata = crate::metadata::test_metadata();
+            metadata.sample.file_size = 0;
+            fs::write(&json, serde_json::to_string(&metadata).unwrap()).unwrap();
+            
+            let watcher = FileWatcher::new(&config).unwrap();
+            let target = watcher.library_path(&metadata, &audio);
+            fs::create_dir_all(target.parent().unwrap()).unwrap();
+            fs::copy(&audio, &target).unwrap();
+            wav::write_chunks(&target, &[Chunk::list(b"INFO", &[Chunk::new(b"INAM", b"Kick\0".to_vec())])]).unwrap();
+            
+            Self { _dir: dir, config, audio, json, target }
+        }
+        
+        async fn import(&self) -> Result<PairOutcome> {
+            let watcher = FileWatcher::new(&self.config).unwrap();
+            watcher.process_sample_pair_public(&self.audio, &self.json).await
+        }
+    }
+    
+    #[tokio::test]
+    async fn overwrite_same_hash_replaces_the_file_once_recorded() {
+        let setup = Overwrite::new();
+        let download = fs::read(&setup.audio).unwrap();
+        
+        assert_eq!(setup.import().await.unwrap(), PairOutcome::Imported);
+        
+        assert_eq!(fs::read(&setup.target).unwrap(), download);
+        assert!(!setup.audio.exists());
+        assert!(!replaced_file_path(&setup.target).exists());
+    }
+    
+    #[tokio::test]
+    async fn overwrite_same_hash_keeps_the_old_file_when_the_insert_fails() {
+        let setup = Overwrite::new();
+        let download = fs::read(&setup.audio).unwrap();
+        let library_copy = fs::read(&setup.target).unwrap();
+        rusqlite::Connection::open(&setup.config.paths.database).unwrap()
+            .execute_batch("CREATE TRIGGER fail_insert BEFORE INSERT ON samples BEGIN SELECT RAISE(ABORT, 'insert failed'); END;")
+            .unwrap();
+        
+        assert!(setup.import().await.is_err());
+        
+        assert_eq!(fs::read(&setup.target).unwrap(), library_copy);
+        assert_eq!(fs::read(&setup.audio).unwrap(), download);
+        assert!(!replaced_file_path(&setup.target).exists());
+    }
+}
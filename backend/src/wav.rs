@@ -0,0 +1,544 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::integrity;
+
+/// `WAVE_FORMAT_PCM`: integer samples
+pub const FORMAT_PCM: u16 = 0x0001;
+/// `WAVE_FORMAT_IEEE_FLOAT`: 32 or 64-bit float samples
+pub const FORMAT_IEEE_FLOAT: u16 = 0x0003;
+/// `WAVE_FORMAT_EXTENSIBLE`: the real format is the first two bytes of the sub-format GUID
+const FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// Technical details of a WAV file, read from its `fmt ` and `data` chunks
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct WavInfo {
+    /// `FORMAT_PCM` or `FORMAT_IEEE_FLOAT`, unwrapped from `WAVE_FORMAT_EXTENSIBLE`
+    pub format_tag: u16,
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub bit_depth: u16,
+    /// Bytes per frame, all channels included
+    pub block_align: u16,
+    pub frames: u64,
+    /// Where the sample data starts in the file, and how many bytes of it there are
+    pub data_offset: u64,
+    pub data_size: u64,
+}
+
+impl WavInfo {
+    /// Exact length of the audio in seconds
+    pub fn duration_secs(&self) -> f64 {
+        self.frames as f64 / self.sample_rate as f64
+    }
+}
+
+fn u16_le(bytes: &[u8]) -> u16 {
+    u16::from_le_bytes([bytes[0], bytes[1]])
+}
+
+fn u32_le(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+/// Printable chunk ID for error messages
+fn chunk_name(id: &[u8]) -> String {
+    String::from_utf8_lossy(id).trim_end().to_string()
+}
+
+/// Read and validate the RIFF/WAVE header of `path` without loading the audio
+pub fn read_info(path: &Path) -> Result<WavInfo> {
+    let file = File::open(path)
+        .map_err(|e| anyhow::anyhow!("Failed to open {:?}: {}", path, e))?;
+    let file_len = file.metadata()?.len();
+    parse(&mut BufReader::new(file), file_len)
+}
+
+/// Walk the chunks of a RIFF/WAVE stream of `file_len` bytes. Anything that would
+/// make the audio unreadable is an error: a truncated file, chunks running past the
+/// end, a missing or inconsistent `fmt `, or no `data` chunk.
+pub fn parse<R: Read + Seek>(reader: &mut R, file_len: u64) -> Result<WavInfo> {
+    let mut header = [0u8; 12];
+    reader.read_exact(&mut header)
+        .map_err(|_| anyhow::anyhow!("file is too short for a RIFF header"))?;
+    match &header[0..4] {
+        b"RIFF" => {}
+        b"RF64" => anyhow::bail!("RF64 files are not supported"),
+        _ => anyhow::bail!("not a RIFF file"),
+    }
+    if &header[8..12] != b"WAVE" {
+        anyhow::bail!("RIFF file is '{}', not WAVE", chunk_name(&header[8..12]));
+    }
+    
+    let riff_end = u32_le(&header[4..8]) as u64 + 8;
+    if riff_end > file_len {
+        anyhow::bail!("RIFF header declares {} bytes but the file has {}", riff_end, file_len);
+    }
+    
+    let mut fmt: Option<WavInfo> = None;
+    let mut offset = 12u64;
+    while offset + 8 <= riff_end {
+        reader.seek(SeekFrom::Start(offset))?;
+        let mut chunk_header = [0u8; 8];
+        reader.read_exact(&mut chunk_header)?;
+        let id = &chunk_header[0..4];
+        let size = u32_le(&chunk_header[4..8]) as u64;
+        let body = offset + 8;
+        
+        if body + size > riff_end {
+            anyhow::bail!("'{}' chunk at offset {} runs past the end of the file", chunk_name(id), offset);
+        }
+        
+        match id {
+            b"fmt " => {
+                if fmt.is_some() {
+                    anyhow::bail!("more than one 'fmt ' chunk");
+                }
+                let mut body = vec![0u8; size.min(40) as usize];
+                reader.read_exact(&mut body)?;
+                fmt = Some(parse_fmt(&body)?);
+            }
+            b"data" => {
+                let Some(mut info) = fmt else {
+                    anyhow::bail!("'data' chunk comes before the 'fmt ' chunk");
+                };
+                info.data_offset = body;
+                info.data_size = size;
+                info.frames = size / info.block_align as u64;
+                if info.frames == 0 {
+                    anyhow::bail!("'data' chunk holds no complete frames");
+                }
+                return Ok(info);
+            }
+            _ => {}
+        }
+        
+        // Chunks are padded to an even length
+        offset = body + size + (size & 1);
+    }
+    
+    if fmt.is_none() {
+        anyhow::bail!("no 'fmt ' chunk");
+    }
+    anyhow::bail!("no 'data' chunk")
+}
+
+fn parse_fmt(body: &[u8]) -> Result<WavInfo> {
+    if body.len() < 16 {
+        anyhow::bail!("'fmt ' chunk is {} bytes, expected at least 16", body.len());
+    }
+    
+    let mut format_tag = u16_le(&body[0..2]);
+    let channels = u16_le(&body[2..4]);
+    let sample_rate = u32_le(&body[4..8]);
+    let block_align = u16_le(&body[12..14]);
+    let bit_depth = u16_le(&body[14..16]);
+    
+    if format_tag == FORMAT_EXTENSIBLE {
+        if body.len() < 40 {
+            anyhow::bail!("extensible 'fmt ' chunk is {} bytes, expected 40", body.len());
+        }
+        format_tag = u16_le(&body[24..26]);
+    }
+    if format_tag != FORMAT_PCM && format_tag != FORMAT_IEEE_FLOAT {
+        anyhow::bail!("unsupported encoding (format tag {:#06x}), only PCM and float are read", format_tag);
+    }
+    
+    if channels == 0 {
+        anyhow::bail!("'fmt ' chunk declares no channels");
+    }
+    if sample_rate == 0 {
+        anyhow::bail!("'fmt ' chunk declares a sample rate of 0");
+    }
+    if bit_depth == 0 || bit_depth > 64 {
+        anyhow::bail!("'fmt ' chunk declares {} bits per sample", bit_depth);
+    }
+    let expected_align = channels as u32 * bit_depth.div_ceil(8) as u32;
+    if block_align as u32 != expected_align {
+        anyhow::bail!("block align is {}, but {} channels of {} bits need {}", block_align, channels, bit_depth, expected_align);
+    }
+    
+    Ok(WavInfo {
+        format_tag,
+        channels,
+        sample_rate,
+        bit_depth,
+        block_align,
+        frames: 0,
+        data_offset: 0,
+        data_size: 0,
+    })
+}
+
+/// SHA-256 of the sample data alone, which stays the same when chunks around it are
+/// added or rewritten
+pub fn data_sha256(path: &Path) -> Result<String> {
+    let info = read_info(path)?;
+    integrity::sha256_range(path, info.data_offset, info.data_size)
+}
+
+/// Decode one sample of `bytes.len()` bytes to the range -1.0..1.0
+fn decode_sample(bytes: &[u8], format_tag: u16) -> f32 {
+    match (format_tag, bytes.len()) {
+        (FORMAT_IEEE_FLOAT, 4) => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        (FORMAT_IEEE_FLOAT, 8) => f64::from_le_bytes(bytes.try_into().unwrap_or_default()) as f32,
+        // 8-bit PCM is unsigned, centred on 128
+        (_, 1) => (bytes[0] as f32 - 128.0) / 128.0,
+        (_, width) => {
+            let mut value = 0i64;
+            for (i, byte) in bytes.iter().enumerate() {
+                value |= (*byte as i64) << (8 * i);
+            }
+            // Sign-extend from the top bit of the last byte
+            let shift = 64 - 8 * width as u32;
+            ((value << shift) >> shift) as f32 / (1i64 << (8 * width - 1)) as f32
+        }
+    }
+}
+
+/// Decode a PCM or float WAV frame by frame, handing `visit` one sample per channel in
+/// the range -1.0..1.0; stops after `max_frames` when given
+pub fn for_each_frame(path: &Path, max_frames: Option<u64>, mut visit: impl FnMut(&[f32])) -> Result<WavInfo> {
+    let info = read_info(path)?;
+    let width = (info.block_align / info.channels) as usize;
+    match (info.format_tag, width) {
+        (FORMAT_PCM, 1..=4) | (FORMAT_IEEE_FLOAT, 4 | 8) => {}
+        _ => anyhow::bail!("can't decode {}-bit samples with format tag {:#06x}", info.bit_depth, info.format_tag),
+    }
+    
+    let mut reader = BufReader::new(File::open(path)?);
+    reader.seek(SeekFrom::Start(info.data_offset))?;
+    let frames = max_frames.map_or(info.frames, |max| info.frames.min(max));
+    let mut frame = vec![0u8; info.block_align as usize];
+    let mut samples = vec![0f32; info.channels as usize];
+    for _ in 0..frames {
+        reader.read_exact(&mut frame)?;
+        for (sample, bytes) in samples.iter_mut().zip(frame.chunks_exact(width)) {
+            *sample = decode_sample(bytes, info.format_tag);
+        }
+        visit(&samples);
+    }
+    Ok(info)
+}
+
+/// Decode up to the first `max_seconds` of a PCM or float WAV into mono samples (the
+/// channels averaged) in the range -1.0..1.0, for analysis
+pub fn read_mono(path: &Path, max_seconds: u64) -> Result<(WavInfo, Vec<f32>)> {
+    let mut samples = Vec::new();
+    let max_frames = max_seconds * read_info(path)?.sample_rate as u64;
+    let info = for_each_frame(path, Some(max_frames), |frame| {
+        samples.push(frame.iter().sum::<f32>() / frame.len() as f32);
+    })?;
+    Ok((info, samples))
+}
+
+/// A chunk to write into a WAV file: its ID and body, without the padding byte
+#[derive(Debug, Clone)]
+pub struct Chunk {
+    pub id: [u8; 4],
+    pub body: Vec<u8>,
+}
+
+impl Chunk {
+    pub fn new(id: &[u8; 4], body: Vec<u8>) -> Self {
+        Self { id: *id, body }
+    }
+    
+    /// A `LIST` chunk of `list_type` holding `children`
+    pub fn list(list_type: &[u8; 4], children: &[Chunk]) -> Self {
+        let mut body = list_type.to_vec();
+        for child in children {
+            child.write_to(&mut body).expect("writing to a Vec can't fail");
+        }
+        Self::new(b"LIST", body)
+    }
+    
+    /// Whether an existing chunk `id` with `body_start` as the first bytes of its body
+    /// is one this chunk supersedes. `LIST` chunks only replace lists of the same type.
+    fn replaces(&self, id: &[u8], body_start: &[u8]) -> bool {
+        id == self.id && (&self.id != b"LIST" || body_start.get(..4) == self.body.get(..4))
+    }
+    
+    fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.id)?;
+        writer.write_all(&(self.body.len() as u32).to_le_bytes())?;
+        writer.write_all(&self.body)?;
+        if self.body.len() % 2 == 1 {
+            writer.write_all(&[0])?;
+        }
+        Ok(())
+    }
+    
+    /// Size on disk, header and padding included
+    fn stored_len(&self) -> u64 {
+        8 + self.body.len() as u64 + (self.body.len() as u64 & 1)
+    }
+}
+
+/// Position of an existing chunk in a file
+struct ChunkSpan {
+    id: [u8; 4],
+    body: u64,
+    size: u64,
+    /// First four bytes of the body, the list type for `LIST` chunks
+    body_start: Vec<u8>,
+}
+
+/// Every chunk of the RIFF/WAVE stream, in file order
+fn chunk_spans<R: Read + Seek>(reader: &mut R, riff_end: u64) -> Result<Vec<ChunkSpan>> {
+    let mut spans = Vec::new();
+    let mut offset = 12u64;
+    while offset + 8 <= riff_end {
+        reader.seek(SeekFrom::Start(offset))?;
+        let mut chunk_header = [0u8; 8];
+        reader.read_exact(&mut chunk_header)?;
+        let id = [chunk_header[0], chunk_header[1], chunk_header[2], chunk_header[3]];
+        let size = u32_le(&chunk_header[4..8]) as u64;
+        let body = offset + 8;
+        if body + size > riff_end {
+            anyhow::bail!("'{}' chunk at offset {} runs past the end of the file", chunk_name(&id), offset);
+        }
+        
+        let mut body_start = vec![0u8; size.min(4) as usize];
+        reader.read_exact(&mut body_start)?;
+        spans.push(ChunkSpan { id, body, size, body_start });
+        offset = body + size + (size & 1);
+    }
+    Ok(spans)
+}
+
+/// Hidden file next to `path` that a rewrite is written to before replacing it
+fn rewrite_temp_path(path: &Path) -> PathBuf {
+    let mut name = OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(".retag");
+    path.with_file_name(name)
+}
+
+/// Rewrite the WAV at `path` with `chunks` in place of any existing chunks they
+/// supersede, placed before the `data` chunk where readers that stop at the audio
+/// still see them. Every other chunk, the audio included, is copied byte for byte;
+/// the audio is hashed before and after to prove it, and the file is only replaced
+/// once the new copy is complete and synced.
+pub fn write_chunks(path: &Path, chunks: &[Chunk]) -> Result<()> {
+    let temp_path = rewrite_temp_path(path);
+    
+    let result = (|| {
+        let info = read_info(path)?;
+        let mut source = BufReader::new(File::open(path)?);
+        let mut header = [0u8; 12];
+        source.read_exact(&mut header)?;
+        let riff_end = u32_le(&header[4..8]) as u64 + 8;
+        let spans = chunk_spans(&mut source, riff_end)?;
+        
+        let kept: Vec<&ChunkSpan> = spans.iter()
+            .filter(|span| !chunks.iter().any(|chunk| chunk.replaces(&span.id, &span.body_start)))
+            .collect();
+        let riff_size = 4
+            + kept.iter().map(|span| 8 + span.size + (span.size & 1)).sum::<u64>()
+            + chunks.iter().map(Chunk::stored_len).sum::<u64>();
+        if riff_size > u32::MAX as u64 {
+            anyhow::bail!("tagged file would exceed the 4 GiB WAV limit");
+        }
+        
+        let mut target = BufWriter::new(File::create(&temp_path)?);
+        target.write_all(b"RIFF")?;
+        target.write_all(&(riff_size as u32).to_le_bytes())?;
+        target.write_all(b"WAVE")?;
+        for span in kept {
+            if &span.id == b"data" {
+                for chunk in chunks {
+                    chunk.write_to(&mut target)?;
+                }
+            }
+            target.write_all(&span.id)?;
+            target.write_all(&(span.size as u32).to_le_bytes())?;
+            source.seek(SeekFrom::Start(span.body))?;
+            let copied = io::copy(&mut (&mut source).take(span.size), &mut target)?;
+            if copied != span.size {
+                anyhow::bail!("'{}' chunk ended early", chunk_name(&span.id));
+            }
+            if span.size % 2 == 1 {
+                target.write_all(&[0])?;
+            }
+        }
+        target.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        
+        let unchanged = read_info(&temp_path)?.data_size == info.data_size
+            && data_sha256(&temp_path)? == data_sha256(path)?;
+        if !unchanged {
+            anyhow::bail!("audio data changed while rewriting");
+        }
+        
+        fs::rename(&temp_path, path)?;
+        Ok(())
+    })();
+    
+    if let Err(e) = result {
+        let _ = fs::remove_file(&temp_path);
+        return Err(anyhow::anyhow!("Failed to write chunks into {:?}: {}", path, e));
+    }
+    Ok(())
+}
+
+/// Write interleaved samples as a 32-bit float WAV, for tests that need real audio
+#[cfg(test)]
+pub fn write_float(path: &Path, channels: u16, sample_rate: u32, samples: &[f32]) -> Result<()> {
+    let data: Vec<u8> = samples.iter().flat_map(|sample| sample.to_le_bytes()).collect();
+    let mut fmt = Vec::new();
+    fmt.extend_from_slice(&FORMAT_IEEE_FLOAT.to_le_bytes());
+    fmt.extend_from_slice(&channels.to_le_bytes());
+    fmt.extend_from_slice(&sample_rate.to_le_bytes());
+    fmt.extend_from_slice(&(sample_rate * channels as u32 * 4).to_le_bytes());
+    fmt.extend_from_slice(&(channels * 4).to_le_bytes());
+    fmt.extend_from_slice(&32u16.to_le_bytes());
+    
+    let chunks = [Chunk::new(b"fmt ", fmt), Chunk::new(b"data", data)];
+    let mut file = BufWriter::new(File::create(path)?);
+    file.write_all(b"RIFF")?;
+    file.write_all(&(4 + chunks.iter().map(Chunk::stored_len).sum::<u64>() as u32).to_le_bytes())?;
+    file.write_all(b"WAVE")?;
+    for chunk in &chunks {
+        chunk.write_to(&mut file)?;
+    }
+    file.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    
+    fn fmt_chunk(format_tag: u16, channels: u16, sample_rate: u32, bit_depth: u16) -> Chunk {
+        let block_align = channels * bit_depth.div_ceil(8);
+        let mut body = Vec::new();
+        body.extend_from_slice(&format_tag.to_le_bytes());
+        body.extend_from_slice(&channels.to_le_bytes());
+        body.extend_from_slice(&sample_rate.to_le_bytes());
+        body.extend_from_slice(&(sample_rate * block_align as u32).to_le_bytes());
+        body.extend_from_slice(&block_align.to_le_bytes());
+        body.extend_from_slice(&bit_depth.to_le_bytes());
+        Chunk::new(b"fmt ", body)
+    }
+    
+    /// `WAVE_FORMAT_EXTENSIBLE` wrapping `format_tag` in its sub-format GUID
+    fn extensible_fmt_chunk(format_tag: u16, channels: u16, sample_rate: u32, bit_depth: u16) -> Chunk {
+        let mut chunk = fmt_chunk(FORMAT_EXTENSIBLE, channels, sample_rate, bit_depth);
+        chunk.body.extend_from_slice(&22u16.to_le_bytes());
+        chunk.body.extend_from_slice(&bit_depth.to_le_bytes());
+        chunk.body.extend_from_slice(&0u32.to_le_bytes());
+        chunk.body.extend_from_slice(&format_tag.to_le_bytes());
+        chunk.body.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xAA, 0x00, 0x38, 0x9B, 0x71]);
+        chunk
+    }
+    
+    fn riff(chunks: &[Chunk]) -> Vec<u8> {
+        let mut bytes = b"RIFF".to_vec();
+        bytes.extend_from_slice(&(4 + chunks.iter().map(Chunk::stored_len).sum::<u64>() as u32).to_le_bytes());
+        bytes.extend_from_slice(b"WAVE");
+        for chunk in chunks {
+            chunk.write_to(&mut bytes).unwrap();
+        }
+        bytes
+    }
+    
+    fn parse_bytes(bytes: &[u8]) -> Result<WavInfo> {
+        parse(&mut Cursor::new(bytes), bytes.len() as u64)
+    }
+    
+    fn parse_error(bytes: &[u8]) -> String {
+        parse_bytes(bytes).unwrap_err().to_string()
+    }
+    
+    #[test]
+    fn reads_a_24_bit_48_khz_file() {
+        // One second of stereo 24-bit audio
+        let data = Chunk::new(b"data", vec![0; 48000 * 6]);
+        let info = parse_bytes(&riff(&[fmt_chunk(FORMAT_PCM, 2, 48000, 24), data])).unwrap();
+        assert_eq!((info.format_tag, info.channels, info.sample_rate, info.bit_depth), (FORMAT_PCM, 2, 48000, 24));
+        assert_eq!(info.block_align, 6);
+        assert_eq!(info.frames, 48000);
+        assert_eq!(info.duration_secs(), 1.0);
+        assert_eq!((info.data_offset, info.data_size), (44, 48000 * 6));
+    }
+    
+    #[test]
+    fn rejects_a_truncated_file() {
+        let mut bytes = riff(&[fmt_chunk(FORMAT_PCM, 1, 44100, 16), Chunk::new(b"data", vec![0; 1000])]);
+        bytes.truncate(bytes.len() - 100);
+        assert!(parse_error(&bytes).contains("RIFF header declares"));
+        assert!(parse_error(&bytes[..8]).contains("too short"));
+    }
+    
+    #[test]
+    fn rejects_a_chunk_running_past_the_end() {
+        let mut bytes = riff(&[fmt_chunk(FORMAT_PCM, 1, 44100, 16), Chunk::new(b"data", vec![0; 1000])]);
+        let data_size = bytes.len() - 1000 - 4;
+        bytes[data_size..data_size + 4].copy_from_slice(&2000u32.to_le_bytes());
+        assert!(parse_error(&bytes).contains("'data' chunk at offset 36 runs past the end"));
+    }
+    
+    #[test]
+    fn rejects_missing_chunks() {
+        let no_fmt = riff(&[Chunk::new(b"data", vec![0; 100])]);
+        assert!(parse_error(&no_fmt).contains("before the 'fmt ' chunk"));
+        
+        let no_fmt_at_all = riff(&[Chunk::new(b"LIST", b"INFO".to_vec())]);
+        assert_eq!(parse_error(&no_fmt_at_all), "no 'fmt ' chunk");
+        
+        let no_data = riff(&[fmt_chunk(FORMAT_PCM, 1, 44100, 16)]);
+        assert_eq!(parse_error(&no_data), "no 'data' chunk");
+    }
+    
+    #[test]
+    fn skips_the_pad_byte_of_odd_sized_chunks() {
+        let junk = Chunk::new(b"junk", vec![0xFF; 7]);
+        let bytes = riff(&[fmt_chunk(FORMAT_PCM, 1, 44100, 16), junk, Chunk::new(b"data", vec![0; 200])]);
+        let info = parse_bytes(&bytes).unwrap();
+        assert_eq!(info.data_offset, 36 + 8 + 8 + 8);
+        assert_eq!(info.frames, 100);
+    }
+    
+    #[test]
+    fn unwraps_extensible_formats() {
+        let float = riff(&[extensible_fmt_chunk(FORMAT_IEEE_FLOAT, 2, 96000, 32), Chunk::new(b"data", vec![0; 80])]);
+        let info = parse_bytes(&float).unwrap();
+        assert_eq!((info.format_tag, info.bit_depth, info.frames), (FORMAT_IEEE_FLOAT, 32, 10));
+        
+        let mut short = extensible_fmt_chunk(FORMAT_PCM, 2, 44100, 16);
+        short.body.truncate(18);
+        assert!(parse_error(&riff(&[short, Chunk::new(b"data", vec![0; 8])])).contains("expected 40"));
+        
+        let adpcm = riff(&[extensible_fmt_chunk(0x0002, 1, 44100, 4), Chunk::new(b"data", vec![0; 8])]);
+        assert!(parse_error(&adpcm).contains("unsupported encoding"));
+    }
+    
+    #[test]
+    fn rejects_a_bad_block_align() {
+        let mut fmt = fmt_chunk(FORMAT_PCM, 2, 44100, 16);
+        fmt.body[12..14].copy_from_slice(&3u16.to_le_bytes());
+        let bytes = riff(&[fmt, Chunk::new(b"data", vec![0; 100])]);
+        assert_eq!(parse_error(&bytes), "block align is 3, but 2 channels of 16 bits need 4");
+    }
+    
+    #[test]
+    fn rejects_data_without_a_whole_frame() {
+        let bytes = riff(&[fmt_chunk(FORMAT_PCM, 2, 44100, 24), Chunk::new(b"data", vec![0; 5])]);
+        assert!(parse_error(&bytes).contains("no complete frames"));
+    }
+    
+    #[test]
+    fn reads_info_from_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tone.wav");
+        write_float(&path, 1, 22050, &[0.5; 2205]).unwrap();
+        let info = read_info(&path).unwrap();
+        assert_eq!((info.sample_rate, info.frames), (22050, 2205));
+        assert!((info.duration_secs() - 0.1).abs() < 1e-9);
+    }
+}